@@ -0,0 +1,20 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate urlencoding;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(data) = std::str::from_utf8(data) {
+        // None of these may panic, whatever the input
+        let _ = urlencoding::decode(data);
+        let mut out = Vec::new();
+        let _ = urlencoding::decode_to_vec(data, &mut out);
+        let mut bytes = data.as_bytes().to_vec();
+        let _ = urlencoding::decode_in_place(&mut bytes);
+        let mut string = data.to_string();
+        let _ = urlencoding::decode_in_place_str(&mut string);
+
+        // Whatever encode produces decodes back to the input
+        assert_eq!(data, urlencoding::decode(&urlencoding::encode(data)).unwrap());
+    }
+});
@@ -0,0 +1,62 @@
+use std::alloc::Allocator;
+use std::str;
+
+use super::{decode_iter, is_unreserved, FromUrlEncodingError};
+
+/// Percent-encodes `data` like `encode`, into a buffer allocated with
+/// `alloc`, such as a per-request arena.
+///
+/// `String` can't use a custom allocator, so the result is its bytes, which
+/// are always ASCII.
+pub fn encode_in<A: Allocator>(data: &str, alloc: A) -> Vec<u8, A> {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+    let mut escaped = Vec::with_capacity_in(data.len(), alloc);
+    for b in data.bytes() {
+        if is_unreserved(b) {
+            escaped.push(b);
+        } else {
+            escaped.extend_from_slice(&[b'%', HEX[(b >> 4) as usize], HEX[(b & 0xF) as usize]]);
+        }
+    }
+    escaped
+}
+
+/// Percent-decodes `data` like `decode`, into a buffer allocated with
+/// `alloc`, and checks the result is valid UTF-8.
+///
+/// Only the error for invalid UTF-8 uses the global allocator, as
+/// `FromUtf8Error` holds a copy of the decoded bytes.
+pub fn decode_in<A: Allocator>(data: &str, alloc: A) -> Result<Vec<u8, A>, FromUrlEncodingError> {
+    let mut decoded = Vec::with_capacity_in(data.len(), alloc);
+    for b in decode_iter(data) {
+        decoded.push(b?);
+    }
+    if str::from_utf8(&decoded).is_err() {
+        let error = String::from_utf8(decoded.to_vec()).unwrap_err();
+        return Err(FromUrlEncodingError::Utf8CharacterError { error });
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::alloc::Global;
+
+    use super::decode_in;
+    use super::encode_in;
+    use super::super::encode;
+
+    #[test]
+    fn it_encodes_in_allocator() {
+        let data = "👾 Exterminate!";
+        assert_eq!(encode(data).as_bytes(), &encode_in(data, Global)[..]);
+        assert_eq!(b"a%2Fb", &encode_in("a/b", &Global)[..]);
+    }
+
+    #[test]
+    fn it_decodes_in_allocator() {
+        assert_eq!("👾 Exterminate!".as_bytes(), &decode_in("%F0%9F%91%BE%20Exterminate%21", Global).unwrap()[..]);
+        assert!(decode_in("%FF", Global).is_err());
+        assert!(decode_in("%F", &Global).is_err());
+    }
+}
@@ -0,0 +1,592 @@
+//! Percent-encodes or decodes text from the command line.
+//!
+//! ```text
+//! urlencode [--decode] [--lines | --null] [--jobs N] [ESCAPING] [TEXT...]
+//! urlencode [--decode] [--file PATH] [--output PATH] [ESCAPING]
+//! urlencode normalize [--lines] [--jobs N] [STEPS] [URL...]
+//! ```
+//!
+//! Each `TEXT` argument is converted and printed on its own line. Without
+//! arguments the whole of stdin is converted, with one trailing newline
+//! ignored, or with `--lines` every line of stdin is converted on its own
+//! and written out as soon as it is read. A line that fails to decode is
+//! reported on stderr and left out, and the exit code is 1 if any did.
+//! `--null` works like `--lines` with NUL-terminated records instead, as
+//! written by `find -print0` and read by `xargs -0`, which are treated as
+//! raw bytes, so any file name goes through. It also ends the output of
+//! each `TEXT` with NUL.
+//!
+//! `--jobs N` converts the records of `--lines` and `--null` on `N` threads.
+//! They are read in batches and each batch is written out once all of it is
+//! converted, in the order it was read, so only the latency changes.
+//!
+//! `--file` and `--output` read the input from and write the output to a
+//! file instead, and treat data as raw bytes rather than UTF-8 text: the
+//! bytes of `--file` are encoded as they are, and decoded bytes are written
+//! out as they are, without a trailing newline.
+//!
+//! Text is encoded like `urlencoding::encode` unless one `ESCAPING` flag picks
+//! what to leave unencoded instead: `--component` for `encodeURIComponent`,
+//! `--path` for RFC 3986 paths, `--form` for `application/x-www-form-urlencoded`
+//! with spaces as `+`, which `--decode` then turns back into spaces, or
+//! `--set CHARS` for the unreserved characters plus `CHARS`.
+//!
+//! `normalize` normalizes URLs like `urlencoding::UrlNormalizer` instead,
+//! lowercasing the scheme and host, removing default ports and dot segments
+//! and decoding escapes of unreserved characters. The `STEPS` flags
+//! `--no-case`, `--no-ports`, `--no-dot-segments` and `--no-percent` turn
+//! each of these off. It needs the `uri` feature.
+//!
+//! Only the standard library is used, so this also runs on `wasm32-wasip1`,
+//! where `--file` and `--output` paths must be in a directory the runtime
+//! has given access to, and `--jobs` fails unless the runtime has threads.
+
+extern crate urlencoding;
+
+use std::borrow::Cow;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, Read, Write};
+use std::process;
+use std::str;
+use std::thread;
+
+use urlencoding::{AsciiSet, EncodeSet, FromUrlEncodingError};
+#[cfg(feature = "uri")]
+use urlencoding::UrlNormalizer;
+
+const USAGE: &str = "usage: urlencode [--decode] [--lines | --null] [--jobs N] [ESCAPING] [TEXT...]
+       urlencode [--decode] [--file PATH] [--output PATH] [ESCAPING]
+       urlencode normalize [--lines] [--jobs N] [STEPS] [URL...]
+escaping: --component | --form | --path | --set CHARS
+steps: --no-case | --no-ports | --no-dot-segments | --no-percent";
+
+// How many records each thread converts per batch with `--jobs`.
+const RECORDS_PER_JOB: usize = 1024;
+
+#[derive(Debug, Default, PartialEq)]
+enum Escaping {
+    #[default]
+    Unreserved,
+    Component,
+    Form,
+    Path,
+    Set(AsciiSet),
+}
+
+impl Escaping {
+    fn encode(&self, data: &[u8]) -> String {
+        match self {
+            Escaping::Unreserved => AsciiSet::unreserved().encode_bytes(data),
+            Escaping::Component => AsciiSet::component().encode_bytes(data),
+            // Spaces are the only source of "%20", as '%' itself is encoded
+            Escaping::Form => form_set().encode_bytes(data).replace("%20", "+"),
+            Escaping::Path => AsciiSet::path().encode_bytes(data),
+            Escaping::Set(set) => set.encode_bytes(data),
+        }
+    }
+
+    // Undoes what `encode` does besides percent-encoding.
+    fn unescape<'a>(&self, data: &'a str) -> Cow<'a, str> {
+        match self {
+            Escaping::Form if data.contains('+') => Cow::Owned(data.replace('+', " ")),
+            _ => Cow::Borrowed(data),
+        }
+    }
+}
+
+// The WHATWG `application/x-www-form-urlencoded` set, before spaces become
+// '+'.
+fn form_set() -> AsciiSet {
+    AsciiSet::unreserved().remove(b'~').add(b'*')
+}
+
+fn parse_set(chars: &str) -> Result<AsciiSet, String> {
+    if !chars.is_ascii() {
+        return Err("--set only takes ASCII characters".to_string());
+    }
+    Ok(chars.bytes().fold(AsciiSet::unreserved(), |set, b| set.add(b)))
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Options {
+    decode: bool,
+    lines: bool,
+    null: bool,
+    jobs: usize,
+    file: Option<String>,
+    output: Option<String>,
+    escaping: Escaping,
+    #[cfg(feature = "uri")]
+    normalizer: Option<UrlNormalizer>,
+    inputs: Vec<String>,
+}
+
+impl Options {
+    fn binary(&self) -> bool {
+        self.file.is_some() || self.output.is_some()
+    }
+
+    fn delimiter(&self) -> u8 {
+        if self.null {
+            b'\0'
+        } else {
+            b'\n'
+        }
+    }
+
+    fn record_name(&self) -> &'static str {
+        if self.null {
+            "record"
+        } else {
+            "line"
+        }
+    }
+
+    #[cfg(feature = "uri")]
+    fn normalize(&mut self) -> Result<(), String> {
+        self.normalizer = Some(UrlNormalizer::new());
+        Ok(())
+    }
+
+    #[cfg(not(feature = "uri"))]
+    fn normalize(&mut self) -> Result<(), String> {
+        Err("normalize needs the uri feature".to_string())
+    }
+
+    // Turns off the normalization step named by a `--no-*` flag.
+    #[cfg(feature = "uri")]
+    fn skip_step(&mut self, flag: &str) -> Result<(), String> {
+        let normalizer = self.normalizer.ok_or_else(|| format!("{} only works with normalize", flag))?;
+        self.normalizer = Some(match flag {
+            "--no-case" => normalizer.case(false),
+            "--no-ports" => normalizer.default_ports(false),
+            "--no-dot-segments" => normalizer.dot_segments(false),
+            _ => normalizer.percent_encoding(false),
+        });
+        Ok(())
+    }
+
+    #[cfg(not(feature = "uri"))]
+    fn skip_step(&mut self, flag: &str) -> Result<(), String> {
+        Err(format!("{} only works with normalize", flag))
+    }
+
+    #[cfg(feature = "uri")]
+    fn normalizes(&self) -> bool {
+        self.normalizer.is_some()
+    }
+
+    #[cfg(not(feature = "uri"))]
+    fn normalizes(&self) -> bool {
+        false
+    }
+}
+
+fn parse_args<I: Iterator<Item = String>>(args: I) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut escapings = 0;
+    let mut args = args.peekable();
+    if args.peek().map(String::as_str) == Some("normalize") {
+        args.next();
+        options.normalize()?;
+    }
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-d" | "--decode" => options.decode = true,
+            "-l" | "--lines" => options.lines = true,
+            "-0" | "--null" => options.null = true,
+            "-j" | "--jobs" => options.jobs = parse_jobs(&value(&arg, args.next())?)?,
+            "-f" | "--file" => options.file = Some(value(&arg, args.next())?),
+            "-o" | "--output" => options.output = Some(value(&arg, args.next())?),
+            "--component" | "--form" | "--path" | "--set" => {
+                escapings += 1;
+                options.escaping = match arg.as_str() {
+                    "--component" => Escaping::Component,
+                    "--form" => Escaping::Form,
+                    "--path" => Escaping::Path,
+                    _ => Escaping::Set(parse_set(&value(&arg, args.next())?)?),
+                };
+            },
+            "--no-case" | "--no-ports" | "--no-dot-segments" | "--no-percent" => options.skip_step(&arg)?,
+            "--" => options.inputs.extend(args.by_ref()),
+            _ if arg.starts_with('-') && arg.len() > 1 => return Err(format!("unknown option {}", arg)),
+            _ => options.inputs.push(arg),
+        }
+    }
+    if escapings > 1 {
+        return Err("only one of --component, --form, --path and --set can be given".to_string());
+    }
+    if options.normalizes() && (options.decode || options.null || options.binary() || escapings > 0) {
+        return Err("normalize can't be combined with --decode, --null, --file, --output or ESCAPING".to_string());
+    }
+    if options.jobs > 0 && !(options.lines || options.null) {
+        return Err("--jobs only works with --lines or --null".to_string());
+    }
+    if options.binary() && (options.lines || options.null || !options.inputs.is_empty()) {
+        return Err("--file and --output can't be combined with --lines, --null or TEXT".to_string());
+    }
+    Ok(options)
+}
+
+fn parse_jobs(jobs: &str) -> Result<usize, String> {
+    match jobs.parse() {
+        Ok(jobs) if jobs > 0 => Ok(jobs),
+        _ => Err(format!("--jobs needs a positive number, not {:?}", jobs)),
+    }
+}
+
+fn value(option: &str, value: Option<String>) -> Result<String, String> {
+    value.ok_or_else(|| format!("{} needs a value", option))
+}
+
+fn convert(options: &Options, data: &str) -> Result<String, String> {
+    #[cfg(feature = "uri")]
+    if let Some(normalizer) = &options.normalizer {
+        return Ok(normalizer.normalize(data));
+    }
+    if options.decode {
+        urlencoding::decode(&options.escaping.unescape(data)).map_err(|error| describe(&error))
+    } else {
+        Ok(options.escaping.encode(data.as_bytes()))
+    }
+}
+
+fn describe(error: &FromUrlEncodingError) -> String {
+    match error {
+        FromUrlEncodingError::UriCharacterError { character, index } => {
+            format!("invalid character {:?} at offset {}", character, index)
+        },
+        FromUrlEncodingError::Utf8CharacterError { error } => format!("decoded bytes are not UTF-8: {}", error),
+    }
+}
+
+// Converts each record of `input`, split on lines or on NUL bytes for
+// `--null`, into `output`, reporting failures to `errors`, and returns how
+// many records were read and how many failed. Without `--jobs` every record
+// is written out as soon as it is read, and otherwise once its batch is done.
+fn convert_records<R: BufRead, W: Write, E: Write>(
+    options: &Options,
+    mut input: R,
+    mut output: W,
+    mut errors: E,
+) -> io::Result<(usize, usize)> {
+    let delimiter = options.delimiter();
+    let batch_size = options.jobs * RECORDS_PER_JOB;
+    let (mut total, mut failed) = (0, 0);
+    let mut batch = Vec::new();
+    loop {
+        let mut record = Vec::new();
+        let read = input.read_until(delimiter, &mut record)?;
+        if read > 0 {
+            if record.last() == Some(&delimiter) {
+                record.pop();
+            }
+            batch.push(record);
+        }
+        if batch.len() >= batch_size || (read == 0 && !batch.is_empty()) {
+            for converted in convert_batch(options, &batch)? {
+                total += 1;
+                match converted {
+                    Ok(converted) => {
+                        output.write_all(&converted)?;
+                        output.write_all(&[delimiter])?;
+                    },
+                    Err(message) => {
+                        failed += 1;
+                        writeln!(errors, "urlencode: {} {}: {}", options.record_name(), total, message)?;
+                    },
+                }
+            }
+            output.flush()?;
+            batch.clear();
+        }
+        if read == 0 {
+            return Ok((total, failed));
+        }
+    }
+}
+
+// Converts `records` in order, splitting them between `--jobs` threads.
+fn convert_batch(options: &Options, records: &[Vec<u8>]) -> io::Result<Vec<Result<Vec<u8>, String>>> {
+    let convert_all = |records: &[Vec<u8>]| records.iter().map(|record| convert_record(options, record)).collect::<Vec<_>>();
+    if options.jobs < 2 || records.len() < 2 {
+        return Ok(convert_all(records));
+    }
+    thread::scope(|scope| {
+        let threads = records
+            .chunks(records.len().div_ceil(options.jobs))
+            .map(|chunk| thread::Builder::new().spawn_scoped(scope, move || convert_all(chunk)))
+            .collect::<io::Result<Vec<_>>>()?;
+        Ok(threads.into_iter().flat_map(|thread| thread.join().expect("conversion thread panicked")).collect())
+    })
+}
+
+fn convert_record(options: &Options, record: &[u8]) -> Result<Vec<u8>, String> {
+    if options.null {
+        return convert_raw(options, record);
+    }
+    let line = record.strip_suffix(b"\r").unwrap_or(record);
+    str::from_utf8(line)
+        .map_err(|_| "line is not UTF-8 text".to_string())
+        .and_then(|line| convert(options, line))
+        .map(String::into_bytes)
+}
+
+// Converts raw bytes for `--file` and `--output`, ignoring one trailing
+// newline of encoded input.
+fn convert_bytes(options: &Options, data: &[u8]) -> Result<Vec<u8>, String> {
+    if !options.decode {
+        return convert_raw(options, data);
+    }
+    let data = data.strip_suffix(b"\n").unwrap_or(data);
+    convert_raw(options, data.strip_suffix(b"\r").unwrap_or(data))
+}
+
+fn convert_raw(options: &Options, data: &[u8]) -> Result<Vec<u8>, String> {
+    if !options.decode {
+        return Ok(options.escaping.encode(data).into_bytes());
+    }
+    let data = str::from_utf8(data).map_err(|_| "encoded input is not UTF-8 text".to_string())?;
+    let mut decoded = Vec::new();
+    urlencoding::decode_to_vec(&options.escaping.unescape(data), &mut decoded).map_err(|error| describe(&error))?;
+    Ok(decoded)
+}
+
+fn run_binary(options: &Options) -> io::Result<bool> {
+    let data = match &options.file {
+        Some(path) => fs::read(path)?,
+        None => {
+            let mut data = Vec::new();
+            io::stdin().lock().read_to_end(&mut data)?;
+            data
+        },
+    };
+    let converted = match convert_bytes(options, &data) {
+        Ok(converted) => converted,
+        Err(message) => {
+            eprintln!("urlencode: {}", message);
+            return Ok(false);
+        },
+    };
+    match &options.output {
+        Some(path) => File::create(path)?.write_all(&converted)?,
+        None => {
+            let stdout = io::stdout();
+            let mut stdout = stdout.lock();
+            stdout.write_all(&converted)?;
+            if !options.decode {
+                stdout.write_all(b"\n")?;
+            }
+        },
+    }
+    Ok(true)
+}
+
+fn run(options: &Options) -> io::Result<bool> {
+    if options.binary() {
+        return run_binary(options);
+    }
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    if !options.inputs.is_empty() {
+        let mut ok = true;
+        for input in &options.inputs {
+            match convert(options, input) {
+                Ok(converted) => {
+                    stdout.write_all(converted.as_bytes())?;
+                    stdout.write_all(&[options.delimiter()])?;
+                },
+                Err(message) => {
+                    eprintln!("urlencode: {}", message);
+                    ok = false;
+                },
+            }
+        }
+        return Ok(ok);
+    }
+
+    let stdin = io::stdin();
+    if options.lines || options.null {
+        let (total, failed) = convert_records(options, stdin.lock(), &mut stdout, io::stderr())?;
+        if failed > 0 {
+            eprintln!("urlencode: {} of {} {}s failed", failed, total, options.record_name());
+        }
+        return Ok(failed == 0);
+    }
+
+    let mut input = String::new();
+    stdin.lock().read_to_string(&mut input)?;
+    let input = input.strip_suffix('\n').unwrap_or(&input);
+    match convert(options, input.strip_suffix('\r').unwrap_or(input)) {
+        Ok(converted) => writeln!(stdout, "{}", converted)?,
+        Err(message) => {
+            eprintln!("urlencode: {}", message);
+            return Ok(false);
+        },
+    }
+    Ok(true)
+}
+
+fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("urlencode: {}\n{}", message, USAGE);
+            process::exit(2);
+        },
+    };
+    match run(&options) {
+        Ok(true) => {},
+        Ok(false) => process::exit(1),
+        Err(error) => {
+            eprintln!("urlencode: {}", error);
+            process::exit(1);
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::convert_bytes;
+    use super::convert;
+    use super::convert_records;
+    use super::parse_args;
+    use super::Escaping;
+    use super::Options;
+    use urlencoding::AsciiSet;
+
+    fn args(args: &[&str]) -> impl Iterator<Item = String> {
+        args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>().into_iter()
+    }
+
+    #[test]
+    fn it_parses_args() {
+        let options = parse_args(args(&["--decode", "-l", "a", "--", "--b"])).unwrap();
+        assert!(options.decode && options.lines);
+        assert_eq!(vec!["a".to_string(), "--b".to_string()], options.inputs);
+        assert!(parse_args(args(&["--nope"])).is_err());
+
+        let options = parse_args(args(&["-d", "--file", "in.txt", "-o", "out.bin"])).unwrap();
+        assert_eq!(Some("in.txt".to_string()), options.file);
+        assert_eq!(Some("out.bin".to_string()), options.output);
+        assert!(parse_args(args(&["--file"])).is_err());
+        assert!(parse_args(args(&["--file", "in.txt", "--lines"])).is_err());
+        assert!(parse_args(args(&["--output", "out.bin", "text"])).is_err());
+        assert!(parse_args(args(&["-0"])).unwrap().null);
+        assert!(parse_args(args(&["--null", "--file", "in.txt"])).is_err());
+        assert_eq!(4, parse_args(args(&["-l", "--jobs", "4"])).unwrap().jobs);
+        assert!(parse_args(args(&["-l", "-j", "0"])).is_err());
+        assert!(parse_args(args(&["-l", "-j", "x"])).is_err());
+        assert!(parse_args(args(&["-j", "4", "text"])).is_err());
+    }
+
+    #[test]
+    fn it_parses_escaping_args() {
+        assert_eq!(Escaping::Unreserved, parse_args(args(&[])).unwrap().escaping);
+        assert_eq!(Escaping::Form, parse_args(args(&["--form"])).unwrap().escaping);
+        let set = AsciiSet::unreserved().add(b'/').add(b':');
+        assert_eq!(Escaping::Set(set), parse_args(args(&["--set", "/:"])).unwrap().escaping);
+        assert!(parse_args(args(&["--set"])).is_err());
+        assert!(parse_args(args(&["--set", "é"])).is_err());
+        assert!(parse_args(args(&["--path", "--form"])).is_err());
+    }
+
+    #[test]
+    fn it_converts_with_escaping() {
+        let with = |escaping, decode| Options { escaping, decode, ..Options::default() };
+        let data = "a b/c?d=e*f~'";
+        assert_eq!("a%20b%2Fc%3Fd%3De%2Af~%27", convert(&with(Escaping::Unreserved, false), data).unwrap());
+        assert_eq!("a%20b%2Fc%3Fd%3De*f~'", convert(&with(Escaping::Component, false), data).unwrap());
+        assert_eq!("a+b%2Fc%3Fd%3De*f%7E%27", convert(&with(Escaping::Form, false), data).unwrap());
+        assert_eq!("a%20b/c%3Fd=e*f~'", convert(&with(Escaping::Path, false), data).unwrap());
+        let set = AsciiSet::unreserved().add(b'?');
+        assert_eq!("a%20b%2Fc?d%3De%2Af~%27", convert(&with(Escaping::Set(set), false), data).unwrap());
+
+        assert_eq!("a b+c", convert(&with(Escaping::Form, true), "a+b%2Bc").unwrap());
+        assert_eq!("a+b+c", convert(&with(Escaping::Path, true), "a+b%2Bc").unwrap());
+    }
+
+    #[test]
+    fn it_converts_binary_data() {
+        let encode = Options { file: Some("image.png".to_string()), ..Options::default() };
+        assert_eq!(b"%89PNG%0D%0A%1A%0A%00".to_vec(), convert_bytes(&encode, b"\x89PNG\r\n\x1a\n\0").unwrap());
+
+        let decode = Options { decode: true, ..encode };
+        assert_eq!(b"\x89PNG\r\n\x1a\n\0".to_vec(), convert_bytes(&decode, b"%89PNG%0D%0A%1A%0A%00\n").unwrap());
+        assert_eq!(b"\xff".to_vec(), convert_bytes(&decode, b"%FF").unwrap());
+        assert!(convert_bytes(&decode, b"%F").is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "uri")]
+    fn it_normalizes_urls() {
+        let url = "HTTP://Example.com:80/a/./b/%7e";
+        let options = parse_args(args(&["normalize", url])).unwrap();
+        assert_eq!("http://example.com/a/b/~", convert(&options, url).unwrap());
+        let options = parse_args(args(&["normalize", "--no-ports", "--no-dot-segments", "-l"])).unwrap();
+        assert!(options.lines);
+        assert_eq!("http://example.com:80/a/./b/~", convert(&options, url).unwrap());
+        let options = parse_args(args(&["normalize", "--no-case", "--no-percent"])).unwrap();
+        assert_eq!("HTTP://Example.com/a/b/%7e", convert(&options, url).unwrap());
+
+        assert!(parse_args(args(&["--no-case"])).is_err());
+        assert!(parse_args(args(&["normalize", "--decode"])).is_err());
+        assert!(parse_args(args(&["normalize", "--path"])).is_err());
+        assert_eq!(vec!["a".to_string(), "normalize".to_string()], parse_args(args(&["a", "normalize"])).unwrap().inputs);
+    }
+
+    #[test]
+    fn it_converts_lines_independently() {
+        let options = Options { decode: true, lines: true, ..Options::default() };
+        let mut output = Vec::new();
+        let mut errors = Vec::new();
+        let counts = convert_records(&options, &b"a%20b\n%zz\r\n\n%F0%9F%91%BE\r\n"[..], &mut output, &mut errors).unwrap();
+        assert_eq!((4, 1), counts);
+        assert_eq!("a b\n\n👾\n", String::from_utf8(output).unwrap());
+        assert_eq!("urlencode: line 2: invalid character 'z' at offset 1\n", String::from_utf8(errors).unwrap());
+    }
+
+    #[test]
+    fn it_encodes_lines() {
+        let options = Options { lines: true, ..Options::default() };
+        let mut output = Vec::new();
+        let counts = convert_records(&options, &b"a b\nc/d"[..], &mut output, Vec::new()).unwrap();
+        assert_eq!((2, 0), counts);
+        assert_eq!("a%20b\nc%2Fd\n", String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn it_converts_lines_in_parallel_in_order() {
+        let input = (0..10_000).map(|i| if i % 1000 == 999 { "%zz\n".to_string() } else { format!("{}%20%2F\n", i) }).collect::<String>();
+        let convert_with = |jobs| {
+            let options = Options { decode: true, lines: true, jobs, ..Options::default() };
+            let mut output = Vec::new();
+            let mut errors = Vec::new();
+            let counts = convert_records(&options, input.as_bytes(), &mut output, &mut errors).unwrap();
+            (counts, String::from_utf8(output).unwrap(), String::from_utf8(errors).unwrap())
+        };
+        let (counts, output, errors) = convert_with(3);
+        assert_eq!((10_000, 10), counts);
+        assert!(output.starts_with("0 /\n1 /\n"));
+        assert!(errors.ends_with("urlencode: line 10000: invalid character 'z' at offset 1\n"));
+        assert_eq!(convert_with(0), (counts, output, errors));
+    }
+
+    #[test]
+    fn it_converts_null_terminated_records() {
+        let options = Options { null: true, ..Options::default() };
+        let mut output = Vec::new();
+        let counts = convert_records(&options, &b"./a\nb\0./\xff c\0"[..], &mut output, Vec::new()).unwrap();
+        assert_eq!((2, 0), counts);
+        assert_eq!(b".%2Fa%0Ab\0.%2F%FF%20c\0".to_vec(), output);
+
+        let options = Options { decode: true, ..options };
+        let mut decoded = Vec::new();
+        let mut errors = Vec::new();
+        let counts = convert_records(&options, &b".%2Fa%0Ab\0%zz\0.%2F%FF%20c"[..], &mut decoded, &mut errors).unwrap();
+        assert_eq!((3, 1), counts);
+        assert_eq!(b"./a\nb\0./\xff c\0".to_vec(), decoded);
+        assert_eq!("urlencode: record 2: invalid character 'z' at offset 1\n", String::from_utf8(errors).unwrap());
+    }
+}
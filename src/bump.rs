@@ -0,0 +1,57 @@
+use std::str;
+
+use bumpalo::collections::{String as BumpString, Vec as BumpVec};
+use bumpalo::Bump;
+
+use super::{decode_iter, FromUrlEncodingError};
+use display::encode_pieces;
+
+/// Percent-encodes `data` like `encode`, into a string allocated in `bump`,
+/// so nothing goes through the global allocator.
+pub fn encode_in_bump<'b>(data: &str, bump: &'b Bump) -> &'b str {
+    let mut escaped = BumpString::with_capacity_in(data.len(), bump);
+    for piece in encode_pieces(data) {
+        escaped.push_str(piece);
+    }
+    escaped.into_bump_str()
+}
+
+/// Percent-decodes `data` like `decode`, into a string allocated in `bump`.
+///
+/// Only the error for invalid UTF-8 uses the global allocator, as
+/// `FromUtf8Error` holds a copy of the decoded bytes.
+pub fn decode_in_bump<'b>(data: &str, bump: &'b Bump) -> Result<&'b str, FromUrlEncodingError> {
+    let mut decoded = BumpVec::with_capacity_in(data.len(), bump);
+    for b in decode_iter(data) {
+        decoded.push(b?);
+    }
+    let decoded = decoded.into_bump_slice();
+    str::from_utf8(decoded).map_err(|_| FromUrlEncodingError::Utf8CharacterError {
+        error: String::from_utf8(decoded.to_vec()).unwrap_err(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use super::decode_in_bump;
+    use super::encode_in_bump;
+
+    #[test]
+    fn it_encodes_in_bump() {
+        let bump = Bump::new();
+        let encoded = encode_in_bump("👾 Exterminate!", &bump);
+        assert_eq!("%F0%9F%91%BE%20Exterminate%21", encoded);
+        assert_eq!("", encode_in_bump("", &bump));
+        assert!(bump.allocated_bytes() > 0);
+    }
+
+    #[test]
+    fn it_decodes_in_bump() {
+        let bump = Bump::new();
+        assert_eq!("👾 Exterminate!", decode_in_bump("%F0%9F%91%BE%20Exterminate%21", &bump).unwrap());
+        assert!(decode_in_bump("%FF", &bump).is_err());
+        assert!(decode_in_bump("%F", &bump).is_err());
+    }
+}
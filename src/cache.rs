@@ -0,0 +1,213 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use super::{decode, encode, FromUrlEncodingError};
+
+/// Remembers the encoded forms of the strings it has encoded, for services
+/// that encode the same few strings, such as header names or enum-like
+/// parameter values, over and over.
+///
+/// At most `capacity` strings are kept. Once it is full, other strings are
+/// still encoded, just not remembered, so the first strings seen are the
+/// ones that stay cached.
+///
+/// ```
+/// use urlencoding::EncodeCache;
+///
+/// let mut cache = EncodeCache::new(64);
+/// assert_eq!("en%20US", cache.encode("en US"));
+/// assert_eq!("en%20US", cache.encode("en US"));
+/// assert_eq!(1, cache.len());
+/// ```
+#[derive(Debug, Clone)]
+pub struct EncodeCache {
+    capacity: usize,
+    entries: HashMap<String, String>,
+}
+
+impl EncodeCache {
+    /// Creates an empty cache that keeps at most `capacity` strings.
+    pub fn new(capacity: usize) -> EncodeCache {
+        EncodeCache {
+            capacity,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Percent-encodes `data` like `encode`, borrowing the result from the
+    /// cache when it can.
+    pub fn encode(&mut self, data: &str) -> Cow<'_, str> {
+        if !self.entries.contains_key(data) {
+            if self.entries.len() >= self.capacity {
+                return Cow::Owned(encode(data));
+            }
+            self.entries.insert(data.to_string(), encode(data));
+        }
+        Cow::Borrowed(&self.entries[data])
+    }
+
+    /// How many strings are cached.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Forgets every cached string.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Remembers the decoded forms of the most recently decoded strings, for
+/// servers that see the same encoded parameter names on every request.
+///
+/// At most `capacity` strings are kept, and the least recently used one is
+/// dropped to make room for a new one. Dropping one scans the whole cache,
+/// so `capacity` should stay in the hundreds. Input that fails to decode is
+/// never cached. `hits` and `misses` tell whether the cache is worth it:
+///
+/// ```
+/// use urlencoding::DecodeCache;
+///
+/// let mut cache = DecodeCache::new(64);
+/// for _ in 0..3 {
+///     assert_eq!("user id", cache.decode("user%20id").unwrap());
+/// }
+/// assert_eq!((2, 1), (cache.hits(), cache.misses()));
+/// ```
+#[derive(Debug, Clone)]
+pub struct DecodeCache {
+    capacity: usize,
+    // The decoded form and when it was last used
+    entries: HashMap<String, (String, u64)>,
+    clock: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl DecodeCache {
+    /// Creates an empty cache that keeps at most `capacity` strings. The
+    /// decoded string `decode` borrows has to be kept somewhere, so it always
+    /// keeps at least one.
+    pub fn new(capacity: usize) -> DecodeCache {
+        DecodeCache {
+            capacity: capacity.max(1),
+            entries: HashMap::new(),
+            clock: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Percent-decodes `data` like `decode`, borrowing the result from the
+    /// cache.
+    pub fn decode(&mut self, data: &str) -> Result<&str, FromUrlEncodingError> {
+        self.clock += 1;
+        if self.entries.contains_key(data) {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+            let decoded = decode(data)?;
+            if self.entries.len() >= self.capacity {
+                let oldest = self.entries.iter().min_by_key(|(_, &(_, used))| used).map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    self.entries.remove(&oldest);
+                }
+            }
+            self.entries.insert(data.to_string(), (decoded, 0));
+        }
+        let entry = self.entries.get_mut(data).expect("data is cached by now");
+        entry.1 = self.clock;
+        Ok(&entry.0)
+    }
+
+    /// How many calls to `decode` were answered from the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// How many calls to `decode` had to decode, including those that failed.
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// How many strings are cached.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Forgets every cached string. The counters are kept.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::DecodeCache;
+    use super::EncodeCache;
+
+    #[test]
+    fn it_encodes_with_cache() {
+        let mut cache = EncodeCache::new(2);
+        assert_eq!("a%20b", cache.encode("a b"));
+        assert!(matches!(cache.encode("a b"), Cow::Borrowed("a%20b")));
+        assert_eq!("c%2Fd", cache.encode("c/d"));
+        assert_eq!(2, cache.len());
+    }
+
+    #[test]
+    fn it_stops_caching_when_full() {
+        let mut cache = EncodeCache::new(1);
+        assert_eq!("a%20b", cache.encode("a b"));
+        assert!(matches!(cache.encode("c/d"), Cow::Owned(ref encoded) if encoded == "c%2Fd"));
+        assert!(matches!(cache.encode("a b"), Cow::Borrowed(_)));
+        assert_eq!(1, cache.len());
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert!(matches!(cache.encode("c/d"), Cow::Borrowed("c%2Fd")));
+    }
+
+    #[test]
+    fn it_decodes_with_cache() {
+        let mut cache = DecodeCache::new(2);
+        assert_eq!("a b", cache.decode("a%20b").unwrap());
+        assert_eq!("a b", cache.decode("a%20b").unwrap());
+        assert_eq!("c/d", cache.decode("c%2Fd").unwrap());
+        assert!(cache.decode("%zz").is_err());
+        assert_eq!((1, 3), (cache.hits(), cache.misses()));
+        assert_eq!(2, cache.len());
+    }
+
+    #[test]
+    fn it_evicts_least_recently_used() {
+        let mut cache = DecodeCache::new(2);
+        cache.decode("a").unwrap();
+        cache.decode("b").unwrap();
+        cache.decode("a").unwrap();
+        // "b" is the least recently used
+        cache.decode("c").unwrap();
+        assert_eq!(2, cache.len());
+        cache.decode("a").unwrap();
+        assert_eq!((2, 3), (cache.hits(), cache.misses()));
+        cache.decode("b").unwrap();
+        assert_eq!((2, 4), (cache.hits(), cache.misses()));
+
+        let mut cache = DecodeCache::new(0);
+        assert_eq!("x", cache.decode("x").unwrap());
+        assert_eq!("y", cache.decode("y").unwrap());
+        assert_eq!(1, cache.len());
+    }
+}
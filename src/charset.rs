@@ -0,0 +1,74 @@
+use encoding_rs::Encoding;
+
+use super::{decode_bytes, encode_bytes, FromUrlEncodingError};
+
+/// Decodes percent-encoded bytes in a legacy charset, such as the
+/// Windows-1252 or Shift_JIS bytes submitted by forms on non-UTF-8 pages.
+///
+/// Byte sequences that aren't valid in `encoding` are replaced with U+FFFD,
+/// the same way browsers decode them.
+pub fn decode_with_charset(data: &str, encoding: &'static Encoding) -> Result<String, FromUrlEncodingError> {
+    let bytes = decode_bytes(data)?;
+    let (decoded, _) = encoding.decode_without_bom_handling(&bytes);
+    Ok(decoded.into_owned())
+}
+
+/// Transcodes `data` to a legacy charset and percent-encodes the resulting
+/// bytes, for servers that expect forms in a specific charset.
+///
+/// As browsers do, characters that can't be represented in `encoding` are
+/// written as HTML numeric character references (`&#8364;`) before encoding.
+/// UTF-16 encodings aren't used for output and fall back to UTF-8.
+pub fn encode_with_charset(data: &str, encoding: &'static Encoding) -> String {
+    let (bytes, _, _) = encoding.encode(data);
+    encode_bytes(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use encoding_rs::{SHIFT_JIS, UTF_8, WINDOWS_1252};
+
+    use super::decode_with_charset;
+    use super::encode_with_charset;
+
+    #[test]
+    fn it_decodes_with_charset_windows_1252() {
+        assert_eq!("café €5", decode_with_charset("caf%E9%20%805", WINDOWS_1252).unwrap());
+    }
+
+    #[test]
+    fn it_decodes_with_charset_shift_jis() {
+        assert_eq!("日本", decode_with_charset("%93%FA%96%7B", SHIFT_JIS).unwrap());
+    }
+
+    #[test]
+    fn it_decodes_with_charset_replacing_malformed_bytes() {
+        assert_eq!("a\u{FFFD}b", decode_with_charset("a%E9b", UTF_8).unwrap());
+    }
+
+    #[test]
+    fn it_decodes_with_charset_unsuccessfully() {
+        assert!(decode_with_charset("caf%E", WINDOWS_1252).is_err());
+    }
+
+    #[test]
+    fn it_encodes_with_charset_windows_1252() {
+        assert_eq!("caf%E9%20%805", encode_with_charset("café €5", WINDOWS_1252));
+    }
+
+    #[test]
+    fn it_encodes_with_charset_shift_jis() {
+        assert_eq!("%93%FA%96%7B", encode_with_charset("日本", SHIFT_JIS));
+    }
+
+    #[test]
+    fn it_encodes_with_charset_unmappable_characters() {
+        assert_eq!("%26%2328450%3B", encode_with_charset("漢", WINDOWS_1252));
+    }
+
+    #[test]
+    fn it_round_trips_with_charset() {
+        let data = "naïve façade";
+        assert_eq!(data, decode_with_charset(&encode_with_charset(data, WINDOWS_1252), WINDOWS_1252).unwrap());
+    }
+}
@@ -0,0 +1,120 @@
+use super::{decode, AsciiSet, EncodeSet, FromUrlEncodingError};
+
+impl AsciiSet {
+    /// The characters left unencoded in an Amazon S3 object key: the
+    /// unreserved characters and `/`.
+    pub const fn s3_key() -> AsciiSet {
+        AsciiSet::unreserved().add(b'/')
+    }
+
+    /// The characters left unencoded in a Google Cloud Storage object name
+    /// in a JSON API URL: only the unreserved characters. The name is a
+    /// single path segment there, so `/` is encoded too.
+    pub const fn gcs_object_name() -> AsciiSet {
+        AsciiSet::unreserved()
+    }
+
+    /// The characters left unencoded in an Azure Blob Storage blob name:
+    /// the unreserved characters and `/`, which separates virtual
+    /// directories.
+    pub const fn azure_blob_name() -> AsciiSet {
+        AsciiSet::unreserved().add(b'/')
+    }
+}
+
+/// Percent-encodes an Amazon S3 object key for an object URL or the
+/// canonical URI of an AWS Signature Version 4 request.
+///
+/// Every byte but the unreserved characters and `/` is encoded, so a space
+/// becomes `%20` and `+` becomes `%2B`, which is what S3 signs. A `+` left
+/// unencoded would be read back as a space by S3 and the signature would
+/// not match.
+///
+/// ```
+/// use urlencoding::encode_s3_key;
+///
+/// assert_eq!("photos/2024/a%20b%2Bc%3D.jpg", encode_s3_key("photos/2024/a b+c=.jpg"));
+/// ```
+pub fn encode_s3_key(key: &str) -> String {
+    AsciiSet::s3_key().encode(key)
+}
+
+/// Percent-encodes a Google Cloud Storage object name for the JSON API, as
+/// in `https://storage.googleapis.com/storage/v1/b/{bucket}/o/{object}`.
+///
+/// Google requires the reserved characters `!#$&'()*+,/:;=?@[]` and the
+/// space to be encoded, and since the object name is one path segment, a
+/// `/` in it is encoded as `%2F`. The XML API takes the name as a path
+/// instead, with `/` left alone, like `encode_s3_key`.
+///
+/// ```
+/// use urlencoding::encode_gcs_object_name;
+///
+/// assert_eq!("logs%2F2024%2Fa%20b%2B.txt", encode_gcs_object_name("logs/2024/a b+.txt"));
+/// ```
+pub fn encode_gcs_object_name(name: &str) -> String {
+    AsciiSet::gcs_object_name().encode(name)
+}
+
+/// Percent-encodes an Azure Blob Storage blob name for a blob URL, as in
+/// `https://{account}.blob.core.windows.net/{container}/{blob}`, and for
+/// the canonicalized resource of a Shared Key signature.
+///
+/// The reserved URL characters must be encoded, while `/` is left alone:
+/// it splits the name into virtual directories, so `a/b` and `a%2Fb` are
+/// the same blob.
+///
+/// ```
+/// use urlencoding::encode_azure_blob_name;
+///
+/// assert_eq!("reports/q1%20%232.pdf", encode_azure_blob_name("reports/q1 #2.pdf"));
+/// ```
+pub fn encode_azure_blob_name(name: &str) -> String {
+    AsciiSet::azure_blob_name().encode(name)
+}
+
+/// Decodes an object key from an Amazon S3 event notification, which
+/// encodes keys like a form, with `+` for a space and `%2B` for `+`.
+///
+/// ```
+/// use urlencoding::decode_s3_event_key;
+///
+/// assert_eq!("photos/a b+c.jpg", decode_s3_event_key("photos/a+b%2Bc.jpg").unwrap());
+/// ```
+pub fn decode_s3_event_key(key: &str) -> Result<String, FromUrlEncodingError> {
+    decode(&key.replace('+', " "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::decode_s3_event_key;
+    use super::encode_azure_blob_name;
+    use super::encode_gcs_object_name;
+    use super::encode_s3_key;
+
+    #[test]
+    fn it_encodes_s3_keys() {
+        assert_eq!("a/b/c-d_e.f~g", encode_s3_key("a/b/c-d_e.f~g"));
+        assert_eq!("%21%24%26%27%28%29%2A%2C%3A%3B%40%3F%23%25", encode_s3_key("!$&'()*,:;@?#%"));
+        assert_eq!("%C3%A9t%C3%A9//x", encode_s3_key("été//x"));
+    }
+
+    #[test]
+    fn it_decodes_s3_event_keys() {
+        assert_eq!("a b", decode_s3_event_key("a%20b").unwrap());
+        assert_eq!("été/x y", decode_s3_event_key("%C3%A9t%C3%A9/x+y").unwrap());
+        assert!(decode_s3_event_key("a%2").is_err());
+    }
+
+    #[test]
+    fn it_encodes_gcs_object_names() {
+        assert_eq!("a-b_c.d~e", encode_gcs_object_name("a-b_c.d~e"));
+        assert_eq!("%21%23%24%26%27%28%29%2A%2B%2C%2F%3A%3B%3D%3F%40%5B%5D%20", encode_gcs_object_name("!#$&'()*+,/:;=?@[] "));
+    }
+
+    #[test]
+    fn it_encodes_azure_blob_names() {
+        assert_eq!("dir/sub/file.txt", encode_azure_blob_name("dir/sub/file.txt"));
+        assert_eq!("%E6%97%A5%E6%9C%AC/a%3Fb%25c%5C", encode_azure_blob_name("日本/a?b%c\\"));
+    }
+}
@@ -0,0 +1,57 @@
+use super::{decode, encode_bytes_with, FromUrlEncodingError};
+
+/// Percent-encodes a cookie value (RFC 6265, section 4.1.1).
+///
+/// Every `cookie-octet` is left alone except `%`, which is encoded so that
+/// `decode_cookie_value` can always recover the original value. Spaces,
+/// double quotes, `,`, `;`, `\` and control or non-ASCII bytes are encoded.
+pub fn encode_cookie_value(data: &str) -> String {
+    encode_bytes_with(data.as_bytes(), |b| match b {
+        // cookie-octet, minus '%'
+        0x21 | 0x23..=0x24 | 0x26..=0x2B | 0x2D..=0x3A | 0x3C..=0x5B | 0x5D..=0x7E => true,
+        _ => false,
+    })
+}
+
+/// Decodes a cookie value produced by `encode_cookie_value`.
+///
+/// A value wrapped in double quotes, as allowed by RFC 6265, is unwrapped
+/// before decoding. Unlike form decoding, `+` is kept as-is.
+pub fn decode_cookie_value(data: &str) -> Result<String, FromUrlEncodingError> {
+    let unquoted = if data.len() >= 2 && data.starts_with('"') && data.ends_with('"') {
+        &data[1..data.len() - 1]
+    } else {
+        data
+    };
+    decode(unquoted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::decode_cookie_value;
+    use super::encode_cookie_value;
+
+    #[test]
+    fn it_encodes_cookie_value_successfully() {
+        assert_eq!("a%20b%3Bc%2Cd%22e%5Cf", encode_cookie_value("a b;c,d\"e\\f"));
+        assert_eq!("50%25%E2%82%AC", encode_cookie_value("50%€"));
+    }
+
+    #[test]
+    fn it_encodes_cookie_value_octets_unchanged() {
+        let octets = "!#$&'()*+-./09:<=>?@AZ[]^_`az{|}~";
+        assert_eq!(octets, encode_cookie_value(octets));
+    }
+
+    #[test]
+    fn it_decodes_cookie_value_successfully() {
+        assert_eq!("a b;c", decode_cookie_value("a%20b%3Bc").unwrap());
+        assert_eq!("a+b", decode_cookie_value("\"a+b\"").unwrap());
+    }
+
+    #[test]
+    fn it_round_trips_cookie_value() {
+        let value = "session=\"x y\"; 100% €";
+        assert_eq!(value, decode_cookie_value(&encode_cookie_value(value)).unwrap());
+    }
+}
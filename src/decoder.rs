@@ -0,0 +1,469 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::str;
+
+use super::{decode_bytes, decode_triplet, hex_value, FromUrlEncodingError};
+
+/// A percent-decoder whose handling of malformed input can be configured.
+///
+/// By default it fails like `decode`. Malformed percent-encoding can instead
+/// be recovered from with an `ErrorRecovery` strategy, and decoded bytes that
+/// aren't valid UTF-8 can be replaced: each invalid sequence with a fixed
+/// string, or with whatever a callback returns for the bad bytes, so corrupt
+/// data can be told apart from genuine `U+FFFD` characters in the input:
+///
+/// ```
+/// use urlencoding::Decoder;
+///
+/// let decoder = Decoder::new().replace_invalid_utf8("<?>");
+/// assert_eq!("a<?>b", decoder.decode("a%FFb").unwrap());
+///
+/// let decoder = Decoder::new().replace_invalid_utf8_with(|bytes| format!("[{:02X?}]", bytes));
+/// assert_eq!("a[[FF]]b", decoder.decode("a%FFb").unwrap());
+/// ```
+#[derive(Default)]
+pub struct Decoder {
+    invalid_utf8: InvalidUtf8,
+    recovery: ErrorRecovery,
+    percent_u: bool,
+}
+
+/// What a `Decoder` does with a `%` that isn't followed by 2 hex digits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ErrorRecovery {
+    /// Fails with `FromUrlEncodingError::UriCharacterError`, like `decode`.
+    #[default]
+    Strict,
+    /// Replaces the `%` with `U+FFFD`, keeping the characters after it.
+    ReplaceInvalid,
+    /// Keeps the `%` as-is, as browsers do.
+    PassThrough,
+    /// Stops decoding, returning what was decoded before the `%`.
+    Truncate,
+}
+
+/// Whether URLs are handled strictly by RFC 3986, to agree with servers, or
+/// the forgiving way the WHATWG URL standard describes, to agree with
+/// browsers.
+///
+/// `Decoder::parse_mode` and `UriParts::parse_with` take a mode, and with the
+/// `uri` feature `ParseMode::normalize` cleans up a whole URL for it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ParseMode {
+    /// Malformed input is an error and is never rewritten.
+    #[default]
+    Strict,
+    /// Malformed input is kept or replaced, the way browsers do.
+    Browser,
+}
+
+// Returns the replacement for a sequence of invalid UTF-8 bytes.
+type ReplaceFn = dyn Fn(&[u8]) -> String + Send + Sync;
+
+#[derive(Default)]
+enum InvalidUtf8 {
+    #[default]
+    Fail,
+    Replace(String),
+    Callback(Box<ReplaceFn>),
+}
+
+impl Decoder {
+    /// Creates a decoder that fails on invalid UTF-8, like `decode`.
+    pub fn new() -> Decoder {
+        Decoder::default()
+    }
+
+    /// Replaces each invalid UTF-8 sequence with `U+FFFD`, like
+    /// `String::from_utf8_lossy`.
+    pub fn lossy(self) -> Decoder {
+        self.replace_invalid_utf8("\u{FFFD}")
+    }
+
+    /// Replaces each invalid UTF-8 sequence with `replacement`.
+    pub fn replace_invalid_utf8(mut self, replacement: &str) -> Decoder {
+        self.invalid_utf8 = InvalidUtf8::Replace(replacement.to_string());
+        self
+    }
+
+    /// Replaces each invalid UTF-8 sequence with the string `replace` returns
+    /// for its bytes.
+    pub fn replace_invalid_utf8_with<F>(mut self, replace: F) -> Decoder
+    where
+        F: Fn(&[u8]) -> String + Send + Sync + 'static,
+    {
+        self.invalid_utf8 = InvalidUtf8::Callback(Box::new(replace));
+        self
+    }
+
+    /// Recovers from malformed percent-encoding with `recovery` instead of
+    /// failing.
+    pub fn error_recovery(mut self, recovery: ErrorRecovery) -> Decoder {
+        self.recovery = recovery;
+        self
+    }
+
+    /// Handles malformed input as `mode` does: `ParseMode::Strict` fails on
+    /// it, like `decode`, while `ParseMode::Browser` keeps a malformed `%`
+    /// as-is and replaces invalid UTF-8 with `U+FFFD`, like a browser's
+    /// `URLSearchParams`. Either replaces any earlier `error_recovery` and
+    /// invalid UTF-8 setting.
+    pub fn parse_mode(self, mode: ParseMode) -> Decoder {
+        match mode {
+            ParseMode::Strict => Decoder {
+                invalid_utf8: InvalidUtf8::Fail,
+                ..self.error_recovery(ErrorRecovery::Strict)
+            },
+            ParseMode::Browser => self.error_recovery(ErrorRecovery::PassThrough).lossy(),
+        }
+    }
+
+    /// Also decodes the `%uXXXX` escapes of JavaScript's legacy `escape()`,
+    /// including surrogate pairs such as `%uD83D%uDC7E`. An unpaired
+    /// surrogate is handled like invalid UTF-8.
+    pub fn percent_u(mut self, percent_u: bool) -> Decoder {
+        self.percent_u = percent_u;
+        self
+    }
+
+    /// Percent-decodes `data`, handling malformed percent-encoding and
+    /// invalid UTF-8 as configured.
+    pub fn decode(&self, data: &str) -> Result<String, FromUrlEncodingError> {
+        let bytes = decode_bytes_recovering(data, self.recovery, self.percent_u)?;
+        let replace: &dyn Fn(&[u8]) -> String = match self.invalid_utf8 {
+            InvalidUtf8::Fail => {
+                return String::from_utf8(bytes).map_err(|e| FromUrlEncodingError::Utf8CharacterError {
+                    error: e,
+                });
+            },
+            InvalidUtf8::Replace(ref replacement) => &move |_| replacement.clone(),
+            InvalidUtf8::Callback(ref callback) => callback,
+        };
+        Ok(replace_invalid_utf8(&bytes, replace))
+    }
+}
+
+impl fmt::Debug for Decoder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let invalid_utf8 = match self.invalid_utf8 {
+            InvalidUtf8::Fail => "Fail".to_string(),
+            InvalidUtf8::Replace(ref replacement) => format!("Replace({:?})", replacement),
+            InvalidUtf8::Callback(_) => "Callback".to_string(),
+        };
+        f.debug_struct("Decoder")
+            .field("invalid_utf8", &format_args!("{}", invalid_utf8))
+            .field("recovery", &self.recovery)
+            .field("percent_u", &self.percent_u)
+            .finish()
+    }
+}
+
+/// Percent-decodes `data` and, if the bytes aren't valid UTF-8, reads them as
+/// Windows-1252 instead, like browsers do when displaying legacy URLs. Every
+/// byte sequence decodes to something, so only malformed percent-encoding
+/// is an error.
+///
+/// `data` is cleaned up with `preprocess_whatwg` first, as a browser would.
+/// The whole value is decoded one way or the other; mixing UTF-8 and
+/// Windows-1252 in a single value isn't recognised.
+pub fn decode_browser_compat(data: &str) -> Result<String, FromUrlEncodingError> {
+    let bytes = decode_bytes(&preprocess_whatwg(data))?;
+    match String::from_utf8(bytes) {
+        Ok(decoded) => Ok(decoded),
+        Err(e) => Ok(e.as_bytes().iter().map(|&b| windows_1252_char(b)).collect()),
+    }
+}
+
+/// Percent-decodes `data` for showing in a terminal or a log, keeping
+/// percent-encoded whatever could corrupt or spoof the output: control
+/// characters such as escape sequences and newlines, bidirectional
+/// overrides that reorder the text around them, invisible zero-width
+/// characters, line separators and bytes that aren't valid UTF-8.
+///
+/// A `%` is kept encoded as `%25`, so if `data` is well-formed, decoding
+/// the result again gives the same as decoding `data`. This never fails: the
+/// `%` of a malformed escape is taken literally, and written as `%25` too,
+/// so `100%`, which doesn't decode, gives `100%25`, which decodes to `100%`.
+///
+/// ```
+/// use urlencoding::decode_display_safe;
+///
+/// assert_eq!("/files/été%E2%80%AEfdp.exe", decode_display_safe("/files/%C3%A9t%C3%A9%E2%80%AEfdp.exe"));
+/// assert_eq!("a b%1B[31m%0A%FF", decode_display_safe("a%20b%1B[31m%0a%ff"));
+/// assert_eq!("100%25", decode_display_safe("100%"));
+/// ```
+pub fn decode_display_safe(data: &str) -> String {
+    let bytes = decode_bytes_recovering(data, ErrorRecovery::PassThrough, false).unwrap_or_default();
+    let mut decoded = String::with_capacity(bytes.len());
+    let escape = |decoded: &mut String, bytes: &[u8]| {
+        for b in bytes {
+            decoded.push_str(&format!("%{:02X}", b));
+        }
+    };
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            if c == '%' || is_display_hostile(c) {
+                escape(&mut decoded, c.encode_utf8(&mut [0; 4]).as_bytes());
+            } else {
+                decoded.push(c);
+            }
+        }
+        escape(&mut decoded, chunk.invalid());
+    }
+    decoded
+}
+
+// Characters that terminals act on or that change how the text around them
+// looks: C0 and C1 controls, bidi controls, zero-width characters and line
+// separators.
+fn is_display_hostile(c: char) -> bool {
+    c.is_control()
+        || matches!(c, '\u{61C}' | '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}')
+        || matches!(c, '\u{200B}'..='\u{200D}' | '\u{2028}' | '\u{2029}' | '\u{2060}' | '\u{FEFF}')
+}
+
+/// Cleans up a URL the way the WHATWG URL parser does before parsing it:
+/// leading and trailing C0 controls and spaces are trimmed, and every ASCII
+/// tab and newline is removed, wherever it is. A URL copied from an email or
+/// a log then parses here the same way it does in a browser.
+///
+/// ```
+/// use urlencoding::preprocess_whatwg;
+///
+/// assert_eq!("https://example.com/a b", preprocess_whatwg("\u{0} https://exam\nple.com/a\t b\r\n"));
+/// ```
+pub fn preprocess_whatwg(url: &str) -> Cow<'_, str> {
+    let trimmed = url.trim_matches(|c| c <= ' ');
+    if trimmed.contains(['\t', '\n', '\r']) {
+        Cow::Owned(trimmed.chars().filter(|&c| !matches!(c, '\t' | '\n' | '\r')).collect())
+    } else {
+        Cow::Borrowed(trimmed)
+    }
+}
+
+// Maps a Windows-1252 byte to its character, as in the WHATWG Encoding
+// Standard: the five undefined bytes map to the C1 controls of the same
+// value.
+fn windows_1252_char(b: u8) -> char {
+    const HIGH: [char; 32] = [
+        '\u{20AC}', '\u{81}', '\u{201A}', '\u{192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+        '\u{2C6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8D}', '\u{17D}', '\u{8F}',
+        '\u{90}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+        '\u{2DC}', '\u{2122}', '\u{161}', '\u{203A}', '\u{153}', '\u{9D}', '\u{17E}', '\u{178}',
+    ];
+    match b {
+        0x80..=0x9F => HIGH[(b - 0x80) as usize],
+        _ => b as char,
+    }
+}
+
+// Percent-decodes `data` into raw bytes, recovering from malformed escapes
+// with `recovery`. With `percent_u`, `%uXXXX` escapes are decoded to UTF-8
+// too; unpaired surrogates are written as the 3 bytes UTF-8 would use for
+// them, which aren't valid UTF-8.
+pub(crate) fn decode_bytes_recovering(
+    data: &str,
+    recovery: ErrorRecovery,
+    percent_u: bool,
+) -> Result<Vec<u8>, FromUrlEncodingError> {
+    let bytes = data.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while let Some(&b) = bytes.get(i) {
+        if b != b'%' {
+            decoded.push(b);
+            i += 1;
+            continue;
+        }
+        if let Some(unit) = percent_u_unit(bytes, i).filter(|_| percent_u) {
+            i += 6;
+            let code_point = match (unit, percent_u_unit(bytes, i)) {
+                (0xD800..=0xDBFF, Some(low @ 0xDC00..=0xDFFF)) => {
+                    i += 6;
+                    0x10000 + ((unit - 0xD800) << 10 | (low - 0xDC00))
+                },
+                _ => unit,
+            };
+            match char::from_u32(code_point) {
+                Some(c) => decoded.extend(c.encode_utf8(&mut [0; 4]).bytes()),
+                None => decoded.extend([0xE0 | (unit >> 12) as u8, 0x80 | (unit >> 6 & 0x3F) as u8, 0x80 | (unit & 0x3F) as u8]),
+            }
+            continue;
+        }
+        match decode_triplet(data, i) {
+            Ok(value) => {
+                decoded.push(value);
+                i += 3;
+            },
+            Err(e) => match recovery {
+                ErrorRecovery::Strict => return Err(e),
+                ErrorRecovery::ReplaceInvalid => {
+                    decoded.extend("\u{FFFD}".bytes());
+                    i += 1;
+                },
+                ErrorRecovery::PassThrough => {
+                    decoded.push(b'%');
+                    i += 1;
+                },
+                ErrorRecovery::Truncate => break,
+            },
+        }
+    }
+    Ok(decoded)
+}
+
+// Returns the UTF-16 code unit of a `%uXXXX` escape at `i`.
+fn percent_u_unit(bytes: &[u8], i: usize) -> Option<u32> {
+    if bytes.get(i..i + 2)? != b"%u" && bytes.get(i..i + 2)? != b"%U" {
+        return None;
+    }
+    let mut unit = 0;
+    for &b in bytes.get(i + 2..i + 6)? {
+        unit = unit << 4 | u32::from(hex_value(b)?);
+    }
+    Some(unit)
+}
+
+// Converts `bytes` to a string, passing every maximal invalid UTF-8
+// sequence to `replace`.
+fn replace_invalid_utf8(mut bytes: &[u8], replace: &dyn Fn(&[u8]) -> String) -> String {
+    let mut decoded = String::with_capacity(bytes.len());
+    loop {
+        match str::from_utf8(bytes) {
+            Ok(valid) => {
+                decoded.push_str(valid);
+                return decoded;
+            },
+            Err(error) => {
+                let (valid, rest) = bytes.split_at(error.valid_up_to());
+                // Checked by from_utf8 above
+                decoded.push_str(str::from_utf8(valid).unwrap_or_default());
+                let invalid_len = error.error_len().unwrap_or(rest.len());
+                decoded.push_str(&replace(&rest[..invalid_len]));
+                bytes = &rest[invalid_len..];
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::decode_browser_compat;
+    use super::decode_display_safe;
+    use super::preprocess_whatwg;
+    use super::Decoder;
+    use super::ErrorRecovery;
+    use super::ParseMode;
+    use super::super::FromUrlEncodingError;
+
+    #[test]
+    fn it_decodes_strictly_by_default() {
+        let decoder = Decoder::new();
+        assert_eq!("this that", decoder.decode("this%20that").unwrap());
+        assert!(decoder.decode("%FF").is_err());
+        assert!(decoder.decode("%F").is_err());
+    }
+
+    #[test]
+    fn it_decodes_lossy() {
+        assert_eq!("a\u{FFFD}b\u{FFFD}", Decoder::new().lossy().decode("a%FFb%E2%82").unwrap());
+    }
+
+    #[test]
+    fn it_decodes_with_replacement() {
+        let decoder = Decoder::new().replace_invalid_utf8("?");
+        // A truncated sequence is replaced once, the genuine U+FFFD is kept
+        assert_eq!("?€??%\u{FFFD}", decoder.decode("%C3%E2%82%AC%FF%FE%25%EF%BF%BD").unwrap());
+        assert!(decoder.decode("%zz").is_err());
+    }
+
+    #[test]
+    fn it_decodes_with_error_recovery() {
+        let decode = |recovery| Decoder::new().error_recovery(recovery).decode("a%20b%zzc%2");
+        assert!(decode(ErrorRecovery::Strict).is_err());
+        assert_eq!("a b\u{FFFD}zzc\u{FFFD}2", decode(ErrorRecovery::ReplaceInvalid).unwrap());
+        assert_eq!("a b%zzc%2", decode(ErrorRecovery::PassThrough).unwrap());
+        assert_eq!("a b", decode(ErrorRecovery::Truncate).unwrap());
+    }
+
+    #[test]
+    fn it_decodes_with_error_recovery_and_percent_u() {
+        let decoder = Decoder::new().error_recovery(ErrorRecovery::PassThrough).percent_u(true).lossy();
+        assert_eq!("€%u20%FF\u{FFFD}", decoder.decode("%u20AC%u20%25FF%FF").unwrap());
+    }
+
+    #[test]
+    fn it_decodes_percent_u() {
+        let decoder = Decoder::new().percent_u(true);
+        assert_eq!("€ 👾!", decoder.decode("%u20AC%20%uD83D%uDC7E%21").unwrap());
+        assert_eq!("caf\u{E9}%u", decoder.decode("caf%U00e9%25u").unwrap());
+        assert!(Decoder::new().decode("%u20AC").is_err());
+    }
+
+    #[test]
+    fn it_decodes_percent_u_unsuccessfully() {
+        let decoder = Decoder::new().percent_u(true);
+        assert!(decoder.decode("%uD83D").is_err());
+        assert!(decoder.decode("%u20A").is_err());
+        // Like String::from_utf8_lossy, each byte of a surrogate is replaced
+        assert_eq!("\u{FFFD}\u{FFFD}\u{FFFD}a", decoder.lossy().decode("%uDC7Ea").unwrap());
+        assert!(Decoder::new().percent_u(true).decode("%7%u20AC").is_err());
+        match Decoder::new().percent_u(true).decode("%u20AC%zz").unwrap_err() {
+            FromUrlEncodingError::UriCharacterError { index: i, character: c } => {
+                assert_eq!(7, i);
+                assert_eq!('z', c)
+            },
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn it_decodes_with_replacement_callback() {
+        let decoder = Decoder::new().replace_invalid_utf8_with(|bytes| format!("<{}>", bytes.len()));
+        assert_eq!("<1>a<2>", decoder.decode("%FFa%E2%82").unwrap());
+        assert_eq!("Decoder { invalid_utf8: Callback, recovery: Strict, percent_u: false }", format!("{:?}", decoder));
+    }
+
+    #[test]
+    fn it_decodes_browser_compat() {
+        assert_eq!("café €5", decode_browser_compat("caf%C3%A9%20%E2%82%AC5").unwrap());
+        assert_eq!("café €5", decode_browser_compat("caf%E9%20%805").unwrap());
+        assert_eq!("\u{201C}ok\u{201D}\u{81}ÿ", decode_browser_compat("%93ok%94%81%FF").unwrap());
+        assert!(decode_browser_compat("caf%E").is_err());
+        assert_eq!("a b", decode_browser_compat(" a%2\n0b\t\r\n").unwrap());
+    }
+
+    #[test]
+    fn it_decodes_display_safe() {
+        assert_eq!("https://example.com/a b?q=ünï", decode_display_safe("https://example.com/a%20b?q=%C3%BCn%C3%AF"));
+        assert_eq!("%09%0D%0A%00%7F%C2%85", decode_display_safe("%09%0D%0A%00%7F%C2%85"));
+        assert_eq!("%D8%9C%E2%80%8B%E2%80%8F%E2%81%A6%E2%80%A8%EF%BB%BF", decode_display_safe("%D8%9C%E2%80%8B%E2%80%8F%E2%81%A6%E2%80%A8%EF%BB%BF"));
+        assert_eq!("100%25 %25zz %25", decode_display_safe("100%25%20%zz %"));
+        assert_eq!("%C3(", decode_display_safe("%c3%28"));
+    }
+
+    #[test]
+    fn it_preprocesses_like_whatwg() {
+        assert_eq!("a b", preprocess_whatwg("\u{1}\u{1F} a b \u{7}"));
+        assert_eq!("ab\u{7}c", preprocess_whatwg("a\tb\u{7}c\n"));
+        assert_eq!("", preprocess_whatwg(" \t\n "));
+        assert!(matches!(preprocess_whatwg(" https://example.com/ "), Cow::Borrowed("https://example.com/")));
+        // Only C0 controls and spaces are trimmed
+        assert_eq!("\u{A0}a\u{7F}", preprocess_whatwg("\u{A0}a\u{7F}"));
+    }
+
+    #[test]
+    fn it_decodes_with_parse_mode() {
+        let browser = Decoder::new().parse_mode(ParseMode::Browser);
+        assert_eq!("100% \u{FFFD}", browser.decode("100%%20%FF").unwrap());
+
+        let strict = Decoder::new().lossy().error_recovery(ErrorRecovery::Truncate).parse_mode(ParseMode::Strict);
+        assert!(strict.decode("100%").is_err());
+        assert!(strict.decode("%FF").is_err());
+        assert_eq!("a b", strict.decode("a%20b").unwrap());
+    }
+}
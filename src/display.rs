@@ -0,0 +1,251 @@
+use std::fmt;
+use std::iter::FusedIterator;
+
+use super::is_unreserved;
+
+/// A `fmt::Write` adapter that percent-encodes everything written to it, the
+/// same way as `encode`, before passing it on to the wrapped writer.
+///
+/// Runs of characters that don't need encoding are passed on as-is, so
+/// nothing is allocated.
+#[derive(Debug)]
+pub struct EncodingWriter<W> {
+    inner: W,
+}
+
+impl<W: fmt::Write> EncodingWriter<W> {
+    pub fn new(inner: W) -> EncodingWriter<W> {
+        EncodingWriter { inner }
+    }
+
+    /// Returns the wrapped writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: fmt::Write> fmt::Write for EncodingWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for piece in encode_pieces(s) {
+            self.inner.write_str(piece)?;
+        }
+        Ok(())
+    }
+}
+
+// "%00%01...%FF", to borrow any escape from.
+const ESCAPES: &str = concat!(
+    "%00%01%02%03%04%05%06%07%08%09%0A%0B%0C%0D%0E%0F",
+    "%10%11%12%13%14%15%16%17%18%19%1A%1B%1C%1D%1E%1F",
+    "%20%21%22%23%24%25%26%27%28%29%2A%2B%2C%2D%2E%2F",
+    "%30%31%32%33%34%35%36%37%38%39%3A%3B%3C%3D%3E%3F",
+    "%40%41%42%43%44%45%46%47%48%49%4A%4B%4C%4D%4E%4F",
+    "%50%51%52%53%54%55%56%57%58%59%5A%5B%5C%5D%5E%5F",
+    "%60%61%62%63%64%65%66%67%68%69%6A%6B%6C%6D%6E%6F",
+    "%70%71%72%73%74%75%76%77%78%79%7A%7B%7C%7D%7E%7F",
+    "%80%81%82%83%84%85%86%87%88%89%8A%8B%8C%8D%8E%8F",
+    "%90%91%92%93%94%95%96%97%98%99%9A%9B%9C%9D%9E%9F",
+    "%A0%A1%A2%A3%A4%A5%A6%A7%A8%A9%AA%AB%AC%AD%AE%AF",
+    "%B0%B1%B2%B3%B4%B5%B6%B7%B8%B9%BA%BB%BC%BD%BE%BF",
+    "%C0%C1%C2%C3%C4%C5%C6%C7%C8%C9%CA%CB%CC%CD%CE%CF",
+    "%D0%D1%D2%D3%D4%D5%D6%D7%D8%D9%DA%DB%DC%DD%DE%DF",
+    "%E0%E1%E2%E3%E4%E5%E6%E7%E8%E9%EA%EB%EC%ED%EE%EF",
+    "%F0%F1%F2%F3%F4%F5%F6%F7%F8%F9%FA%FB%FC%FD%FE%FF",
+);
+
+/// Percent-encodes `data` the same way as `encode`, as pieces that are
+/// either runs of `data` that need no encoding, borrowed as-is, or a single
+/// `%XX` escape, borrowed from a static table. Nothing is allocated, so the
+/// pieces can be handed to vectored I/O as they are:
+///
+/// ```
+/// use std::io::{IoSlice, Write};
+/// use urlencoding::encode_pieces;
+///
+/// let pieces: Vec<&str> = encode_pieces("a b/c").collect();
+/// assert_eq!(vec!["a", "%20", "b", "%2F", "c"], pieces);
+///
+/// let slices: Vec<IoSlice> = pieces.iter().map(|piece| IoSlice::new(piece.as_bytes())).collect();
+/// let mut out = Vec::new();
+/// out.write_vectored(&slices).unwrap();
+/// assert_eq!(b"a%20b%2Fc", &out[..]);
+/// ```
+pub fn encode_pieces(data: &str) -> EncodePieces<'_> {
+    EncodePieces { data, idx: 0 }
+}
+
+/// The iterator returned by `encode_pieces`.
+#[derive(Debug, Clone)]
+pub struct EncodePieces<'a> {
+    data: &'a str,
+    idx: usize,
+}
+
+impl<'a> Iterator for EncodePieces<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let bytes = self.data.as_bytes();
+        let start = self.idx;
+        let b = *bytes.get(start)?;
+        if !is_unreserved(b) {
+            self.idx += 1;
+            let escape = b as usize * 3;
+            return Some(&ESCAPES[escape..escape + 3]);
+        }
+        let len = bytes[start..].iter().take_while(|&&b| is_unreserved(b)).count();
+        self.idx += len;
+        // Runs only hold ASCII bytes, so they always start and end on a char
+        // boundary
+        Some(&self.data[start..start + len])
+    }
+}
+
+impl<'a> FusedIterator for EncodePieces<'a> {}
+
+/// Percent-encodes formatted output without building the unencoded string
+/// first, e.g. `encode_fmt(format_args!("{}-{}", a, b))`.
+pub fn encode_fmt(args: fmt::Arguments) -> String {
+    let mut escaped = String::new();
+    // Writing to a String never fails
+    encode_fmt_to(&mut escaped, args).expect("writing to a String");
+    escaped
+}
+
+/// Percent-encodes formatted output into `out`.
+pub fn encode_fmt_to<W: fmt::Write + ?Sized>(out: &mut W, args: fmt::Arguments) -> fmt::Result {
+    fmt::Write::write_fmt(&mut EncodingWriter::new(out), args)
+}
+
+/// Writes formatted output percent-encoded into a `fmt::Write` destination,
+/// like `write!` but through `encode_fmt_to`.
+///
+/// It works with a `fmt::Formatter`, so it can be used inside `Display`
+/// implementations:
+///
+/// ```
+/// #[macro_use]
+/// extern crate urlencoding;
+///
+/// use std::fmt;
+///
+/// struct Search<'a> {
+///     query: &'a str,
+///     page: u32,
+/// }
+///
+/// impl<'a> fmt::Display for Search<'a> {
+///     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+///         f.write_str("/search?q=")?;
+///         write_encoded!(f, "{}", self.query)?;
+///         write!(f, "&page={}", self.page)
+///     }
+/// }
+///
+/// fn main() {
+///     let search = Search { query: "this that", page: 2 };
+///     assert_eq!("/search?q=this%20that&page=2", search.to_string());
+/// }
+/// ```
+#[macro_export]
+macro_rules! write_encoded {
+    ($dst:expr, $($arg:tt)*) => {{
+        use $crate::WriteEncoded;
+        $dst.write_encoded_fmt(format_args!($($arg)*))
+    }};
+}
+
+// Lets `write_encoded!` borrow its destination the way method calls do, like
+// `write!` relies on `write_fmt`.
+#[doc(hidden)]
+pub trait WriteEncoded: fmt::Write {
+    fn write_encoded_fmt(&mut self, args: fmt::Arguments) -> fmt::Result {
+        encode_fmt_to(self, args)
+    }
+}
+
+impl<W: fmt::Write + ?Sized> WriteEncoded for W {}
+
+/// Lazily percent-encodes the `Display` output of the wrapped value whenever
+/// it is formatted, e.g. `format!("/search?q={}", Encoded(query))`.
+///
+/// `Debug` shows both forms, e.g. `"this%20that" (decoded: "this that")`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Encoded<T>(pub T);
+
+impl<T: fmt::Display> fmt::Display for Encoded<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        encode_fmt_to(f, format_args!("{}", self.0))
+    }
+}
+
+impl<T: fmt::Display> fmt::Debug for Encoded<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?} (decoded: {:?})", self.to_string(), self.0.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+
+    use super::encode_fmt;
+    use super::encode_fmt_to;
+    use super::encode_pieces;
+    use super::Encoded;
+    use super::EncodingWriter;
+    use super::super::encode;
+
+    #[test]
+    fn it_encodes_fmt_successfully() {
+        let (name, id) = ("this that", 42);
+        assert_eq!("this%20that-42%2F%F0%9F%91%BE", encode_fmt(format_args!("{}-{}/👾", name, id)));
+    }
+
+    #[test]
+    fn it_encodes_fmt_to_writer() {
+        let mut out = String::from("q=");
+        encode_fmt_to(&mut out, format_args!("{}&{}", "a b", "c")).unwrap();
+        assert_eq!("q=a%20b%26c", out);
+    }
+
+    #[test]
+    fn it_encodes_pieces() {
+        let pieces: Vec<&str> = encode_pieces("👾 Ex~").collect();
+        assert_eq!(vec!["%F0", "%9F", "%91", "%BE", "%20", "Ex~"], pieces);
+        assert_eq!(None, encode_pieces("").next());
+        for b in 0..=255u8 {
+            let data = [b];
+            if let Ok(data) = std::str::from_utf8(&data) {
+                assert_eq!(encode(data), encode_pieces(data).collect::<String>());
+            }
+        }
+    }
+
+    #[test]
+    fn it_encodes_with_encoding_writer() {
+        let mut writer = EncodingWriter::new(String::new());
+        writer.write_str("plain").unwrap();
+        writer.write_char('?').unwrap();
+        writer.write_str("").unwrap();
+        assert_eq!("plain%3F", writer.into_inner());
+    }
+
+    #[test]
+    fn it_encodes_lazily_with_encoded() {
+        assert_eq!("/search?q=this%20that&page=2", format!("/search?q={}&page={}", Encoded("this that"), Encoded(2)));
+    }
+
+    #[test]
+    fn it_formats_encoded_for_debugging() {
+        assert_eq!("\"this%20that\" (decoded: \"this that\")", format!("{:?}", Encoded("this that")));
+    }
+
+    #[test]
+    fn it_writes_encoded_successfully() {
+        let mut out = String::new();
+        write_encoded!(out, "{}/{}", "a b", 1).unwrap();
+        write_encoded!(out, "!").unwrap();
+        assert_eq!("a%20b%2F1%21", out);
+    }
+}
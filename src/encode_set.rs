@@ -0,0 +1,351 @@
+use std::error::Error;
+use std::fmt;
+
+use super::{encode_bytes_with, is_unreserved};
+
+/// Builds an `AsciiSet` from the union of presets, named like the `AsciiSet`
+/// constructors, and of the characters in string or char literals. It can
+/// be used in a `const`:
+///
+/// ```
+/// #[macro_use]
+/// extern crate urlencoding;
+///
+/// use urlencoding::{AsciiSet, EncodeSet};
+///
+/// const SEGMENT: AsciiSet = encode_set!(ascii_alphanumeric | "-._~" | '/');
+///
+/// fn main() {
+///     assert_eq!("docs/a%20b~", SEGMENT.encode("docs/a b~"));
+/// }
+/// ```
+///
+/// Non-ASCII characters are always encoded, so listing one has no effect.
+#[macro_export]
+macro_rules! encode_set {
+    ($($item:tt)|+) => {
+        $crate::AsciiSet::empty()$(.union($crate::encode_set!(@item $item)))+
+    };
+    (@item $preset:ident) => {
+        $crate::AsciiSet::$preset()
+    };
+    // `concat!` turns a char literal into a string literal too
+    (@item $chars:literal) => {
+        $crate::AsciiSet::from_bytes(concat!($chars).as_bytes())
+    };
+}
+
+/// A set of ASCII bytes left unencoded; every other byte, including all
+/// non-ASCII bytes, is written as `%XX`.
+///
+/// The trait is sealed, so methods can be added to it later, but any type
+/// can become a set by implementing `CustomEncodeSet` instead. A zero-sized
+/// type with a `const` table gets its own monomorphized encoder:
+///
+/// ```
+/// use urlencoding::{CustomEncodeSet, EncodeSet};
+///
+/// struct Hex;
+///
+/// impl CustomEncodeSet for Hex {
+///     fn contains(&self, b: u8) -> bool {
+///         b.is_ascii_hexdigit()
+///     }
+/// }
+///
+/// assert_eq!("ff%20%2B%2000", Hex.encode("ff + 00"));
+/// ```
+pub trait EncodeSet: sealed::Sealed {
+    /// Returns whether `b` is left unencoded. Non-ASCII bytes are always
+    /// encoded, whatever this returns for them.
+    fn contains(&self, b: u8) -> bool;
+
+    /// Percent-encodes every byte of `data` that isn't in the set.
+    fn encode(&self, data: &str) -> String {
+        self.encode_bytes(data.as_bytes())
+    }
+
+    /// Percent-encodes every byte of `data` that isn't in the set, for
+    /// binary data that isn't UTF-8.
+    fn encode_bytes(&self, data: &[u8]) -> String {
+        encode_bytes_with(data, |b| b.is_ascii() && self.contains(b))
+    }
+
+    /// Returns the length `encode` would produce for `data`, without
+    /// encoding it.
+    fn encoded_len(&self, data: &[u8]) -> usize {
+        data.iter().map(|&b| if b.is_ascii() && self.contains(b) { 1 } else { 3 }).sum()
+    }
+}
+
+/// Implementing this trait makes a type an `EncodeSet`.
+pub trait CustomEncodeSet {
+    /// Returns whether `b` is left unencoded.
+    fn contains(&self, b: u8) -> bool;
+}
+
+impl<T: CustomEncodeSet + ?Sized> sealed::Sealed for T {}
+
+impl<T: CustomEncodeSet + ?Sized> EncodeSet for T {
+    fn contains(&self, b: u8) -> bool {
+        CustomEncodeSet::contains(self, b)
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// An `EncodeSet` built from a preset by adding or removing bytes, e.g. the
+/// unreserved characters plus `/` for a path:
+///
+/// ```
+/// use urlencoding::{AsciiSet, EncodeSet};
+///
+/// let path = AsciiSet::unreserved().add(b'/');
+/// assert_eq!("docs/a%20b", path.encode("docs/a b"));
+/// ```
+///
+/// A set is a single `u128` with a bit per ASCII byte, and every constructor
+/// and operation is a `const fn`, so sets can be composed at compile time:
+///
+/// ```
+/// use urlencoding::AsciiSet;
+///
+/// const QUERY_VALUE: AsciiSet = AsciiSet::path().union(AsciiSet::from_bytes(b"?")).remove(b'&').remove(b'=');
+/// assert!(QUERY_VALUE.contains(b'/') && !QUERY_VALUE.contains(b'&'));
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AsciiSet {
+    bits: u128,
+}
+
+impl AsciiSet {
+    /// A set that leaves nothing unencoded.
+    pub const fn empty() -> AsciiSet {
+        AsciiSet { bits: 0 }
+    }
+
+    /// The unreserved characters `A-Z`, `a-z`, `0-9`, `-`, `.`, `_` and `~`,
+    /// the set `encode` uses.
+    pub const fn unreserved() -> AsciiSet {
+        let mut set = AsciiSet::empty();
+        let mut b = 0;
+        while b < 128 {
+            if is_unreserved(b) {
+                set = set.add(b);
+            }
+            b += 1;
+        }
+        set
+    }
+
+    /// The ASCII letters and digits.
+    pub const fn ascii_alphanumeric() -> AsciiSet {
+        AsciiSet::unreserved().difference(encode_set!("-._~"))
+    }
+
+    /// The characters JavaScript's `encodeURIComponent` leaves unencoded: the
+    /// unreserved characters plus `!`, `'`, `(`, `)` and `*`.
+    pub const fn component() -> AsciiSet {
+        encode_set!(unreserved | "!'()*")
+    }
+
+    /// The characters allowed unencoded in a URI path by RFC 3986: the
+    /// unreserved characters, the sub-delimiters, `:`, `@` and `/`.
+    pub const fn path() -> AsciiSet {
+        encode_set!(unreserved | "!$&'()*+,;=" | ':' | '@' | '/')
+    }
+
+    /// The characters left unencoded in the username or password of a URL:
+    /// the unreserved characters plus `!`, `$`, `&`, `'`, `(`, `)`, `*`, `+`
+    /// and `,`. `:`, `@`, `/`, `;` and `=` are encoded, as the WHATWG URL
+    /// standard does.
+    pub const fn userinfo() -> AsciiSet {
+        encode_set!(unreserved | "!$&'()*+,")
+    }
+
+    /// All of ASCII, so only non-ASCII bytes are encoded, like
+    /// `encode_non_ascii_only`.
+    pub const fn ascii() -> AsciiSet {
+        AsciiSet { bits: u128::MAX }
+    }
+
+    /// The set of the ASCII bytes in `bytes`.
+    pub const fn from_bytes(bytes: &[u8]) -> AsciiSet {
+        let mut set = AsciiSet::empty();
+        let mut i = 0;
+        while i < bytes.len() {
+            set = set.add(bytes[i]);
+            i += 1;
+        }
+        set
+    }
+
+    /// Leaves `b` unencoded. Non-ASCII bytes are always encoded, so adding
+    /// one has no effect.
+    #[allow(clippy::should_implement_trait)]
+    pub const fn add(self, b: u8) -> AsciiSet {
+        if b.is_ascii() {
+            return AsciiSet { bits: self.bits | 1 << b };
+        }
+        self
+    }
+
+    /// Encodes `b`.
+    pub const fn remove(self, b: u8) -> AsciiSet {
+        if b.is_ascii() {
+            return AsciiSet { bits: self.bits & !(1 << b) };
+        }
+        self
+    }
+
+    /// Leaves the bytes of both sets unencoded.
+    pub const fn union(self, other: AsciiSet) -> AsciiSet {
+        AsciiSet { bits: self.bits | other.bits }
+    }
+
+    /// Encodes the bytes of `other`.
+    pub const fn difference(self, other: AsciiSet) -> AsciiSet {
+        AsciiSet { bits: self.bits & !other.bits }
+    }
+
+    /// Returns whether `b` is left unencoded.
+    pub const fn contains(&self, b: u8) -> bool {
+        b.is_ascii() && self.bits >> b & 1 == 1
+    }
+}
+
+impl sealed::Sealed for AsciiSet {}
+
+impl EncodeSet for AsciiSet {
+    fn contains(&self, b: u8) -> bool {
+        AsciiSet::contains(self, b)
+    }
+}
+
+impl fmt::Debug for AsciiSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let safe: String = (0..128u8).filter(|&b| self.contains(b)).map(char::from).collect();
+        f.debug_tuple("AsciiSet").field(&safe).finish()
+    }
+}
+
+/// The encoded form of some data would be longer than allowed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EncodedLengthError {
+    pub length: usize,
+    pub limit: usize,
+}
+
+impl EncodedLengthError {
+    /// How many bytes the encoded form is over the limit.
+    pub fn excess(&self) -> usize {
+        self.length - self.limit
+    }
+}
+
+impl fmt::Display for EncodedLengthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "encoded length {} exceeds the limit of {} by {}", self.length, self.limit, self.excess())
+    }
+}
+
+impl Error for EncodedLengthError {}
+
+/// Checks that `data` encoded with `set` fits in `limit` bytes, such as the
+/// 2048 or 8192 byte caps many servers put on URLs, and returns the encoded
+/// length. The encoded string itself is never allocated.
+pub fn check_encoded_len<S: EncodeSet + ?Sized>(data: &str, set: &S, limit: usize) -> Result<usize, EncodedLengthError> {
+    let length = set.encoded_len(data.as_bytes());
+    if length > limit {
+        return Err(EncodedLengthError { length, limit });
+    }
+    Ok(length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_encoded_len;
+    use super::AsciiSet;
+    use super::CustomEncodeSet;
+    use super::EncodeSet;
+    use super::EncodedLengthError;
+    use super::super::encode;
+
+    #[test]
+    fn it_encodes_with_unreserved_set_like_encode() {
+        let data = "👾 Exterminate!~/";
+        assert_eq!(encode(data), AsciiSet::unreserved().encode(data));
+    }
+
+    #[test]
+    fn it_encodes_with_custom_set() {
+        let set = AsciiSet::unreserved().add(b'/').add(b' ').remove(b'~');
+        assert_eq!("a/b c%7E%C3%A9", set.encode("a/b c~é"));
+        assert_eq!("%61%2F", AsciiSet::empty().encode("a/"));
+        assert!(!set.add(0xC3).contains(0xC3));
+        assert_eq!("a%20b/c%C3%A9", AsciiSet::ascii().remove(b' ').encode("a b/cé"));
+        assert_eq!("%89PNG%0D%0A", AsciiSet::unreserved().encode_bytes(b"\x89PNG\r\n"));
+    }
+
+    #[test]
+    fn it_combines_sets() {
+        const SET: AsciiSet = AsciiSet::from_bytes(b"ab\x7f\xff").union(AsciiSet::from_bytes(b"cd"));
+        assert_eq!(AsciiSet::empty().add(b'a').add(b'b').add(b'c').add(b'd').add(0x7F), SET);
+        assert_eq!(AsciiSet::from_bytes(b"b\x7f"), SET.difference(AsciiSet::from_bytes(b"acd")));
+        assert_eq!(AsciiSet::ascii(), AsciiSet::ascii().union(SET));
+        assert!(AsciiSet::ascii().contains(0) && AsciiSet::ascii().contains(0x7F));
+        assert!(!AsciiSet::ascii().contains(0x80));
+    }
+
+    #[test]
+    fn it_builds_sets_with_encode_set_macro() {
+        const SET: AsciiSet = encode_set!(ascii_alphanumeric | "-._~" | '/');
+        assert_eq!(AsciiSet::unreserved().add(b'/'), SET);
+        assert_eq!(AsciiSet::from_bytes(b"ab"), encode_set!('a' | 'é' | 'Ā' | "b👾"));
+        assert_eq!(62, (0..128).filter(|&b| AsciiSet::ascii_alphanumeric().contains(b)).count());
+    }
+
+    #[test]
+    fn it_encodes_with_preset_sets() {
+        assert_eq!("a%20b!'()*%2F%3F", AsciiSet::component().encode("a b!'()*/?"));
+        assert_eq!("/a%20b/c;d=e:f@g%3Fh%23", AsciiSet::path().encode("/a b/c;d=e:f@g?h#"));
+        assert_eq!("%3A%40%2F%3B%3D!$&'()*+,", AsciiSet::userinfo().encode(":@/;=!$&'()*+,"));
+    }
+
+    #[test]
+    fn it_encodes_with_custom_encode_set() {
+        struct Digits;
+
+        impl CustomEncodeSet for Digits {
+            fn contains(&self, b: u8) -> bool {
+                b.is_ascii_digit() || b == 0xC3
+            }
+        }
+
+        assert_eq!("42%2B%78%C3%A9", Digits.encode("42+xé"));
+        assert_eq!(14, Digits.encoded_len("42+xé".as_bytes()));
+        assert_eq!(Ok(2), check_encoded_len("42", &Digits, 2));
+    }
+
+    #[test]
+    fn it_computes_encoded_len() {
+        let set = AsciiSet::unreserved();
+        for data in &["", "this that", "👾 Exterminate!", "a/b?c=d"] {
+            assert_eq!(set.encode(data).len(), set.encoded_len(data.as_bytes()));
+        }
+    }
+
+    #[test]
+    fn it_checks_encoded_len() {
+        let set = AsciiSet::unreserved();
+        assert_eq!(Ok(11), check_encoded_len("this that", &set, 11));
+
+        let error = check_encoded_len("this that", &set, 8).unwrap_err();
+        assert_eq!(EncodedLengthError { length: 11, limit: 8 }, error);
+        assert_eq!(3, error.excess());
+        assert_eq!("encoded length 11 exceeds the limit of 8 by 3", error.to_string());
+    }
+}
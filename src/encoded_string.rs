@@ -0,0 +1,126 @@
+use std::borrow::Borrow;
+use std::fmt;
+use std::str::FromStr;
+
+use super::{decode, decode_bytes, encode, validate_urlencoded_str, FromUrlEncodingError};
+
+/// A string known to be well-formed percent-encoding: every `%` is followed
+/// by two hex digits.
+///
+/// `Display` writes the encoded form, while `Debug` shows both forms, e.g.
+/// `"this%20that" (decoded: "this that")`.
+///
+/// Equality, ordering and hashing compare the encoded form exactly like the
+/// `str` it borrows as, so it can key a `HashMap` or `BTreeMap` that is
+/// looked up with a plain `&str`. `%41` and `A` are therefore different keys.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UrlEncodedString {
+    encoded: String,
+}
+
+impl UrlEncodedString {
+    /// Wraps an already encoded string, validating it first.
+    pub fn new(encoded: String) -> Result<UrlEncodedString, FromUrlEncodingError> {
+        validate_urlencoded_str(&encoded)?;
+        Ok(UrlEncodedString { encoded })
+    }
+
+    /// Encodes `data` with `encode`.
+    pub fn encode(data: &str) -> UrlEncodedString {
+        UrlEncodedString { encoded: encode(data) }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.encoded
+    }
+
+    pub fn into_string(self) -> String {
+        self.encoded
+    }
+
+    /// Decodes the string, which only fails if the decoded bytes aren't
+    /// valid UTF-8.
+    pub fn decode(&self) -> Result<String, FromUrlEncodingError> {
+        decode(&self.encoded)
+    }
+}
+
+impl FromStr for UrlEncodedString {
+    type Err = FromUrlEncodingError;
+
+    fn from_str(encoded: &str) -> Result<UrlEncodedString, FromUrlEncodingError> {
+        UrlEncodedString::new(encoded.to_string())
+    }
+}
+
+impl AsRef<str> for UrlEncodedString {
+    fn as_ref(&self) -> &str {
+        &self.encoded
+    }
+}
+
+impl Borrow<str> for UrlEncodedString {
+    fn borrow(&self) -> &str {
+        &self.encoded
+    }
+}
+
+impl fmt::Display for UrlEncodedString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.encoded)
+    }
+}
+
+impl fmt::Debug for UrlEncodedString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Validated on creation, so only invalid UTF-8 needs replacing
+        let decoded = decode_bytes(&self.encoded).unwrap_or_default();
+        write!(f, "{:?} (decoded: {:?})", self.encoded, String::from_utf8_lossy(&decoded))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use super::UrlEncodedString;
+
+    #[test]
+    fn it_creates_url_encoded_string_successfully() {
+        let encoded = UrlEncodedString::new("this%20that".to_string()).unwrap();
+        assert_eq!("this%20that", encoded.as_str());
+        assert_eq!("this that", encoded.decode().unwrap());
+        assert_eq!(encoded, UrlEncodedString::encode("this that"));
+        assert_eq!(encoded, "this%20that".parse().unwrap());
+    }
+
+    #[test]
+    fn it_creates_url_encoded_string_unsuccessfully() {
+        assert!(UrlEncodedString::new("this%2that".to_string()).is_err());
+        assert!("100%".parse::<UrlEncodedString>().is_err());
+    }
+
+    #[test]
+    fn it_formats_url_encoded_string() {
+        let encoded = UrlEncodedString::encode("this that");
+        assert_eq!("this%20that", encoded.to_string());
+        assert_eq!("\"this%20that\" (decoded: \"this that\")", format!("{:?}", encoded));
+
+        let invalid_utf8: UrlEncodedString = "a%FF".parse().unwrap();
+        assert_eq!("\"a%FF\" (decoded: \"a\u{FFFD}\")", format!("{:?}", invalid_utf8));
+    }
+
+    #[test]
+    fn it_uses_url_encoded_string_as_map_key() {
+        let mut routes = HashMap::new();
+        routes.insert(UrlEncodedString::encode("a b"), 1);
+        routes.insert(UrlEncodedString::encode("a/b"), 2);
+        assert_eq!(Some(&1), routes.get("a%20b"));
+        assert_eq!(Some(&2), routes.get("a%2Fb"));
+        assert_eq!(None, routes.get("a b"));
+
+        let sorted: BTreeMap<_, _> = routes.into_iter().map(|(key, value)| (value, key)).collect();
+        assert_eq!("a%20b", sorted[&1].as_str());
+        assert!(sorted[&1] < sorted[&2]);
+    }
+}
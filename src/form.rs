@@ -0,0 +1,374 @@
+use std::borrow::Cow;
+use std::fs;
+use std::io::{self, Read};
+
+#[cfg(feature = "encoding")]
+use encoding_rs::{Encoding, UTF_8};
+
+use super::{decode_bytes_lenient, encode_bytes, encode_bytes_with, FromUrlEncodingError};
+use query::parse_pairs;
+use uri::UriParts;
+
+/// Selects which specification form data is serialized and parsed by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormMode {
+    /// Only RFC 3986 unreserved characters are left alone, spaces are encoded
+    /// as `%20` and malformed percent escapes are rejected when parsing.
+    Rfc3986,
+    /// Matches the WHATWG URL Standard `application/x-www-form-urlencoded`
+    /// serializer and parser byte for byte, as browsers do: spaces become
+    /// `+`, `*` is left alone, `~` is encoded, and malformed percent escapes
+    /// are kept as-is when parsing.
+    Whatwg,
+}
+
+/// Serializes name/value pairs into an `application/x-www-form-urlencoded`
+/// body or query string.
+///
+/// A pair named `_charset_` with an empty value gets the name of the form's
+/// charset as its value, following the HTML hidden `_charset_` field
+/// convention, so the receiving server knows how to decode the other values.
+#[derive(Debug, Clone)]
+pub struct FormSerializer {
+    serialized: String,
+    mode: FormMode,
+    #[cfg(feature = "encoding")]
+    encoding: &'static Encoding,
+}
+
+impl FormSerializer {
+    /// Creates a serializer that encodes names and values as UTF-8.
+    pub fn new() -> FormSerializer {
+        FormSerializer {
+            serialized: String::new(),
+            mode: FormMode::Rfc3986,
+            #[cfg(feature = "encoding")]
+            encoding: UTF_8,
+        }
+    }
+
+    /// Creates a serializer that encodes names and values in `encoding`, as
+    /// a browser does for a form with `accept-charset` or on a legacy page.
+    #[cfg(feature = "encoding")]
+    pub fn with_charset(encoding: &'static Encoding) -> FormSerializer {
+        FormSerializer {
+            serialized: String::new(),
+            mode: FormMode::Rfc3986,
+            // Forms are never submitted as UTF-16, browsers use UTF-8 instead
+            encoding: encoding.output_encoding(),
+        }
+    }
+
+    /// Sets the specification the pairs are serialized by, which defaults to
+    /// `FormMode::Rfc3986`.
+    pub fn mode(&mut self, mode: FormMode) -> &mut FormSerializer {
+        self.mode = mode;
+        self
+    }
+
+    /// Returns the name of the charset names and values are encoded in.
+    pub fn charset(&self) -> &'static str {
+        #[cfg(feature = "encoding")]
+        return self.encoding.name();
+        #[cfg(not(feature = "encoding"))]
+        return "UTF-8";
+    }
+
+    /// Appends a `name=value` pair, percent-encoding both.
+    pub fn append_pair(&mut self, name: &str, value: &str) -> &mut FormSerializer {
+        let value = if name == "_charset_" && value.is_empty() {
+            self.charset()
+        } else {
+            value
+        };
+        if !self.serialized.is_empty() {
+            self.serialized.push('&');
+        }
+        let encoded_name = self.encode(name);
+        self.serialized.push_str(&encoded_name);
+        self.serialized.push('=');
+        let encoded_value = self.encode(value);
+        self.serialized.push_str(&encoded_value);
+        self
+    }
+
+    /// Returns the serialized pairs, e.g. `name=this%20that&page=2`.
+    pub fn finish(&self) -> String {
+        self.serialized.clone()
+    }
+
+    fn encode(&self, data: &str) -> String {
+        let bytes = self.to_charset(data);
+        match self.mode {
+            FormMode::Rfc3986 => encode_bytes(&bytes),
+            FormMode::Whatwg => encode_whatwg(&bytes),
+        }
+    }
+
+    fn to_charset<'a>(&self, data: &'a str) -> Cow<'a, [u8]> {
+        #[cfg(feature = "encoding")]
+        return self.encoding.encode(data).0;
+        #[cfg(not(feature = "encoding"))]
+        return Cow::Borrowed(data.as_bytes());
+    }
+}
+
+impl Default for FormSerializer {
+    fn default() -> FormSerializer {
+        FormSerializer::new()
+    }
+}
+
+// The WHATWG urlencoded byte serializer
+fn encode_whatwg(data: &[u8]) -> String {
+    encode_bytes_with(data, |b| match b {
+        b'*' | b'-' | b'.' | b'0'..=b'9' | b'A'..=b'Z' | b'_' | b'a'..=b'z' => true,
+        // Spaces are turned into '+' below, so leave them alone here
+        b' ' => true,
+        _ => false,
+    }).replace(' ', "+")
+}
+
+/// Parses an `application/x-www-form-urlencoded` body or query string into
+/// decoded `(name, value)` pairs, in order.
+///
+/// In both modes empty pairs are skipped, a pair without `=` has an empty
+/// value and `+` is decoded as a space. `FormMode::Whatwg` never fails: a `%`
+/// not followed by two hex digits is kept literally and invalid UTF-8 is
+/// replaced with U+FFFD, exactly as browsers parse form data.
+pub fn parse_form(data: &str, mode: FormMode) -> Result<Vec<(String, String)>, FromUrlEncodingError> {
+    match mode {
+        FormMode::Rfc3986 => parse_pairs(data),
+        FormMode::Whatwg => Ok(data
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (name, value) = match pair.find('=') {
+                    Some(idx) => (&pair[..idx], &pair[idx + 1..]),
+                    None => (pair, ""),
+                };
+                (decode_whatwg(name), decode_whatwg(value))
+            })
+            .collect()),
+    }
+}
+
+fn decode_whatwg(data: &str) -> String {
+    let bytes = decode_bytes_lenient(&data.replace('+', " "));
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Builds the URL a browser navigates to when submitting a `GET` form with
+/// the given `action` URL and `(name, value)` pairs.
+///
+/// Any query in `action` is replaced by the pairs serialized in
+/// `FormMode::Whatwg`, while the fragment is kept. A form without any pair
+/// still produces an empty query, e.g. `/search?`.
+pub fn apply_form_get(action: &str, pairs: &[(&str, &str)]) -> String {
+    let mut serializer = FormSerializer::new();
+    serializer.mode(FormMode::Whatwg);
+    for &(name, value) in pairs {
+        serializer.append_pair(name, value);
+    }
+    let mut parts = UriParts::parse(action);
+    parts.query = Some(serializer.finish());
+    parts.to_string()
+}
+
+/// Builds the body one `curl --data-urlencode` argument sends, byte for
+/// byte.
+///
+/// Like curl, the argument is split at its first `=`, and only one without
+/// any `=` is split at its first `@`:
+///
+/// - `content` and `=content` send `content` percent-encoded,
+/// - `name=content` sends `name=` as-is, followed by `content` encoded, even
+///   if `name` holds an `@`,
+/// - `@file` and `name@file` do the same with the contents of `file`, which
+///   is read whole, newlines included; `-` reads standard input.
+///
+/// Content is encoded like `encode`, with spaces as `%20`. Joining the
+/// bodies of several arguments with `&` matches what curl sends for them.
+pub fn curl_data_urlencode(arg: &str) -> io::Result<String> {
+    curl_data_urlencode_with(arg, |file| {
+        if file == "-" {
+            let mut contents = Vec::new();
+            io::stdin().read_to_end(&mut contents)?;
+            Ok(contents)
+        } else {
+            fs::read(file)
+        }
+    })
+}
+
+/// Like `curl_data_urlencode`, but reads the file of an `@file` argument
+/// with `read_file` instead of from disk.
+///
+/// ```
+/// use urlencoding::curl_data_urlencode_with;
+///
+/// let body = curl_data_urlencode_with("msg@note.txt", |_| Ok(b"a&b c\n".to_vec())).unwrap();
+/// assert_eq!("msg=a%26b%20c%0A", body);
+/// ```
+pub fn curl_data_urlencode_with<F>(arg: &str, read_file: F) -> io::Result<String>
+where
+    F: FnOnce(&str) -> io::Result<Vec<u8>>,
+{
+    let (name, content) = match (arg.find('='), arg.find('@')) {
+        (Some(idx), _) => (&arg[..idx], Cow::Borrowed(&arg.as_bytes()[idx + 1..])),
+        (None, Some(idx)) => (&arg[..idx], Cow::Owned(read_file(&arg[idx + 1..])?)),
+        (None, None) => ("", Cow::Borrowed(arg.as_bytes())),
+    };
+    let content = encode_bytes(&content);
+    if name.is_empty() {
+        Ok(content)
+    } else {
+        Ok(format!("{}={}", name, content))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "encoding")]
+    use encoding_rs::{SHIFT_JIS, UTF_16LE, WINDOWS_1252};
+
+    use std::io;
+
+    use super::apply_form_get;
+    use super::curl_data_urlencode;
+    use super::curl_data_urlencode_with;
+    use super::parse_form;
+    use super::FormMode;
+    use super::FormSerializer;
+
+    #[test]
+    fn it_serializes_form_successfully() {
+        let form = FormSerializer::new()
+            .append_pair("name", "this that")
+            .append_pair("emoji", "👾")
+            .finish();
+        assert_eq!("name=this%20that&emoji=%F0%9F%91%BE", form);
+    }
+
+    #[test]
+    fn it_serializes_form_empty() {
+        assert_eq!("", FormSerializer::new().finish());
+    }
+
+    #[test]
+    fn it_serializes_form_charset_field() {
+        let form = FormSerializer::new()
+            .append_pair("_charset_", "")
+            .append_pair("q", "café")
+            .finish();
+        assert_eq!("_charset_=UTF-8&q=caf%C3%A9", form);
+    }
+
+    #[test]
+    fn it_serializes_form_charset_field_with_explicit_value() {
+        let form = FormSerializer::new().append_pair("_charset_", "x").finish();
+        assert_eq!("_charset_=x", form);
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn it_serializes_form_with_charset() {
+        let form = FormSerializer::with_charset(WINDOWS_1252)
+            .append_pair("_charset_", "")
+            .append_pair("q", "café €")
+            .finish();
+        assert_eq!("_charset_=windows-1252&q=caf%E9%20%80", form);
+
+        let form = FormSerializer::with_charset(SHIFT_JIS)
+            .append_pair("_charset_", "")
+            .append_pair("q", "日本")
+            .finish();
+        assert_eq!("_charset_=Shift_JIS&q=%93%FA%96%7B", form);
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn it_serializes_form_with_utf16_charset_as_utf8() {
+        let mut serializer = FormSerializer::with_charset(UTF_16LE);
+        assert_eq!("UTF-8", serializer.charset());
+        assert_eq!("q=%C3%A9", serializer.append_pair("q", "é").finish());
+    }
+
+    #[test]
+    fn it_serializes_form_whatwg() {
+        let form = FormSerializer::new()
+            .mode(FormMode::Whatwg)
+            .append_pair("a b", "~*'!-._")
+            .append_pair("_charset_", "")
+            .finish();
+        assert_eq!("a+b=%7E*%27%21-._&_charset_=UTF-8", form);
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn it_serializes_form_whatwg_with_charset() {
+        let form = FormSerializer::with_charset(WINDOWS_1252)
+            .mode(FormMode::Whatwg)
+            .append_pair("q", "café crème")
+            .finish();
+        assert_eq!("q=caf%E9+cr%E8me", form);
+    }
+
+    #[test]
+    fn it_parses_form_rfc3986() {
+        let expected = vec![
+            ("a b".to_string(), "c".to_string()),
+            ("d".to_string(), "".to_string()),
+        ];
+        assert_eq!(expected, parse_form("a+b=c&&d", FormMode::Rfc3986).unwrap());
+        assert!(parse_form("a=%zz", FormMode::Rfc3986).is_err());
+    }
+
+    #[test]
+    fn it_parses_form_whatwg() {
+        let expected = vec![
+            ("a".to_string(), "%zz".to_string()),
+            ("b".to_string(), "%2".to_string()),
+            ("c".to_string(), "A x=y".to_string()),
+            ("%".to_string(), "\u{FFFD}".to_string()),
+            ("?d".to_string(), "".to_string()),
+        ];
+        assert_eq!(expected, parse_form("a=%zz&b=%2&c=%41+x=y&%=%FF&?d", FormMode::Whatwg).unwrap());
+    }
+
+    #[test]
+    fn it_applies_form_get_successfully() {
+        let url = apply_form_get("https://example.com/search?old=1#results", &[("q", "this that"), ("page", "2")]);
+        assert_eq!("https://example.com/search?q=this+that&page=2#results", url);
+    }
+
+    #[test]
+    fn it_applies_form_get_without_pairs() {
+        assert_eq!("/search?", apply_form_get("/search", &[]));
+    }
+
+    #[test]
+    fn it_builds_curl_data_urlencode_bodies() {
+        let no_file = |_: &str| -> io::Result<Vec<u8>> { panic!("no file is read") };
+        assert_eq!("a b=c", curl_data_urlencode_with("a b=c", no_file).unwrap());
+        assert_eq!("hello%20world", curl_data_urlencode_with("hello world", no_file).unwrap());
+        assert_eq!("a%3Db%26c", curl_data_urlencode_with("=a=b&c", no_file).unwrap());
+        assert_eq!("q=caf%C3%A9%20%2B%20~", curl_data_urlencode_with("q=café + ~", no_file).unwrap());
+        assert_eq!("n a=", curl_data_urlencode_with("n a=", no_file).unwrap());
+        assert_eq!("a=b%40c", curl_data_urlencode_with("a=b@c", no_file).unwrap());
+        assert_eq!("user@example.com=x", curl_data_urlencode_with("user@example.com=x", no_file).unwrap());
+        assert_eq!("data@dir/a=b.txt", curl_data_urlencode_with("data@dir/a=b.txt", no_file).unwrap());
+        assert_eq!("@dir/a=b.txt", curl_data_urlencode_with("@dir/a=b.txt", no_file).unwrap());
+    }
+
+    #[test]
+    fn it_builds_curl_data_urlencode_bodies_from_files() {
+        let read = |file: &str| -> io::Result<Vec<u8>> {
+            assert_eq!("dir/a@b.txt", file);
+            Ok(vec![b'x', 0xFF, b'\n'])
+        };
+        assert_eq!("x%FF%0A", curl_data_urlencode_with("@dir/a@b.txt", read).unwrap());
+        assert_eq!("data=x%FF%0A", curl_data_urlencode_with("data@dir/a@b.txt", read).unwrap());
+        assert!(curl_data_urlencode("f@/nonexistent/urlencoding-test").is_err());
+    }
+}
@@ -0,0 +1,401 @@
+use std::error::Error;
+use std::fmt;
+use std::string::FromUtf8Error;
+
+use super::{decode_bytes, encode_bytes_with, FromUrlEncodingError};
+
+/// A decoded RFC 8187 `ext-value`, as used by `filename*` and other extended
+/// header parameters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtValue {
+    /// The charset name, as written in the header.
+    pub charset: String,
+    /// The language tag, if one was given.
+    pub language: Option<String>,
+    /// The decoded value.
+    pub value: String,
+}
+
+/// Why `decode_ext_value` rejected a value.
+#[derive(Debug)]
+pub enum ExtValueError {
+    /// The value is missing one of the two `'` delimiters.
+    MissingDelimiterError,
+    /// The charset isn't one of the supported ones: only UTF-8, US-ASCII and
+    /// ISO-8859-1 values can be decoded.
+    CharsetError { charset: String },
+    /// The value isn't valid percent-encoding, or doesn't decode to UTF-8.
+    DecodingError { error: FromUrlEncodingError },
+}
+
+impl fmt::Display for ExtValueError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExtValueError::MissingDelimiterError => f.write_str("ext-value is missing a ' delimiter"),
+            ExtValueError::CharsetError { charset } => write!(f, "unsupported ext-value charset {:?}", charset),
+            ExtValueError::DecodingError { error } => write!(f, "invalid ext-value: {}", error),
+        }
+    }
+}
+
+impl Error for ExtValueError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ExtValueError::DecodingError { error } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Encodes `data` as an RFC 8187 `ext-value` in the UTF-8 charset, e.g.
+/// `UTF-8''%E2%82%AC%20rates.txt` for `€ rates.txt`.
+///
+/// The result can be used directly as a header parameter value such as
+/// `Content-Disposition: attachment; filename*=...`.
+pub fn encode_ext_value(data: &str, language: Option<&str>) -> String {
+    let mut ext_value = String::from("UTF-8'");
+    ext_value.push_str(language.unwrap_or(""));
+    ext_value.push('\'');
+    ext_value.push_str(&encode_bytes_with(data.as_bytes(), |b| match b {
+        // attr-char
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' => true,
+        b'!' | b'#' | b'$' | b'&' | b'+' | b'-' | b'.' | b'^' | b'_' | b'`' | b'|' | b'~' => true,
+        _ => false,
+    }));
+    ext_value
+}
+
+/// Decodes an RFC 8187 `ext-value` such as `UTF-8'en'%E2%82%AC%20rates`.
+///
+/// The charset is matched case-insensitively; `UTF-8` and `ISO-8859-1` are
+/// supported.
+pub fn decode_ext_value(data: &str) -> Result<ExtValue, ExtValueError> {
+    let mut parts = data.splitn(3, '\'');
+    let (charset, language, value) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(charset), Some(language), Some(value)) => (charset, language, value),
+        _ => return Err(ExtValueError::MissingDelimiterError),
+    };
+
+    let bytes = decode_bytes(value).map_err(|e| ExtValueError::DecodingError { error: e })?;
+    let value = match decode_charset(charset, bytes) {
+        Some(Ok(value)) => value,
+        Some(Err(e)) => return Err(ExtValueError::DecodingError {
+            error: FromUrlEncodingError::Utf8CharacterError { error: e },
+        }),
+        None => return Err(ExtValueError::CharsetError { charset: charset.to_string() }),
+    };
+
+    Ok(ExtValue {
+        charset: charset.to_string(),
+        language: if language.is_empty() { None } else { Some(language.to_string()) },
+        value,
+    })
+}
+
+// Converts `bytes` in the named charset to a `String`, or returns `None` if
+// the charset isn't supported.
+fn decode_charset(charset: &str, bytes: Vec<u8>) -> Option<Result<String, FromUtf8Error>> {
+    if charset.eq_ignore_ascii_case("UTF-8") || charset.eq_ignore_ascii_case("US-ASCII") {
+        Some(String::from_utf8(bytes))
+    } else if charset.eq_ignore_ascii_case("ISO-8859-1") {
+        // Every ISO-8859-1 byte maps to the code point of the same value
+        Some(Ok(bytes.iter().map(|&b| b as char).collect()))
+    } else {
+        None
+    }
+}
+
+/// Why `decode_encoded_words` rejected a header value.
+#[derive(Debug)]
+pub enum EncodedWordError {
+    /// The charset of a word isn't one of the supported ones: only UTF-8,
+    /// US-ASCII and ISO-8859-1 words can be decoded.
+    CharsetError { charset: String },
+    /// The encoded text of a word isn't valid Q or B encoding.
+    EncodingError { word: String },
+    /// A UTF-8 or US-ASCII word doesn't decode to valid UTF-8.
+    Utf8CharacterError { error: FromUtf8Error },
+}
+
+impl fmt::Display for EncodedWordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EncodedWordError::CharsetError { charset } => write!(f, "unsupported encoded-word charset {:?}", charset),
+            EncodedWordError::EncodingError { word } => write!(f, "invalid encoded text {:?}", word),
+            EncodedWordError::Utf8CharacterError { error } => write!(f, "encoded-word is not UTF-8: {}", error),
+        }
+    }
+}
+
+impl Error for EncodedWordError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            EncodedWordError::Utf8CharacterError { error } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+// An encoded-word may be at most 75 characters long, 12 of which are taken
+// by the `=?UTF-8?Q?` prefix and the `?=` suffix
+const MAX_ENCODED_TEXT_LEN: usize = 75 - 12;
+
+/// Encodes `data` as one or more RFC 2047 `Q` encoded-words in the UTF-8
+/// charset, e.g. `=?UTF-8?Q?caf=C3=A9_cr=C3=A8me?=` for `café crème`.
+///
+/// The output is safe to use in `phrase` contexts such as display names.
+/// Text that wouldn't fit in a single 75 character encoded-word is split into
+/// several words separated by a space, never splitting a character. Empty
+/// text gives an empty string, as an encoded-word can't be empty.
+pub fn encode_encoded_word(data: &str) -> String {
+    if data.is_empty() {
+        return String::new();
+    }
+    let mut words = Vec::new();
+    let mut text = String::new();
+    let mut buf = [0; 4];
+    for c in data.chars() {
+        let encoded_char = if c == ' ' {
+            String::from("_")
+        } else {
+            encode_bytes_with(c.encode_utf8(&mut buf).as_bytes(), |b| {
+                matches!(b, b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'!' | b'*' | b'+' | b'-' | b'/')
+            }).replace('%', "=")
+        };
+        if text.len() + encoded_char.len() > MAX_ENCODED_TEXT_LEN {
+            words.push(format!("=?UTF-8?Q?{}?=", text));
+            text.clear();
+        }
+        text.push_str(&encoded_char);
+    }
+    words.push(format!("=?UTF-8?Q?{}?=", text));
+    words.join(" ")
+}
+
+/// Decodes every RFC 2047 encoded-word found in a header value, such as
+/// `=?UTF-8?Q?caf=C3=A9?=` or `=?ISO-8859-1?B?Y2Fm6Q==?=`.
+///
+/// Text outside encoded-words is kept as-is, while whitespace between two
+/// adjacent encoded-words is dropped. Both the `Q` and the `B` encodings are
+/// supported, in the UTF-8, US-ASCII and ISO-8859-1 charsets.
+pub fn decode_encoded_words(data: &str) -> Result<String, EncodedWordError> {
+    let mut decoded = String::new();
+    let mut rest = data;
+    let mut pending_whitespace = "";
+    let mut after_word = false;
+    while let Some(start) = rest.find("=?") {
+        let (literal, candidate) = rest.split_at(start);
+        match parse_encoded_word(candidate) {
+            Some((charset, encoding, text, len)) => {
+                // Whitespace separating two encoded-words isn't part of the text
+                if !(after_word && literal.chars().all(char::is_whitespace)) {
+                    decoded.push_str(pending_whitespace);
+                    decoded.push_str(literal);
+                }
+                decoded.push_str(&decode_encoded_word(charset, encoding, text)?);
+                pending_whitespace = "";
+                after_word = true;
+                rest = &candidate[len..];
+            },
+            None => {
+                decoded.push_str(pending_whitespace);
+                decoded.push_str(literal);
+                decoded.push_str("=?");
+                pending_whitespace = "";
+                after_word = false;
+                rest = &candidate[2..];
+            }
+        }
+        if after_word {
+            // Hold back whitespace until we know whether another word follows
+            let trimmed = rest.trim_start();
+            pending_whitespace = &rest[..rest.len() - trimmed.len()];
+            rest = trimmed;
+        }
+    }
+    decoded.push_str(pending_whitespace);
+    decoded.push_str(rest);
+    Ok(decoded)
+}
+
+// Parses `=?charset?encoding?text?=` at the start of `data`, returning its
+// parts and total length.
+fn parse_encoded_word(data: &str) -> Option<(&str, char, &str, usize)> {
+    let mut parts = data[2..].splitn(3, '?');
+    let charset = parts.next()?;
+    let encoding = parts.next()?;
+    let rest = parts.next()?;
+    let text = &rest[..rest.find("?=")?];
+    let is_token = |s: &str| !s.is_empty() && !s.contains(|c: char| c.is_whitespace() || c.is_control());
+    if !is_token(charset) || encoding.len() != 1 || text.contains(char::is_whitespace) {
+        return None;
+    }
+    let encoding = encoding.chars().next()?.to_ascii_uppercase();
+    if encoding != 'Q' && encoding != 'B' {
+        return None;
+    }
+    let len = 2 + charset.len() + 1 + 1 + 1 + text.len() + 2;
+    Some((charset, encoding, text, len))
+}
+
+fn decode_encoded_word(charset: &str, encoding: char, text: &str) -> Result<String, EncodedWordError> {
+    let invalid = || EncodedWordError::EncodingError { word: text.to_string() };
+    let bytes = if encoding == 'Q' {
+        let percent_encoded = text.replace('%', "%25").replace('=', "%").replace('_', "%20");
+        decode_bytes(&percent_encoded).map_err(|_| invalid())?
+    } else {
+        decode_base64(text).ok_or_else(invalid)?
+    };
+    // RFC 2231 allows a language suffix such as `UTF-8*en`
+    let charset = charset.split('*').next().unwrap_or(charset);
+    match decode_charset(charset, bytes) {
+        Some(Ok(value)) => Ok(value),
+        Some(Err(e)) => Err(EncodedWordError::Utf8CharacterError { error: e }),
+        None => Err(EncodedWordError::CharsetError { charset: charset.to_string() }),
+    }
+}
+
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len() / 4 * 3);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for b in text.trim_end_matches('=').bytes() {
+        let value = match b {
+            b'A'..=b'Z' => b - b'A',
+            b'a'..=b'z' => b - b'a' + 26,
+            b'0'..=b'9' => b - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use super::decode_encoded_words;
+    use super::decode_ext_value;
+    use super::encode_encoded_word;
+    use super::encode_ext_value;
+    use super::EncodedWordError;
+    use super::ExtValueError;
+
+    #[test]
+    fn it_encodes_ext_value_successfully() {
+        assert_eq!("UTF-8''%E2%82%AC%20rates.txt", encode_ext_value("€ rates.txt", None));
+        assert_eq!("UTF-8'en'a%27b%3Bc", encode_ext_value("a'b;c", Some("en")));
+    }
+
+    #[test]
+    fn it_decodes_ext_value_successfully() {
+        let decoded = decode_ext_value("UTF-8'en'%E2%82%AC%20rates").unwrap();
+        assert_eq!("UTF-8", decoded.charset);
+        assert_eq!(Some("en".to_string()), decoded.language);
+        assert_eq!("€ rates", decoded.value);
+    }
+
+    #[test]
+    fn it_decodes_ext_value_latin1() {
+        let decoded = decode_ext_value("iso-8859-1''%A3%20rates").unwrap();
+        assert_eq!(None, decoded.language);
+        assert_eq!("£ rates", decoded.value);
+    }
+
+    #[test]
+    fn it_decodes_ext_value_unsuccessfully() {
+        match decode_ext_value("UTF-8%E2%82%AC").unwrap_err() {
+            ExtValueError::MissingDelimiterError => {},
+            _ => panic!()
+        }
+        match decode_ext_value("KOI8-R''%C1").unwrap_err() {
+            ExtValueError::CharsetError { charset: c } => assert_eq!("KOI8-R", c),
+            _ => panic!()
+        }
+        match decode_ext_value("UTF-8''%A3").unwrap_err() {
+            ExtValueError::DecodingError { .. } => {},
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn it_describes_ext_value_errors() {
+        let error = decode_ext_value("KOI8-R''%C1").unwrap_err();
+        assert_eq!("unsupported ext-value charset \"KOI8-R\"", error.to_string());
+        assert!(error.source().is_none());
+        let error = decode_ext_value("UTF-8''%zz").unwrap_err();
+        assert_eq!("invalid ext-value: invalid character 'z' at offset 1", error.to_string());
+        assert!(error.source().is_some());
+    }
+
+    #[test]
+    fn it_encodes_encoded_word_successfully() {
+        assert_eq!("=?UTF-8?Q?caf=C3=A9_cr=C3=A8me?=", encode_encoded_word("café crème"));
+        assert_eq!("", encode_encoded_word(""));
+        assert_eq!("=?UTF-8?Q?a=3Fb=3Dc=5Fd?=", encode_encoded_word("a?b=c_d"));
+    }
+
+    #[test]
+    fn it_encodes_encoded_word_split_into_several_words() {
+        let data = "€".repeat(10);
+        let encoded = encode_encoded_word(&data);
+        // Each '€' takes 9 characters, so only 7 fit in a single word
+        assert_eq!(format!("=?UTF-8?Q?{}?= =?UTF-8?Q?{}?=", "=E2=82=AC".repeat(7), "=E2=82=AC".repeat(3)), encoded);
+        assert!(encoded.split(' ').all(|word| word.len() <= 75));
+        assert_eq!(data, decode_encoded_words(&encoded).unwrap());
+    }
+
+    #[test]
+    fn it_decodes_encoded_words_successfully() {
+        assert_eq!("café crème", decode_encoded_words("=?UTF-8?Q?caf=C3=A9_cr=C3=A8me?=").unwrap());
+        assert_eq!("café", decode_encoded_words("=?iso-8859-1?b?Y2Fm6Q==?=").unwrap());
+        assert_eq!("Re: café ok", decode_encoded_words("Re: =?UTF-8?Q?caf=C3=A9?= ok").unwrap());
+    }
+
+    #[test]
+    fn it_decodes_encoded_words_joining_adjacent_words() {
+        assert_eq!("ab", decode_encoded_words("=?UTF-8?Q?a?=  \r\n =?UTF-8?Q?b?=").unwrap());
+        assert_eq!("a b", decode_encoded_words("=?UTF-8?Q?a?= =?UTF-8?Q?_b?=").unwrap());
+    }
+
+    #[test]
+    fn it_decodes_encoded_words_leaving_other_text_alone() {
+        let text = "a =? b =?UTF-8?X?c?= 100%";
+        assert_eq!(text, decode_encoded_words(text).unwrap());
+    }
+
+    #[test]
+    fn it_decodes_encoded_words_unsuccessfully() {
+        match decode_encoded_words("=?UTF-8?Q?=ZZ?=").unwrap_err() {
+            EncodedWordError::EncodingError { word: w } => assert_eq!("=ZZ", w),
+            _ => panic!()
+        }
+        match decode_encoded_words("=?KOI8-R?Q?=C1?=").unwrap_err() {
+            EncodedWordError::CharsetError { charset: c } => assert_eq!("KOI8-R", c),
+            _ => panic!()
+        }
+        match decode_encoded_words("=?UTF-8?Q?=E9?=").unwrap_err() {
+            EncodedWordError::Utf8CharacterError { .. } => {},
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn it_describes_encoded_word_errors() {
+        let error = decode_encoded_words("=?UTF-8?Q?=ZZ?=").unwrap_err();
+        assert_eq!("invalid encoded text \"=ZZ\"", error.to_string());
+        assert!(error.source().is_none());
+        let error = decode_encoded_words("=?UTF-8?Q?=E9?=").unwrap_err();
+        assert!(error.to_string().starts_with("encoded-word is not UTF-8: "));
+        assert!(error.source().is_some());
+    }
+}
@@ -0,0 +1,78 @@
+use std::borrow::Cow;
+
+use super::triplet_value;
+
+/// The case of the hex digits of `%XX` escapes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HexCase {
+    /// `%2F`, which RFC 3986 recommends and `encode` writes.
+    Upper,
+    /// `%2f`.
+    Lower,
+}
+
+/// Changes the hex digits of every `%XX` escape in `data` to `case`,
+/// leaving everything else as it is, so two encoded strings can be
+/// compared without decoding them.
+///
+/// A `%` that isn't followed by 2 hex digits is skipped. If every escape is
+/// already in `case`, `data` is borrowed.
+///
+/// ```
+/// use urlencoding::{normalize_hex_case, HexCase};
+///
+/// assert_eq!("/caf%C3%A9?q=a%2Fb", normalize_hex_case("/caf%c3%A9?q=a%2fb", HexCase::Upper));
+/// assert_eq!("%2fAbC%zz", normalize_hex_case("%2FAbC%zz", HexCase::Lower));
+/// ```
+pub fn normalize_hex_case(data: &str, case: HexCase) -> Cow<'_, str> {
+    let bytes = data.as_bytes();
+    let is_in_case = |b: &u8| match case {
+        HexCase::Upper => !b.is_ascii_lowercase(),
+        HexCase::Lower => !b.is_ascii_uppercase(),
+    };
+    let needs_change = |i: usize| triplet_value(bytes, i).is_some() && !bytes[i + 1..i + 3].iter().all(is_in_case);
+    let first = match (0..bytes.len()).find(|&i| needs_change(i)) {
+        Some(first) => first,
+        None => return Cow::Borrowed(data),
+    };
+    let mut normalized = bytes.to_vec();
+    let mut i = first;
+    while i < bytes.len() {
+        if triplet_value(bytes, i).is_some() {
+            for digit in &mut normalized[i + 1..i + 3] {
+                *digit = match case {
+                    HexCase::Upper => digit.to_ascii_uppercase(),
+                    HexCase::Lower => digit.to_ascii_lowercase(),
+                };
+            }
+            i += 3;
+        } else {
+            i += 1;
+        }
+    }
+    // Only ASCII hex digits changed case, so the bytes are still UTF-8
+    Cow::Owned(String::from_utf8(normalized).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::normalize_hex_case;
+    use super::HexCase;
+
+    #[test]
+    fn it_normalizes_hex_case() {
+        assert_eq!("%AB%CD%EF%09", normalize_hex_case("%ab%Cd%eF%09", HexCase::Upper));
+        assert_eq!("%ab%cd%ef%09", normalize_hex_case("%AB%cD%Ef%09", HexCase::Lower));
+        assert_eq!("é%E9 ab%", normalize_hex_case("é%e9 ab%", HexCase::Upper));
+        assert_eq!("%%AA%a", normalize_hex_case("%%aa%a", HexCase::Upper));
+    }
+
+    #[test]
+    fn it_normalizes_hex_case_without_allocating() {
+        for (data, case) in &[("%2F%C3abc", HexCase::Upper), ("%2f%c3ABC", HexCase::Lower), ("%zz%", HexCase::Upper), ("", HexCase::Lower)] {
+            assert!(matches!(normalize_hex_case(data, *case), Cow::Borrowed(_)), "{}", data);
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use unicode_security::{skeleton, MixedScript};
+
+use super::decode_bytes_lenient;
+
+/// A reason a decoded host name may be impersonating another one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpoofingWarning {
+    /// The label mixes characters from several scripts, such as Latin and
+    /// Cyrillic.
+    MixedScript { label: String },
+    /// The label isn't ASCII but looks like the ASCII label `lookalike`, such
+    /// as `раypal` written with Cyrillic letters for `paypal`.
+    Confusable { label: String, lookalike: String },
+}
+
+/// Percent-decodes `host` and checks each of its labels for mixed scripts
+/// and for confusable characters, following the Unicode security mechanisms
+/// of UTS #39.
+///
+/// Percent-encoding hides these characters, so decoding is where a spoofed
+/// host name becomes visible. Malformed escapes are kept as-is and invalid
+/// UTF-8 is replaced. An empty list means nothing suspicious was found.
+pub fn check_host_spoofing(host: &str) -> Vec<SpoofingWarning> {
+    let decoded = String::from_utf8_lossy(&decode_bytes_lenient(host)).into_owned();
+    let mut warnings = Vec::new();
+    for label in decoded.split('.') {
+        if !label.is_single_script() {
+            warnings.push(SpoofingWarning::MixedScript { label: label.to_string() });
+        }
+        if !label.is_ascii() {
+            let lookalike: String = skeleton(label).collect();
+            if lookalike.is_ascii() {
+                warnings.push(SpoofingWarning::Confusable {
+                    label: label.to_string(),
+                    lookalike: lookalike.to_ascii_lowercase(),
+                });
+            }
+        }
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_host_spoofing;
+    use super::SpoofingWarning;
+
+    #[test]
+    fn it_accepts_plain_hosts() {
+        assert!(check_host_spoofing("www.example.com").is_empty());
+        assert!(check_host_spoofing("%E4%BE%8B%E3%81%88.jp").is_empty());
+        assert!(check_host_spoofing("b%C3%BCcher.de").is_empty());
+    }
+
+    #[test]
+    fn it_flags_mixed_script_hosts() {
+        // "pаypal" with a Cyrillic "а"
+        let warnings = check_host_spoofing("p%D0%B0ypal.com");
+        assert_eq!(SpoofingWarning::MixedScript { label: "p\u{430}ypal".to_string() }, warnings[0]);
+        assert_eq!(
+            SpoofingWarning::Confusable { label: "p\u{430}ypal".to_string(), lookalike: "paypal".to_string() },
+            warnings[1]
+        );
+    }
+
+    #[test]
+    fn it_flags_whole_script_confusable_hosts() {
+        // "раура" is entirely Cyrillic, so only the confusable is flagged
+        assert_eq!(
+            vec![SpoofingWarning::Confusable { label: "\u{440}\u{430}\u{443}\u{440}\u{430}".to_string(), lookalike: "paypa".to_string() }],
+            check_host_spoofing("%D1%80%D0%B0%D1%83%D1%80%D0%B0.com")
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use std::error::Error;
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+#[cfg(feature = "idna")]
+use idna::domain_to_ascii;
+
+use super::decode_bytes;
+
+/// Why `normalize_host` rejected a host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostError {
+    /// A `%` isn't followed by 2 hex digits.
+    PercentEncodingError,
+    /// The host decodes to a character no host can hold, such as a space or
+    /// `/`.
+    CharacterError { character: char },
+    /// The host ends in a number, so it is an IPv4 address, but not a valid
+    /// one, e.g. `256.0.0.0` or `1.2.3.4.5`.
+    Ipv4Error,
+    /// The host is an invalid IPv6 literal in square brackets.
+    Ipv6Error,
+    /// The host isn't a valid internationalized domain name.
+    IdnaError,
+}
+
+impl fmt::Display for HostError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HostError::PercentEncodingError => f.write_str("invalid percent-encoding in host"),
+            HostError::CharacterError { character } => write!(f, "forbidden character {:?} in host", character),
+            HostError::Ipv4Error => f.write_str("invalid IPv4 address"),
+            HostError::Ipv6Error => f.write_str("invalid IPv6 address"),
+            HostError::IdnaError => f.write_str("invalid internationalized domain name"),
+        }
+    }
+}
+
+impl Error for HostError {}
+
+/// Normalizes a host the way the WHATWG URL parser does, so an SSRF filter
+/// sees the address a browser would actually connect to.
+///
+/// The host is percent-decoded and lowercased. A domain that ends in a
+/// number is read as an IPv4 address, in any of the forms browsers accept:
+/// `2130706433`, `0x7f.1` and `0177.0.0.1` all become `127.0.0.1`. IPv6
+/// literals are written in their shortest form, such as `[::1]`. With the
+/// `idna` feature, internationalized domain names are mapped and converted
+/// to Punycode; without it, they are only lowercased.
+///
+/// ```
+/// use urlencoding::normalize_host;
+///
+/// assert_eq!("127.0.0.1", normalize_host("0x7F.1").unwrap());
+/// assert_eq!("example.com", normalize_host("EXAMPLE.%63om").unwrap());
+/// assert_eq!("[::1]", normalize_host("[0:0::1]").unwrap());
+/// ```
+pub fn normalize_host(host: &str) -> Result<String, HostError> {
+    if let Some(literal) = host.strip_prefix('[') {
+        let literal = literal.strip_suffix(']').ok_or(HostError::Ipv6Error)?;
+        let address: Ipv6Addr = literal.parse().map_err(|_| HostError::Ipv6Error)?;
+        return Ok(format!("[{}]", address));
+    }
+    let decoded = decode_bytes(host).map_err(|_| HostError::PercentEncodingError)?;
+    let domain = String::from_utf8_lossy(&decoded);
+    if let Some(character) = domain.chars().find(|&c| is_forbidden(c)) {
+        return Err(HostError::CharacterError { character });
+    }
+    let domain = to_ascii(&domain)?;
+    if ends_in_number(&domain) {
+        return parse_ipv4(&domain).map(|address| address.to_string()).ok_or(HostError::Ipv4Error);
+    }
+    Ok(domain)
+}
+
+// The forbidden domain code points of the WHATWG URL standard.
+fn is_forbidden(c: char) -> bool {
+    c <= ' '
+        || c == '\u{7F}'
+        || matches!(c, '#' | '%' | '/' | ':' | '<' | '>' | '?' | '@' | '[' | '\\' | ']' | '^' | '|')
+}
+
+#[cfg(feature = "idna")]
+fn to_ascii(domain: &str) -> Result<String, HostError> {
+    domain_to_ascii(domain).map_err(|_| HostError::IdnaError)
+}
+
+#[cfg(not(feature = "idna"))]
+fn to_ascii(domain: &str) -> Result<String, HostError> {
+    Ok(domain.to_lowercase())
+}
+
+// Whether the last label, ignoring one trailing dot, is a number, which
+// makes the whole host an IPv4 address.
+fn ends_in_number(domain: &str) -> bool {
+    let domain = domain.strip_suffix('.').unwrap_or(domain);
+    let last = domain.rsplit('.').next().unwrap_or("");
+    !last.is_empty() && (last.bytes().all(|b| b.is_ascii_digit()) || parse_ipv4_part(last).is_some())
+}
+
+// Parses the 1 to 4 parts of an IPv4 address, where every part but the
+// last is a byte and the last one fills the remaining bytes.
+fn parse_ipv4(domain: &str) -> Option<Ipv4Addr> {
+    let domain = domain.strip_suffix('.').unwrap_or(domain);
+    let parts = domain.split('.').map(parse_ipv4_part).collect::<Option<Vec<u64>>>()?;
+    let (&last, init) = parts.split_last()?;
+    if parts.len() > 4 || init.iter().any(|&part| part > 255) || last >= 256u64.pow(5 - parts.len() as u32) {
+        return None;
+    }
+    let address = init.iter().enumerate().fold(last, |address, (i, &part)| address + (part << (8 * (3 - i))));
+    Some(Ipv4Addr::from(address as u32))
+}
+
+// Parses one part of an IPv4 address: hex after "0x", octal after a
+// leading '0', decimal otherwise.
+fn parse_ipv4_part(part: &str) -> Option<u64> {
+    let (digits, radix) = match part.strip_prefix("0x").or_else(|| part.strip_prefix("0X")) {
+        Some(hex) => (hex, 16),
+        None if part.len() > 1 && part.starts_with('0') => (&part[1..], 8),
+        None => (part, 10),
+    };
+    if digits.is_empty() {
+        // "0x" is 0, but an empty part isn't a number
+        return if radix == 16 { Some(0) } else { None };
+    }
+    if !digits.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+    // Anything too long to fit is out of range anyway
+    u64::from_str_radix(digits, radix).ok().or(Some(u64::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::normalize_host;
+    use super::HostError;
+
+    #[test]
+    fn it_normalizes_ipv4_hosts() {
+        for host in &["127.0.0.1", "2130706433", "0x7f000001", "0x7f.1", "0177.0.0.1", "127.1", "127.0.1", "0x7F.0.0.1.", "%31%32%37.1"] {
+            assert_eq!("127.0.0.1", normalize_host(host).unwrap(), "{}", host);
+        }
+        assert_eq!("0.0.0.0", normalize_host("0").unwrap());
+        assert_eq!("0.0.0.0", normalize_host("0x").unwrap());
+        assert_eq!("255.255.255.255", normalize_host("4294967295").unwrap());
+    }
+
+    #[test]
+    fn it_rejects_invalid_ipv4_hosts() {
+        for host in &["256.0.0.1", "1.2.3.4.5", "4294967296", "1.2.3.09", "1..2", "0x100.1", "99999999999999999999999"] {
+            assert_eq!(Err(HostError::Ipv4Error), normalize_host(host), "{}", host);
+        }
+    }
+
+    #[test]
+    fn it_normalizes_domains() {
+        assert_eq!("example.com", normalize_host("Example.COM").unwrap());
+        assert_eq!("1.example", normalize_host("1.example").unwrap());
+        assert_eq!("0x1g", normalize_host("0x1g").unwrap());
+        assert_eq!("example.com.", normalize_host("example.com.").unwrap());
+        assert_eq!(Err(HostError::CharacterError { character: '/' }), normalize_host("evil.com%2F"));
+        assert_eq!(Err(HostError::CharacterError { character: ' ' }), normalize_host("a b"));
+        assert_eq!(Err(HostError::PercentEncodingError), normalize_host("a%zz"));
+    }
+
+    #[test]
+    fn it_normalizes_ipv6_hosts() {
+        assert_eq!("[2001:db8::1]", normalize_host("[2001:DB8:0:0:0::1]").unwrap());
+        assert_eq!(Err(HostError::Ipv6Error), normalize_host("[::1"));
+        assert_eq!(Err(HostError::Ipv6Error), normalize_host("[nope]"));
+    }
+
+    #[test]
+    #[cfg(feature = "idna")]
+    fn it_normalizes_internationalized_domains() {
+        assert_eq!("xn--bcher-kva.example", normalize_host("Bücher.example").unwrap());
+        assert_eq!("xn--bcher-kva.example", normalize_host("B%C3%BCcher.example").unwrap());
+        assert_eq!("example.com", normalize_host("ｅｘａｍｐｌｅ.com").unwrap());
+    }
+
+    #[test]
+    #[cfg(not(feature = "idna"))]
+    fn it_lowercases_internationalized_domains() {
+        assert_eq!("bücher.example", normalize_host("BÜCHER.example").unwrap());
+    }
+}
@@ -0,0 +1,71 @@
+use super::{is_unreserved, triplet_value};
+
+/// Escapes a URL for an HTML or XML attribute value, such as `href` or
+/// `src`, in one pass.
+///
+/// For the URL, every byte that can't appear in a URI is percent-encoded,
+/// which covers spaces, `"`, `<`, `>` and non-ASCII characters, while
+/// reserved characters and valid `%XX` escapes are kept, so the URL means the
+/// same thing. For the markup, `&` and `'` are then written as `&amp;` and
+/// `&#39;`. The result is safe inside both double- and single-quoted
+/// attributes.
+///
+/// This only keeps the URL from breaking out of the attribute. It doesn't
+/// make a `javascript:` URL safe to link to.
+///
+/// ```
+/// use urlencoding::encode_for_html_attr;
+///
+/// assert_eq!(
+///     "/search?q=a%20b&amp;lang=fr&amp;x=%22%3E%3Cscript%3E",
+///     encode_for_html_attr("/search?q=a b&lang=fr&x=\"><script>"),
+/// );
+/// ```
+pub fn encode_for_html_attr(url: &str) -> String {
+    let bytes = url.as_bytes();
+    let mut escaped = String::with_capacity(bytes.len());
+    for (i, &b) in bytes.iter().enumerate() {
+        match b {
+            b'&' => escaped.push_str("&amp;"),
+            b'\'' => escaped.push_str("&#39;"),
+            b'%' if triplet_value(bytes, i).is_some() => escaped.push('%'),
+            _ if is_uri_char(b) => escaped.push(b as char),
+            _ => escaped.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    escaped
+}
+
+// The characters of RFC 3986 other than '%': unreserved, gen-delims and
+// sub-delims.
+fn is_uri_char(b: u8) -> bool {
+    is_unreserved(b)
+        || matches!(b, b':' | b'/' | b'?' | b'#' | b'[' | b']' | b'@')
+        || matches!(b, b'!' | b'$' | b'&' | b'\'' | b'(' | b')' | b'*' | b'+' | b',' | b';' | b'=')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::encode_for_html_attr;
+
+    #[test]
+    fn it_encodes_for_html_attr_unchanged() {
+        let url = "https://user@example.com:8080/a/b;c=d?e=f+g,h*(i)$!#j:k@l";
+        assert_eq!(url, encode_for_html_attr(url));
+    }
+
+    #[test]
+    fn it_encodes_for_html_attr_markup_characters() {
+        assert_eq!("?a=1&amp;b=2", encode_for_html_attr("?a=1&b=2"));
+        assert_eq!("/it&#39;s", encode_for_html_attr("/it's"));
+        assert_eq!("%22%20onclick=%22x", encode_for_html_attr("\" onclick=\"x"));
+        assert_eq!("%3C/a%3E%3Cb%3E", encode_for_html_attr("</a><b>"));
+    }
+
+    #[test]
+    fn it_encodes_for_html_attr_percent_signs() {
+        assert_eq!("/a%20b/100%25/%252", encode_for_html_attr("/a%20b/100%/%2"));
+        assert_eq!("/%C3%A9t%C3%A9%0A", encode_for_html_attr("/\u{e9}t%C3%A9\n"));
+        assert_eq!("%5C%5E%60%7B%7C%7D", encode_for_html_attr("\\^`{|}"));
+    }
+}
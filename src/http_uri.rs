@@ -0,0 +1,93 @@
+use http::uri::PathAndQuery;
+use http::{Request, Uri};
+
+use query::{QueryEditError, QueryExt, QueryParams};
+
+impl QueryExt for Uri {
+    fn query_params(&self) -> Result<QueryParams, QueryEditError> {
+        QueryParams::parse(self.query().unwrap_or(""))
+            .map_err(|e| QueryEditError::DecodingError { error: e })
+    }
+
+    fn set_query_params(&mut self, params: &QueryParams) -> Result<(), QueryEditError> {
+        let mut path_and_query = match self.path() {
+            "" => String::from("/"),
+            path => path.to_string(),
+        };
+        if !params.is_empty() {
+            path_and_query.push('?');
+            path_and_query.push_str(&params.to_string());
+        }
+
+        let mut parts = self.clone().into_parts();
+        // Every byte of an encoded query is valid in a URI
+        parts.path_and_query = Some(PathAndQuery::from_maybe_shared(path_and_query).expect("valid path and query"));
+        *self = Uri::from_parts(parts).map_err(|e| QueryEditError::InvalidUriError { error: e })?;
+        Ok(())
+    }
+}
+
+impl<B> QueryExt for Request<B> {
+    fn query_params(&self) -> Result<QueryParams, QueryEditError> {
+        self.uri().query_params()
+    }
+
+    fn set_query_params(&mut self, params: &QueryParams) -> Result<(), QueryEditError> {
+        self.uri_mut().set_query_params(params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{Request, Uri};
+
+    use query::{QueryEditError, QueryExt};
+
+    #[test]
+    fn it_reads_uri_query_params() {
+        let uri: Uri = "https://example.com/search?q=this+that&page=2".parse().unwrap();
+        let params = uri.query_params().unwrap();
+        assert_eq!(Some("this that"), params.get("q"));
+        assert_eq!(Some(Ok(2)), params.get_parsed::<u32>("page"));
+
+        let uri: Uri = "/search".parse().unwrap();
+        assert!(uri.query_params().unwrap().is_empty());
+    }
+
+    #[test]
+    fn it_edits_uri_query_params() {
+        let mut uri: Uri = "https://example.com/search?q=rust&page=2".parse().unwrap();
+        uri.set_query_param("page", "3").unwrap();
+        uri.append_query_param("tag", "a&b").unwrap();
+        assert_eq!("https://example.com/search?q=rust&page=3&tag=a%26b", uri.to_string());
+
+        uri.remove_query_param("q").unwrap();
+        uri.remove_query_param("page").unwrap();
+        uri.remove_query_param("tag").unwrap();
+        assert_eq!("https://example.com/search", uri.to_string());
+    }
+
+    #[test]
+    fn it_edits_uri_query_params_without_path() {
+        let mut uri: Uri = "https://example.com".parse().unwrap();
+        uri.append_query_param("q", "€").unwrap();
+        assert_eq!("https://example.com/?q=%E2%82%AC", uri.to_string());
+    }
+
+    #[test]
+    fn it_edits_request_query_params() {
+        let mut request = Request::get("/items?a=1").body(()).unwrap();
+        request.append_query_param("b", "2 3").unwrap();
+        assert_eq!("/items?a=1&b=2%203", request.uri().to_string());
+        assert_eq!(Some("2 3"), request.query_params().unwrap().get("b"));
+    }
+
+    #[test]
+    fn it_edits_uri_query_params_unsuccessfully() {
+        let mut uri: Uri = "/items?a=%2".parse().unwrap();
+        match uri.append_query_param("b", "2").unwrap_err() {
+            QueryEditError::DecodingError { .. } => {},
+            _ => panic!()
+        }
+    }
+}
@@ -1,80 +1,546 @@
-use std::str;
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
+
+use std::error::Error;
+use std::fmt;
+use std::iter::FusedIterator;
+use std::mem;
 use std::string::FromUtf8Error;
 
+#[cfg(feature = "bumpalo")]
+extern crate bumpalo;
+#[cfg(feature = "encoding")]
+extern crate encoding_rs;
+#[cfg(feature = "http")]
+extern crate http;
+#[cfg(feature = "idna")]
+extern crate idna;
+#[cfg(feature = "proptest")]
+extern crate proptest;
+#[cfg(feature = "psl")]
+extern crate psl;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
+#[cfg(feature = "ufmt")]
+extern crate ufmt;
+#[cfg(feature = "unicode")]
+extern crate unicode_normalization;
+#[cfg(feature = "unicode-security")]
+extern crate unicode_security;
+#[cfg(feature = "url")]
+extern crate url;
+#[cfg(feature = "derive")]
+extern crate urlencoding_derive;
+#[cfg(feature = "defmt")]
+extern crate defmt;
+#[cfg(feature = "zeroize")]
+extern crate zeroize;
+
+#[cfg(feature = "allocator_api")]
+mod allocator;
+#[cfg(feature = "bumpalo")]
+mod bump;
+#[cfg(feature = "cache")]
+mod cache;
+#[cfg(feature = "encoding")]
+mod charset;
+#[cfg(feature = "cloud")]
+mod cloud;
+#[cfg(feature = "cookie")]
+mod cookie;
+#[cfg(feature = "decoder")]
+mod decoder;
+#[cfg(feature = "display")]
+mod display;
+#[cfg(feature = "encode-set")]
+mod encode_set;
+#[cfg(feature = "encoded-string")]
+mod encoded_string;
+#[cfg(feature = "form")]
+mod form;
+#[cfg(feature = "header")]
+mod header;
+#[cfg(feature = "hex-case")]
+mod hex_case;
+#[cfg(feature = "unicode-security")]
+mod homograph;
+#[cfg(feature = "uri")]
+mod host;
+#[cfg(feature = "html")]
+mod html;
+#[cfg(feature = "http")]
+mod http_uri;
+#[cfg(feature = "uri")]
+mod lint;
+#[cfg(feature = "uri")]
+mod normalize;
+#[cfg(feature = "oauth")]
+mod oauth;
+#[cfg(feature = "odata")]
+mod odata;
+#[cfg(feature = "offsets")]
+mod offsets;
+#[cfg(feature = "uri")]
+mod origin;
+#[cfg(feature = "path")]
+mod path;
+#[cfg(feature = "uri")]
+mod pattern;
+#[cfg(feature = "proptest")]
+mod proptest_support;
+#[cfg(feature = "uri")]
+mod proxy;
+#[cfg(feature = "psl")]
+mod public_suffix;
+#[cfg(feature = "query")]
+mod query;
+#[cfg(feature = "uri")]
+mod reencode;
+#[cfg(feature = "secret")]
+mod secret;
+#[cfg(feature = "serde")]
+mod serde_support;
+#[cfg(feature = "slug")]
+mod slug;
+#[cfg(feature = "spaces")]
+mod spaces;
+#[cfg(feature = "test-vectors")]
+mod test_vectors;
+#[cfg(feature = "ufmt")]
+mod ufmt_support;
+#[cfg(feature = "unicode")]
+mod unicode;
+#[cfg(feature = "uri")]
+mod uri;
+#[cfg(feature = "url")]
+mod url_interop;
+#[cfg(feature = "query")]
+mod url_string;
+
+#[cfg(feature = "allocator_api")]
+pub use allocator::{decode_in, encode_in};
+#[cfg(feature = "bumpalo")]
+pub use bump::{decode_in_bump, encode_in_bump};
+#[cfg(feature = "cache")]
+pub use cache::{DecodeCache, EncodeCache};
+#[cfg(feature = "encoding")]
+pub use charset::{decode_with_charset, encode_with_charset};
+#[cfg(feature = "cloud")]
+pub use cloud::{decode_s3_event_key, encode_azure_blob_name, encode_gcs_object_name, encode_s3_key};
+#[cfg(feature = "cookie")]
+pub use cookie::{decode_cookie_value, encode_cookie_value};
+#[cfg(feature = "decoder")]
+pub use decoder::{decode_browser_compat, decode_display_safe, preprocess_whatwg, Decoder, ErrorRecovery, ParseMode};
+#[cfg(feature = "display")]
+pub use display::{encode_fmt, encode_fmt_to, encode_pieces, EncodePieces, Encoded, EncodingWriter};
+#[cfg(feature = "display")]
+#[doc(hidden)]
+pub use display::WriteEncoded;
+#[cfg(feature = "encode-set")]
+pub use encode_set::{check_encoded_len, AsciiSet, CustomEncodeSet, EncodeSet, EncodedLengthError};
+#[cfg(feature = "encoded-string")]
+pub use encoded_string::UrlEncodedString;
+#[cfg(feature = "form")]
+pub use form::{apply_form_get, curl_data_urlencode, curl_data_urlencode_with, parse_form, FormMode, FormSerializer};
+#[cfg(feature = "header")]
+pub use header::{decode_encoded_words, encode_encoded_word, EncodedWordError};
+#[cfg(feature = "header")]
+pub use header::{decode_ext_value, encode_ext_value, ExtValue, ExtValueError};
+#[cfg(feature = "hex-case")]
+pub use hex_case::{normalize_hex_case, HexCase};
+#[cfg(feature = "unicode-security")]
+pub use homograph::{check_host_spoofing, SpoofingWarning};
+#[cfg(feature = "uri")]
+pub use host::{normalize_host, HostError};
+#[cfg(feature = "html")]
+pub use html::encode_for_html_attr;
+#[cfg(feature = "uri")]
+pub use lint::{lint_url, LintKind, UrlLint};
+#[cfg(feature = "uri")]
+pub use normalize::UrlNormalizer;
+#[cfg(feature = "oauth")]
+pub use oauth::{encode_oauth1, normalize_oauth1_parameters, signature_base_string};
+#[cfg(feature = "odata")]
+pub use odata::{encode_odata_query, odata_string};
+#[cfg(feature = "offsets")]
+pub use offsets::{decode_spans, decode_with_offsets, DecodeSpans, DecodedSpan, OffsetMap};
+#[cfg(feature = "uri")]
+pub use origin::{origin, Origin};
+#[cfg(feature = "path")]
+pub use path::{decode_segments, match_route, split_encoded, MatrixSegment, RouteMatch, SplitEncoded};
+#[cfg(feature = "uri")]
+pub use pattern::matches_pattern;
+#[cfg(feature = "proptest")]
+pub use proptest_support::{arb_encoded_string, arb_nasty_encoded_string, arb_query_string};
+#[cfg(feature = "uri")]
+pub use proxy::{parse_proxy_url, ProxyUrl, ProxyUrlError};
+#[cfg(feature = "psl")]
+pub use public_suffix::{is_public_suffix, registrable_domain};
+#[cfg(feature = "query")]
+pub use query::{canonicalize_query, diff_query, for_each_query_pair, merge_query, redact_query, MergePolicy, QueryChange};
+#[cfg(feature = "query")]
+pub use query::{FromQuery, FromQueryError, ParamError, QueryParams, ToQuery};
+#[cfg(feature = "query")]
+pub use query::{QueryEditError, QueryExt};
+#[cfg(feature = "uri")]
+pub use reencode::{reencode, EncodeContext};
+#[cfg(feature = "secret")]
+pub use secret::ct_eq_decoded;
+#[cfg(feature = "zeroize")]
+pub use secret::{decode_bytes_zeroizing, decode_zeroizing};
+#[cfg(feature = "slug")]
+pub use slug::{slugify, Slugifier};
+#[cfg(feature = "spaces")]
+pub use spaces::{percent20_to_plus, percent20_to_plus_in_place, plus_to_percent20};
+#[cfg(feature = "test-vectors")]
+pub use test_vectors::{check_urlencoded_vectors, UrlencodedVector, VectorMismatch, URLENCODED_VECTORS};
+#[cfg(feature = "ufmt")]
+pub use ufmt_support::encode_to_uwrite;
+#[cfg(feature = "unicode")]
+pub use unicode::encode_nfc;
+#[cfg(feature = "uri")]
+pub use uri::{classify, decode_component, default_port, elide_default_port, Authority, Host};
+#[cfg(feature = "uri")]
+pub use uri::{decode_userinfo, encode_userinfo, normalize_backslashes, parse_authority};
+#[cfg(feature = "uri")]
+pub use uri::{is_dangerous_scheme, sanitize_scheme};
+#[cfg(feature = "uri")]
+pub use uri::{validate_request_target, validate_uri, validate_uri_reference, Component, RequestTargetForm, UriParts, UriValidationError, UrlKind};
+#[cfg(feature = "query")]
+pub use url_string::{append_params, get_param, remove_param, set_param, strip_params, ParamMatcher};
+#[cfg(feature = "derive")]
+pub use urlencoding_derive::{FromQuery, ToQuery};
+
 pub fn encode(data: &str) -> String {
-    let mut escaped = String::new();
-    for b in data.as_bytes().iter() {
-        match *b as char {
-            // Accepted characters
-            'A'...'Z' | 'a'...'z' | '0'...'9' | '-' | '_' | '.' | '~' => escaped.push(*b as char),
+    encode_bytes(data.as_bytes())
+}
 
-            // Everything else is percent-encoded
-            b => escaped.push_str(format!("%{:02X}", b as u32).as_str()),
-        };
+/// Percent-encodes `data` like `encode`, but leaves `%XX` sequences that are
+/// already valid escapes untouched, so encoding input that may already be
+/// encoded doesn't turn `%20` into `%2520`.
+///
+/// A `%` that doesn't start a valid escape is encoded as `%25`. Input that
+/// is meant to contain a literal `%20` can't be told apart from an escape, so
+/// only use this when the input is known to be partially encoded.
+pub fn encode_idempotent(data: &str) -> String {
+    let bytes = data.as_bytes();
+    let mut escaped = String::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if triplet_value(bytes, i).is_some() {
+            escaped.push_str(&data[i..i + 3]);
+            i += 3;
+        } else if is_unreserved(bytes[i]) {
+            escaped.push(bytes[i] as char);
+            i += 1;
+        } else {
+            escaped.push_str(format!("%{:02X}", bytes[i]).as_str());
+            i += 1;
+        }
     }
-    return escaped;
+    escaped
+}
+
+/// Percent-encodes only the non-ASCII bytes of `data`, leaving all of ASCII,
+/// including punctuation and spaces, as-is. This is how an IRI is turned
+/// into a URI that stays readable.
+///
+/// To also encode spaces, use `AsciiSet::ascii().remove(b' ')`, with the
+/// `encode-set` feature.
+pub fn encode_non_ascii_only(data: &str) -> String {
+    encode_bytes_with(data.as_bytes(), |b| b.is_ascii())
+}
+
+// Percent-encodes raw bytes with the same accepted characters as `encode`.
+pub(crate) fn encode_bytes(data: &[u8]) -> String {
+    encode_bytes_with(data, is_unreserved)
 }
 
+// The characters `encode` leaves alone.
+pub(crate) const fn is_unreserved(b: u8) -> bool {
+    match b {
+        // Accepted characters
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => true,
+
+        // Everything else is percent-encoded
+        _ => false,
+    }
+}
+
+// Percent-encodes every byte for which `is_safe` returns false. `is_safe`
+// must only accept ASCII bytes, so the output is always valid UTF-8.
+pub(crate) fn encode_bytes_with<F: Fn(u8) -> bool>(data: &[u8], is_safe: F) -> String {
+    let mut escaped = String::with_capacity(data.len());
+    for &b in data {
+        if is_safe(b) {
+            escaped.push(b as char);
+        } else {
+            escaped.push_str(format!("%{:02X}", b).as_str());
+        }
+    }
+    escaped
+}
+
+/// Percent-decodes `data` and checks the result is valid UTF-8.
+///
+/// Decoding is a single pass that never panics, whatever the input: a `%`
+/// that isn't followed by 2 hex digits, including one at the very end, is
+/// reported as an error instead. The `fuzz` directory holds the cargo-fuzz
+/// targets that check this.
 pub fn decode(data: &str) -> Result<String, FromUrlEncodingError> {
-    validate_urlencoded_str(data)?;
-    let mut unescaped_bytes: Vec<u8> = Vec::new();
-    let mut bytes = data.bytes();
-    // If validate_urlencoded_str returned Ok, then we know
-    // every '%' is followed by 2 hex characters
-    while let Some(b) = bytes.next() {
-        match b as char {
-            '%' => {
-                let bytes_to_decode = &[bytes.next().unwrap(), bytes.next().unwrap()];
-                let hex_str = str::from_utf8(bytes_to_decode).unwrap();
-                unescaped_bytes.push(u8::from_str_radix(hex_str, 16).unwrap());
+    let unescaped_bytes = decode_bytes(data)?;
+    String::from_utf8(unescaped_bytes).map_err(|e| FromUrlEncodingError::Utf8CharacterError {
+        error: e,
+    })
+}
+
+// Percent-decodes `data` into raw bytes, without checking they form valid
+// UTF-8.
+pub(crate) fn decode_bytes(data: &str) -> Result<Vec<u8>, FromUrlEncodingError> {
+    let mut unescaped_bytes: Vec<u8> = Vec::with_capacity(data.len());
+    decode_to_vec(data, &mut unescaped_bytes)?;
+    Ok(unescaped_bytes)
+}
+
+/// Percent-decodes `data` into `out`, without checking the bytes form valid
+/// UTF-8.
+///
+/// `out` is cleared first, but keeps its allocation, so a single buffer can
+/// be reused to decode many values. On error `out` is left empty.
+pub fn decode_to_vec(data: &str, out: &mut Vec<u8>) -> Result<(), FromUrlEncodingError> {
+    out.clear();
+    out.reserve(data.len());
+    for b in decode_iter(data) {
+        match b {
+            Ok(b) => out.push(b),
+            Err(e) => {
+                out.clear();
+                return Err(e);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Percent-decodes `data` one byte at a time, without allocating, for
+/// parsers that feed the decoded bytes straight into their own state
+/// machine.
+///
+/// The bytes aren't checked to form valid UTF-8. Malformed percent-encoding
+/// is yielded as an error, the same one `decode` returns, after which the
+/// iterator ends.
+///
+/// ```
+/// use urlencoding::decode_iter;
+///
+/// let bytes: Result<Vec<u8>, _> = decode_iter("a%20b%FF").collect();
+/// assert_eq!(b"a b\xFF".to_vec(), bytes.unwrap());
+/// ```
+pub fn decode_iter(data: &str) -> DecodeBytes<'_> {
+    DecodeBytes { data, idx: 0 }
+}
+
+/// The iterator returned by `decode_iter`.
+#[derive(Debug, Clone)]
+pub struct DecodeBytes<'a> {
+    data: &'a str,
+    idx: usize,
+}
+
+impl<'a> Iterator for DecodeBytes<'a> {
+    type Item = Result<u8, FromUrlEncodingError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let b = *self.data.as_bytes().get(self.idx)?;
+        if b != b'%' {
+            // Assume whoever did the encoding intended what we got
+            self.idx += 1;
+            return Some(Ok(b));
+        }
+        match decode_triplet(self.data, self.idx) {
+            Ok(decoded) => {
+                self.idx += 3;
+                Some(Ok(decoded))
             },
-            _ => {
-                // Assume whoever did the encoding intended what we got
-                unescaped_bytes.push(b);
+            Err(e) => {
+                self.idx = self.data.len();
+                Some(Err(e))
+            },
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // An error can end it at any point
+        let remaining = self.data.len() - self.idx;
+        (usize::from(remaining > 0), Some(remaining))
+    }
+}
+
+impl<'a> FusedIterator for DecodeBytes<'a> {}
+
+/// Percent-decodes `data` in place and truncates it to the decoded length,
+/// which is returned. Decoding never grows the data, so this doesn't
+/// allocate.
+///
+/// `data` doesn't need to be valid UTF-8 and the decoded bytes aren't checked
+/// either. On error `data` is left unchanged; a byte that isn't a hex digit is
+/// reported as the `char` of the same value.
+pub fn decode_in_place(data: &mut Vec<u8>) -> Result<usize, FromUrlEncodingError> {
+    // Validate everything first so errors leave the buffer untouched
+    for (idx, &b) in data.iter().enumerate() {
+        if b != b'%' {
+            continue;
+        }
+        for i in idx + 1..idx + 3 {
+            match data.get(i) {
+                Some(c) if c.is_ascii_hexdigit() => {},
+                Some(&c) => return Err(FromUrlEncodingError::UriCharacterError {
+                    character: c as char,
+                    index: i,
+                }),
+                None => return Err(FromUrlEncodingError::UriCharacterError {
+                    character: '%',
+                    index: idx,
+                }),
             }
         }
     }
-    String::from_utf8(unescaped_bytes).or_else(|e| Err(FromUrlEncodingError::Utf8CharacterError {
+
+    let mut read = 0;
+    let mut written = 0;
+    while read < data.len() {
+        if data[read] == b'%' {
+            // Validated above, so both hex digits are present
+            let high = hex_value(data[read + 1]).unwrap_or(0);
+            let low = hex_value(data[read + 2]).unwrap_or(0);
+            data[written] = high << 4 | low;
+            read += 3;
+        } else {
+            data[written] = data[read];
+            read += 1;
+        }
+        written += 1;
+    }
+    data.truncate(written);
+    Ok(written)
+}
+
+/// Percent-decodes a `String` in place, like `decode_in_place`, and checks
+/// the result is valid UTF-8.
+///
+/// On a percent-encoding error `data` is left unchanged. If the decoded bytes
+/// aren't valid UTF-8, they are returned inside the error and `data` is left
+/// empty.
+pub fn decode_in_place_str(data: &mut String) -> Result<usize, FromUrlEncodingError> {
+    validate_urlencoded_str(data)?;
+    let mut bytes = mem::take(data).into_bytes();
+    let len = decode_in_place(&mut bytes)?;
+    *data = String::from_utf8(bytes).map_err(|e| FromUrlEncodingError::Utf8CharacterError {
         error: e,
-    }))
+    })?;
+    Ok(len)
+}
+
+// Percent-decodes `data` into raw bytes, keeping any '%' that isn't followed
+// by 2 hex digits as-is instead of failing.
+#[cfg(any(feature = "query", feature = "unicode-security", feature = "uri"))]
+pub(crate) fn decode_bytes_lenient(data: &str) -> Vec<u8> {
+    decoder::decode_bytes_recovering(data, ErrorRecovery::PassThrough, false).unwrap_or_default()
+}
+
+/// Shortens encoded `data` to at most `max_len` bytes without cutting a
+/// `%XX` triplet in half.
+///
+/// A character is kept or dropped whole, whether it appears raw or as the
+/// run of triplets that encodes its UTF-8 bytes, so the result still decodes
+/// to a prefix of what `data` decodes to.
+pub fn truncate_encoded(data: &str, max_len: usize) -> &str {
+    let bytes = data.as_bytes();
+    let mut end = 0;
+    while end < bytes.len() {
+        let unit = encoded_char_len(data, end);
+        if end + unit > max_len {
+            break;
+        }
+        end += unit;
+    }
+    &data[..end]
+}
+
+// Returns the length of the encoded character starting at byte `i` of
+// `data`: a raw character, or the triplets encoding one UTF-8 sequence.
+fn encoded_char_len(data: &str, i: usize) -> usize {
+    let lead = match triplet_value(data.as_bytes(), i) {
+        Some(lead) => lead,
+        None => return data[i..].chars().next().map_or(1, char::len_utf8),
+    };
+    let continuations = match lead {
+        0xC0..=0xDF => 1,
+        0xE0..=0xEF => 2,
+        0xF0..=0xF7 => 3,
+        _ => 0,
+    };
+    let mut len = 3;
+    for _ in 0..continuations {
+        match triplet_value(data.as_bytes(), i + len) {
+            Some(b) if b & 0xC0 == 0x80 => len += 3,
+            _ => break,
+        }
+    }
+    len
+}
+
+// Returns the byte a `%XX` triplet at `i` encodes.
+pub(crate) fn triplet_value(bytes: &[u8], i: usize) -> Option<u8> {
+    if bytes.get(i) != Some(&b'%') {
+        return None;
+    }
+    let high = bytes.get(i + 1).and_then(|&b| hex_value(b))?;
+    let low = bytes.get(i + 2).and_then(|&b| hex_value(b))?;
+    Some(high << 4 | low)
+}
+
+// Returns the value of a single hex digit.
+pub(crate) fn hex_value(b: u8) -> Option<u8> {
+    (b as char).to_digit(16).map(|d| d as u8)
 }
 
 // Validates every '%' character is followed by exactly 2 hex
 // digits.
 fn validate_urlencoded_str(data: &str) -> Result<(), FromUrlEncodingError> {
-    let mut iter = data.char_indices();
-    while let Some((idx, chr)) = iter.next() {
-        match chr {
-            '%' => {
-                validate_percent_encoding(&mut iter, idx)?;
-            },
-            _ => continue,
+    for (idx, b) in data.bytes().enumerate() {
+        if b == b'%' {
+            decode_triplet(data, idx)?;
         }
     }
     Ok(())
 }
 
-// Validates the next two characters returned by the provided iterator are
-// hexadecimal digits.
-fn validate_percent_encoding(iter: &mut str::CharIndices, current_idx: usize) -> Result<(), FromUrlEncodingError> {
-    for _ in 0..2 {
-        match iter.next() {
-            // Only hex digits are valid
-            Some((_, c)) if c.is_digit(16) => {
-                continue
+// Decodes the '%' at byte `idx` of `data` and the 2 hex digits after it.
+//
+// A character that isn't a hex digit is reported whole, even if it isn't
+// ASCII. If the input ends first, the '%' itself is reported.
+pub(crate) fn decode_triplet(data: &str, idx: usize) -> Result<u8, FromUrlEncodingError> {
+    let mut value = 0;
+    for i in idx + 1..idx + 3 {
+        // Only ASCII has been skipped since `idx`, so `i` is a char boundary
+        match data.get(i..).and_then(|rest| rest.chars().next()) {
+            Some(c) if c.is_ascii_hexdigit() => {
+                value = value << 4 | hex_value(c as u8).unwrap_or(0);
             },
-            Some((i, c)) => return Err(FromUrlEncodingError::UriCharacterError {
+            Some(c) => return Err(FromUrlEncodingError::UriCharacterError {
                 character: c,
                 index: i,
             }),
             // We got a '%' without 2 characters after it, so mark the '%' as bad
             None => return Err(FromUrlEncodingError::UriCharacterError {
                 character: '%',
-                index: current_idx,
+                index: idx,
             }),
         }
     }
-    Ok(())
+    Ok(value)
 }
 
 #[derive(Debug)]
@@ -83,10 +549,53 @@ pub enum FromUrlEncodingError {
     Utf8CharacterError { error: FromUtf8Error },
 }
 
+impl fmt::Display for FromUrlEncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FromUrlEncodingError::UriCharacterError { character, index } => {
+                write!(f, "invalid character {:?} at offset {}", character, index)
+            },
+            FromUrlEncodingError::Utf8CharacterError { error } => write!(f, "decoded bytes are not UTF-8: {}", error),
+        }
+    }
+}
+
+impl Error for FromUrlEncodingError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FromUrlEncodingError::UriCharacterError { .. } => None,
+            FromUrlEncodingError::Utf8CharacterError { error } => Some(error),
+        }
+    }
+}
+
+// `FromUtf8Error` has no `defmt::Format`, so only the offset of the invalid
+// UTF-8 is logged
+#[cfg(feature = "defmt")]
+impl defmt::Format for FromUrlEncodingError {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            FromUrlEncodingError::UriCharacterError { character, index } => {
+                defmt::write!(f, "UriCharacterError {{ character: {}, index: {} }}", character, index)
+            },
+            FromUrlEncodingError::Utf8CharacterError { error } => {
+                defmt::write!(f, "Utf8CharacterError {{ valid_up_to: {} }}", error.utf8_error().valid_up_to())
+            },
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::encode;
+    use super::encode_idempotent;
+    use super::encode_non_ascii_only;
     use super::decode;
+    use super::decode_in_place;
+    use super::decode_in_place_str;
+    use super::decode_iter;
+    use super::decode_to_vec;
+    use super::truncate_encoded;
     use super::FromUrlEncodingError;
 
     #[test]
@@ -167,4 +676,176 @@ mod tests {
             _ => panic!()
         }
     }
+
+    #[test]
+    fn it_decodes_to_vec_successfully() {
+        let mut out = Vec::new();
+        decode_to_vec("this%20that", &mut out).unwrap();
+        assert_eq!(b"this that", &out[..]);
+
+        // Invalid UTF-8 is kept as-is
+        decode_to_vec("%FF%00", &mut out).unwrap();
+        assert_eq!(&[0xFF, 0x00], &out[..]);
+    }
+
+    #[test]
+    fn it_decodes_to_vec_reusing_capacity() {
+        let mut out = Vec::with_capacity(64);
+        let ptr = out.as_ptr();
+        for encoded in &["a%20b", "%F0%9F%91%BE", "plain"] {
+            decode_to_vec(encoded, &mut out).unwrap();
+            assert_eq!(ptr, out.as_ptr());
+        }
+        assert_eq!(b"plain", &out[..]);
+    }
+
+    #[test]
+    fn it_decodes_to_vec_unsuccessfully() {
+        let mut out = b"previous".to_vec();
+        assert!(decode_to_vec("this%2that", &mut out).is_err());
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn it_decodes_iter_successfully() {
+        let bytes: Vec<u8> = decode_iter("a%20b%F0%9F%91%BE%ff").map(Result::unwrap).collect();
+        assert_eq!("a b👾".as_bytes(), &bytes[..bytes.len() - 1]);
+        assert_eq!(Some(&0xFF), bytes.last());
+        assert_eq!(None, decode_iter("").next().map(Result::unwrap));
+    }
+
+    #[test]
+    fn it_decodes_iter_unsuccessfully() {
+        let mut iter = decode_iter("ab%2xyz");
+        assert_eq!(Some(b'a'), iter.next().map(Result::unwrap));
+        assert_eq!(Some(b'b'), iter.next().map(Result::unwrap));
+        match iter.next() {
+            Some(Err(FromUrlEncodingError::UriCharacterError { character: 'x', index: 4 })) => {},
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn it_decodes_in_place_successfully() {
+        let mut data = b"this%20that%FF".to_vec();
+        let capacity = data.capacity();
+        assert_eq!(10, decode_in_place(&mut data).unwrap());
+        assert_eq!(b"this that\xFF", &data[..]);
+        assert_eq!(capacity, data.capacity());
+    }
+
+    #[test]
+    fn it_decodes_in_place_unsuccessfully() {
+        let mut data = b"this%2that".to_vec();
+        match decode_in_place(&mut data).unwrap_err() {
+            FromUrlEncodingError::UriCharacterError { index: i, character: c } => {
+                assert_eq!(6, i);
+                assert_eq!('t', c)
+            },
+            _ => panic!()
+        }
+        assert_eq!(b"this%2that", &data[..]);
+
+        let mut data = b"this%20that%2".to_vec();
+        match decode_in_place(&mut data).unwrap_err() {
+            FromUrlEncodingError::UriCharacterError { index: i, character: c } => {
+                assert_eq!(11, i);
+                assert_eq!('%', c)
+            },
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn it_decodes_in_place_str_successfully() {
+        let mut data = String::from("%F0%9F%91%BE%20Exterminate%21");
+        assert_eq!(17, decode_in_place_str(&mut data).unwrap());
+        assert_eq!("👾 Exterminate!", data);
+    }
+
+    #[test]
+    fn it_decodes_in_place_str_unsuccessfully() {
+        let mut data = String::from("this%2that");
+        assert!(decode_in_place_str(&mut data).is_err());
+        assert_eq!("this%2that", data);
+
+        let mut data = String::from("%FF");
+        match decode_in_place_str(&mut data).unwrap_err() {
+            FromUrlEncodingError::Utf8CharacterError { error: e } => assert_eq!(vec![0xFF], e.into_bytes()),
+            _ => panic!()
+        }
+        assert!(data.is_empty());
+    }
+
+    #[test]
+    fn it_truncates_encoded_successfully() {
+        assert_eq!("this%20that", truncate_encoded("this%20that", 11));
+        assert_eq!("this%20", truncate_encoded("this%20that", 7));
+        assert_eq!("this", truncate_encoded("this%20that", 6));
+        assert_eq!("", truncate_encoded("this", 0));
+    }
+
+    #[test]
+    fn it_truncates_encoded_without_splitting_characters() {
+        // The '€' is 3 triplets and the '👾' is 4 raw bytes
+        assert_eq!("a", truncate_encoded("a%E2%82%ACb", 9));
+        assert_eq!("a%E2%82%AC", truncate_encoded("a%E2%82%ACb", 10));
+        assert_eq!("a", truncate_encoded("a👾b", 4));
+        assert_eq!("a👾", truncate_encoded("a👾b", 5));
+    }
+
+    #[test]
+    fn it_truncates_encoded_malformed_input() {
+        assert_eq!("100%", truncate_encoded("100%zz", 4));
+        assert_eq!("%E2", truncate_encoded("%E2x", 3));
+    }
+
+    #[test]
+    fn it_decodes_adversarial_input_without_panicking() {
+        // Every string of up to 4 characters over an alphabet of escapes,
+        // hex digits, non-hex ASCII and multi-byte characters
+        let alphabet = ["%", "0", "f", "F", "g", "é", "👾"];
+        let mut inputs = vec![String::new()];
+        for _ in 0..4 {
+            let longer: Vec<String> = inputs.iter()
+                .flat_map(|input| alphabet.iter().map(move |c| format!("{}{}", input, c)))
+                .collect();
+            inputs.extend(longer);
+        }
+        let mut out = Vec::new();
+        for input in &inputs {
+            let well_formed = input.match_indices('%').all(|(i, _)| {
+                input.as_bytes().get(i + 1..i + 3).is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            });
+            match decode_to_vec(input, &mut out) {
+                Ok(()) => assert!(well_formed),
+                Err(FromUrlEncodingError::UriCharacterError { index, character }) => {
+                    assert!(!well_formed);
+                    assert!(input[index..].starts_with(character));
+                },
+                Err(_) => panic!()
+            }
+        }
+    }
+
+    #[test]
+    fn it_encodes_idempotent_successfully() {
+        assert_eq!("this%20that%2Fand%20more", encode_idempotent("this%20that/and more"));
+        assert_eq!("100%25%20sure%25", encode_idempotent("100% sure%"));
+        assert_eq!("%252x%252x", encode_idempotent("%2x%252x"));
+    }
+
+    #[test]
+    fn it_encodes_idempotent_encoded_input_unchanged() {
+        let encoded = encode("👾 Exterminate! 100%");
+        assert_eq!(encoded, encode_idempotent(&encoded));
+        assert_eq!(encoded, encode_idempotent(&encode_idempotent("👾 Exterminate! 100%")));
+    }
+
+    #[test]
+    fn it_encodes_non_ascii_only() {
+        assert_eq!("https://example.com/caf%C3%A9?q=a b&x=%41", encode_non_ascii_only("https://example.com/café?q=a b&x=%41"));
+        assert_eq!("%F0%9F%91%BE Exterminate!", encode_non_ascii_only("👾 Exterminate!"));
+    }
 }
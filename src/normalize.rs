@@ -0,0 +1,192 @@
+use super::{elide_default_port, is_unreserved, normalize_hex_case, triplet_value, HexCase, UriParts};
+
+/// Normalizes URLs following RFC 3986, section 6, so equivalent URLs
+/// compare equal as strings. Every step is on by default and can be turned
+/// off on its own:
+///
+/// - case: the scheme and host are lowercased, and the hex digits of
+///   escapes uppercased,
+/// - default ports: a port that is the default for the scheme, as given by
+///   `default_port`, is removed,
+/// - dot segments: `.` and `..` segments are removed from an absolute path,
+/// - percent-encoding: escapes of unreserved characters, such as `%41`,
+///   are decoded.
+///
+/// ```
+/// use urlencoding::UrlNormalizer;
+///
+/// let normalizer = UrlNormalizer::new();
+/// assert_eq!(
+///     "https://example.com/a/c%2F~d?q=%C3%A9",
+///     normalizer.normalize("HTTPS://Example.COM:443/a/./b/../c%2f%7Ed?q=%c3%a9"),
+/// );
+/// assert_eq!(
+///     "https://example.com:443/a/./b",
+///     normalizer.default_ports(false).dot_segments(false).normalize("https://example.com:443/a/./b"),
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UrlNormalizer {
+    case: bool,
+    default_ports: bool,
+    dot_segments: bool,
+    percent_encoding: bool,
+}
+
+impl UrlNormalizer {
+    /// Creates a normalizer with every step turned on.
+    pub fn new() -> UrlNormalizer {
+        UrlNormalizer {
+            case: true,
+            default_ports: true,
+            dot_segments: true,
+            percent_encoding: true,
+        }
+    }
+
+    /// Lowercases the scheme and host, and uppercases hex digits.
+    pub fn case(mut self, case: bool) -> UrlNormalizer {
+        self.case = case;
+        self
+    }
+
+    /// Removes the port if it is the default one for the scheme.
+    pub fn default_ports(mut self, default_ports: bool) -> UrlNormalizer {
+        self.default_ports = default_ports;
+        self
+    }
+
+    /// Removes `.` and `..` segments from the path.
+    pub fn dot_segments(mut self, dot_segments: bool) -> UrlNormalizer {
+        self.dot_segments = dot_segments;
+        self
+    }
+
+    /// Decodes escapes of unreserved characters.
+    pub fn percent_encoding(mut self, percent_encoding: bool) -> UrlNormalizer {
+        self.percent_encoding = percent_encoding;
+        self
+    }
+
+    /// Normalizes `url`. Malformed escapes are left as they are, so this
+    /// never fails.
+    pub fn normalize(&self, url: &str) -> String {
+        // Unreserved characters are never delimiters, so decoding them first
+        // can't change how `url` splits, and lets the other steps see them
+        let decoded;
+        let url = if self.percent_encoding {
+            decoded = decode_unreserved(url);
+            &decoded
+        } else {
+            url
+        };
+        let mut parts = UriParts::parse(url);
+        if self.case {
+            parts.scheme = parts.scheme.map(|scheme| scheme.to_ascii_lowercase());
+            parts.authority = parts.authority.map(|authority| lowercase_host(&authority));
+        }
+        if self.dot_segments && parts.path.starts_with('/') {
+            parts.path = remove_dot_segments(&parts.path);
+        }
+        let mut normalized = parts.to_string();
+        if self.case {
+            normalized = normalize_hex_case(&normalized, HexCase::Upper).into_owned();
+        }
+        if self.default_ports {
+            normalized = elide_default_port(&normalized).into_owned();
+        }
+        normalized
+    }
+}
+
+impl Default for UrlNormalizer {
+    fn default() -> UrlNormalizer {
+        UrlNormalizer::new()
+    }
+}
+
+// Lowercases the host of an authority, leaving the userinfo alone.
+fn lowercase_host(authority: &str) -> String {
+    let host_start = authority.rfind('@').map(|idx| idx + 1).unwrap_or(0);
+    format!("{}{}", &authority[..host_start], authority[host_start..].to_ascii_lowercase())
+}
+
+// Decodes the escapes of unreserved characters.
+fn decode_unreserved(data: &str) -> String {
+    let bytes = data.as_bytes();
+    let mut decoded = String::with_capacity(data.len());
+    let mut copied = 0;
+    for i in 0..bytes.len() {
+        match triplet_value(bytes, i) {
+            Some(b) if is_unreserved(b) => {
+                decoded.push_str(&data[copied..i]);
+                decoded.push(b as char);
+                copied = i + 3;
+            },
+            _ => {},
+        }
+    }
+    decoded.push_str(&data[copied..]);
+    decoded
+}
+
+// The remove_dot_segments algorithm of RFC 3986, section 5.2.4, for an
+// absolute path.
+fn remove_dot_segments(path: &str) -> String {
+    let mut output: Vec<&str> = Vec::new();
+    let mut segments = path[1..].split('/').peekable();
+    while let Some(segment) = segments.next() {
+        let is_last = segments.peek().is_none();
+        match segment {
+            "." | ".." => {
+                if segment == ".." {
+                    output.pop();
+                }
+                // "/a/." and "/a/.." keep their trailing '/'
+                if is_last {
+                    output.push("");
+                }
+            },
+            _ => output.push(segment),
+        }
+    }
+    format!("/{}", output.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::remove_dot_segments;
+    use super::UrlNormalizer;
+
+    #[test]
+    fn it_removes_dot_segments() {
+        assert_eq!("/a/g", remove_dot_segments("/a/b/c/./../../g"));
+        assert_eq!("/", remove_dot_segments("/.."));
+        assert_eq!("/", remove_dot_segments("/a/.."));
+        assert_eq!("/a/", remove_dot_segments("/a/."));
+        assert_eq!("/g", remove_dot_segments("/../../g"));
+        assert_eq!("/a/..b/.c", remove_dot_segments("/a/..b/.c"));
+        assert_eq!("//a", remove_dot_segments("//a"));
+    }
+
+    #[test]
+    fn it_normalizes_urls() {
+        let normalizer = UrlNormalizer::new();
+        assert_eq!("http://User@example.com/", normalizer.normalize("HTTP://User@EXAMPLE.com:80/"));
+        assert_eq!("http://example.com/b", normalizer.normalize("http://example.com/a/%2E%2E/b"));
+        assert_eq!("http://example.com/", normalizer.normalize("http://ex%41mple.com/"));
+        assert_eq!(normalizer.normalize("HTTP://EXAMPLE.com/"), normalizer.normalize("http://%45xample.COM/"));
+        assert_eq!("/a%2Fb%ZZ", normalizer.normalize("/a%2fb%ZZ"));
+        assert_eq!("../a/./b", normalizer.normalize("../a/./b"));
+        assert_eq!("mailto:Joe@Example.COM", normalizer.normalize("MAILTO:Joe@Example.COM"));
+        assert_eq!("http://example.com/?a=b#~x", normalizer.normalize("http://example.com/?a=%62#%7ex"));
+    }
+
+    #[test]
+    fn it_normalizes_urls_with_steps_turned_off() {
+        let url = "HTTP://Example.com:80/a/../%7e%2f";
+        assert_eq!("HTTP://Example.com:80/~%2f", UrlNormalizer::new().case(false).default_ports(false).normalize(url));
+        assert_eq!("http://example.com/a/../~%2F", UrlNormalizer::new().dot_segments(false).normalize(url));
+        assert_eq!("http://example.com/%7E%2F", UrlNormalizer::new().percent_encoding(false).normalize(url));
+    }
+}
@@ -0,0 +1,116 @@
+use super::encode;
+
+/// Percent-encodes a parameter key or value as required by OAuth 1.0a
+/// (RFC 5849, section 3.6) before it is used in a signature.
+///
+/// Only the unreserved characters `ALPHA`, `DIGIT`, `-`, `.`, `_` and `~` are
+/// left alone; every other byte of the UTF-8 input is encoded as `%XX` with
+/// uppercase hex digits.
+pub fn encode_oauth1(data: &str) -> String {
+    // The RFC 5849 unreserved set is exactly the set `encode` leaves alone
+    encode(data)
+}
+
+/// Builds the normalized request parameters string (RFC 5849, section
+/// 3.4.1.3.2) from decoded `(name, value)` pairs.
+///
+/// Every name and value is encoded with `encode_oauth1`, the pairs are sorted
+/// by encoded name and then by encoded value, and joined as `name=value`
+/// separated by `&`. The `oauth_signature` parameter must not be included.
+pub fn normalize_oauth1_parameters(params: &[(&str, &str)]) -> String {
+    let mut encoded: Vec<(String, String)> = params
+        .iter()
+        .map(|&(name, value)| (encode_oauth1(name), encode_oauth1(value)))
+        .collect();
+    encoded.sort();
+
+    let mut normalized = String::new();
+    for (i, (name, value)) in encoded.iter().enumerate() {
+        if i > 0 {
+            normalized.push('&');
+        }
+        normalized.push_str(name);
+        normalized.push('=');
+        normalized.push_str(value);
+    }
+    normalized
+}
+
+/// Builds the OAuth 1.0a signature base string (RFC 5849, section 3.4.1).
+///
+/// `base_uri` is the base string URI (scheme, host, non-default port and
+/// path, without query or fragment) and `params` holds every decoded request
+/// parameter from the query, the form body and the OAuth protocol parameters.
+pub fn signature_base_string(method: &str, base_uri: &str, params: &[(&str, &str)]) -> String {
+    let mut base = method.to_ascii_uppercase();
+    base.push('&');
+    base.push_str(&encode_oauth1(base_uri));
+    base.push('&');
+    base.push_str(&encode_oauth1(&normalize_oauth1_parameters(params)));
+    base
+}
+
+#[cfg(test)]
+mod tests {
+    use super::encode_oauth1;
+    use super::normalize_oauth1_parameters;
+    use super::signature_base_string;
+
+    // Request parameters from the example in RFC 5849, section 3.4.1.1
+    const RFC_PARAMS: &[(&str, &str)] = &[
+        ("b5", "=%3D"),
+        ("a3", "a"),
+        ("c@", ""),
+        ("a2", "r b"),
+        ("oauth_consumer_key", "9djdj82h48djs9d2"),
+        ("oauth_token", "kkk9d7dh3k39sjv7"),
+        ("oauth_signature_method", "HMAC-SHA1"),
+        ("oauth_timestamp", "137131201"),
+        ("oauth_nonce", "7d8f3e4a"),
+        ("c2", ""),
+        ("a3", "2 q"),
+    ];
+
+    #[test]
+    fn it_encodes_oauth1_unreserved_characters_unchanged() {
+        let unreserved = "ABCXYZabcxyz0189-._~";
+        assert_eq!(unreserved, encode_oauth1(unreserved));
+    }
+
+    #[test]
+    fn it_encodes_oauth1_reserved_characters() {
+        // Characters some encoders handle differently: space, '+', '*' and '!'
+        assert_eq!("2%20q", encode_oauth1("2 q"));
+        assert_eq!("a%2Bb", encode_oauth1("a+b"));
+        assert_eq!("%2A%21%27%28%29", encode_oauth1("*!'()"));
+        assert_eq!("c%40", encode_oauth1("c@"));
+    }
+
+    #[test]
+    fn it_encodes_oauth1_with_uppercase_hex() {
+        assert_eq!("%3D%25%3D", encode_oauth1("=%="));
+        assert_eq!("%E2%82%AC", encode_oauth1("€"));
+    }
+
+    #[test]
+    fn it_normalizes_oauth1_parameters() {
+        let expected = "a2=r%20b&a3=2%20q&a3=a&b5=%3D%253D&c%40=&c2=&oauth_consumer_key=9djdj82h48djs9d2\
+                        &oauth_nonce=7d8f3e4a&oauth_signature_method=HMAC-SHA1&oauth_timestamp=137131201\
+                        &oauth_token=kkk9d7dh3k39sjv7";
+        assert_eq!(expected, normalize_oauth1_parameters(RFC_PARAMS));
+    }
+
+    #[test]
+    fn it_builds_oauth1_signature_base_string() {
+        let expected = "POST&http%3A%2F%2Fexample.com%2Frequest&a2%3Dr%2520b%26a3%3D2%2520q%26a3%3Da\
+                        %26b5%3D%253D%25253D%26c%2540%3D%26c2%3D%26oauth_consumer_key%3D9djdj82h48djs9d2\
+                        %26oauth_nonce%3D7d8f3e4a%26oauth_signature_method%3DHMAC-SHA1\
+                        %26oauth_timestamp%3D137131201%26oauth_token%3Dkkk9d7dh3k39sjv7";
+        assert_eq!(expected, signature_base_string("post", "http://example.com/request", RFC_PARAMS));
+    }
+
+    #[test]
+    fn it_builds_oauth1_signature_base_string_without_parameters() {
+        assert_eq!("GET&https%3A%2F%2Fexample.com%2F&", signature_base_string("GET", "https://example.com/", &[]));
+    }
+}
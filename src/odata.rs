@@ -0,0 +1,59 @@
+use super::{AsciiSet, EncodeSet};
+
+impl AsciiSet {
+    /// The characters left unencoded in the value of an OData system query
+    /// option such as `$filter`: the unreserved characters plus
+    /// `!$'()*,:;=?@/`. `&`, `#` and `+` are encoded, as are spaces, which
+    /// become `%20`.
+    pub const fn odata_query() -> AsciiSet {
+        AsciiSet::unreserved().union(AsciiSet::from_bytes(b"!$'()*,:;=?@/"))
+    }
+}
+
+/// Percent-encodes the value of an OData query option, such as the
+/// expression of `$filter` or the field list of `$select`, with
+/// `AsciiSet::odata_query`.
+///
+/// The quotes around string literals, the `$` of `$it` or `$root` and the
+/// parentheses of function calls stay readable, while a `+` is encoded as
+/// `%2B`, because many servers read a bare `+` as a space. Quotes inside a
+/// string literal must be doubled first, which `odata_string` does.
+///
+/// ```
+/// use urlencoding::{encode_odata_query, odata_string};
+///
+/// let filter = format!("LastName eq {} and Age gt 21", odata_string("O'Neil"));
+/// assert_eq!(
+///     "LastName%20eq%20'O''Neil'%20and%20Age%20gt%2021",
+///     encode_odata_query(&filter),
+/// );
+/// ```
+pub fn encode_odata_query(value: &str) -> String {
+    AsciiSet::odata_query().encode(value)
+}
+
+/// Writes `value` as an OData string literal: in single quotes, with every
+/// single quote in it doubled.
+pub fn odata_string(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::encode_odata_query;
+    use super::odata_string;
+
+    #[test]
+    fn it_encodes_odata_query() {
+        assert_eq!("Name,Price", encode_odata_query("Name,Price"));
+        assert_eq!("contains(Name,'a%26b')%20or%20$it/Price%20le%201%2B1", encode_odata_query("contains(Name,'a&b') or $it/Price le 1+1"));
+        assert_eq!("'%23tag'%20eq%20'%C3%A9'", encode_odata_query("'#tag' eq 'é'"));
+        assert_eq!("@p1=:x;y?z!*", encode_odata_query("@p1=:x;y?z!*"));
+    }
+
+    #[test]
+    fn it_writes_odata_strings() {
+        assert_eq!("''", odata_string(""));
+        assert_eq!("'it''s'''", odata_string("it's'"));
+    }
+}
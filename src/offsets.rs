@@ -0,0 +1,235 @@
+use std::iter::FusedIterator;
+use std::ops::Range;
+use std::str;
+
+use super::{decode_triplet, triplet_value, FromUrlEncodingError};
+
+/// Maps byte offsets in a decoded string back to offsets in the encoded
+/// string it came from, as returned by `decode_with_offsets`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OffsetMap {
+    // The encoded offset of every decoded byte, then the encoded length
+    offsets: Vec<usize>,
+}
+
+impl OffsetMap {
+    /// Returns the offset in the encoded string of the byte at `decoded` in
+    /// the decoded string. The end of the decoded string maps to the end of
+    /// the encoded one, and so do offsets past it.
+    pub fn encoded_offset(&self, decoded: usize) -> usize {
+        self.offsets[decoded.min(self.offsets.len() - 1)]
+    }
+
+    /// Returns the range of the encoded string that decoded to the bytes in
+    /// `decoded`, so that a `%XX` escape is covered whole.
+    pub fn encoded_range(&self, decoded: Range<usize>) -> Range<usize> {
+        let start = self.encoded_offset(decoded.start);
+        if decoded.end <= decoded.start {
+            return start..start;
+        }
+        start..self.encoded_offset(decoded.end)
+    }
+}
+
+/// Percent-decodes `data` like `decode`, also returning where each decoded
+/// byte came from, so a parser working on the decoded string can report a
+/// problem at its position in the original URL.
+///
+/// ```
+/// use urlencoding::decode_with_offsets;
+///
+/// let (decoded, offsets) = decode_with_offsets("name=J%C3%B6rg%20%3Cb%3E").unwrap();
+/// assert_eq!("name=Jörg <b>", decoded);
+/// let bad = decoded.find('<').unwrap();
+/// assert_eq!(17, offsets.encoded_offset(bad));
+/// assert_eq!(6..12, offsets.encoded_range(6..8));
+/// ```
+pub fn decode_with_offsets(data: &str) -> Result<(String, OffsetMap), FromUrlEncodingError> {
+    let bytes = data.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut offsets = Vec::with_capacity(bytes.len() + 1);
+    let mut i = 0;
+    while i < bytes.len() {
+        offsets.push(i);
+        if bytes[i] == b'%' {
+            decoded.push(decode_triplet(data, i)?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    offsets.push(bytes.len());
+    let decoded = String::from_utf8(decoded).map_err(|e| FromUrlEncodingError::Utf8CharacterError { error: e })?;
+    Ok((decoded, OffsetMap { offsets }))
+}
+
+/// One decoded character and the part of the encoded string it came from,
+/// as yielded by `decode_spans`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedSpan<'a> {
+    /// The byte range of the span in the encoded string.
+    pub range: Range<usize>,
+    /// The encoded text of the span, such as `a` or `%C3%A9`.
+    pub encoded: &'a str,
+    /// The decoded character, or `None` if the escapes of the span decode
+    /// to bytes that aren't valid UTF-8, such as `%FF` or a `%C3` that no
+    /// continuation byte follows.
+    pub decoded: Option<char>,
+}
+
+/// Splits `data` into the spans that each decode to one character, for
+/// diagnostics that highlight exactly which `%XX` sequence is wrong.
+///
+/// Bytes that aren't valid UTF-8 don't end the iteration: they come as
+/// spans without a character, holding the escapes of one invalid sequence.
+/// A `%` that isn't followed by 2 hex digits yields an error instead, after
+/// which the iterator ends.
+///
+/// ```
+/// use urlencoding::decode_spans;
+///
+/// let invalid: Vec<_> = decode_spans("caf%C3%A9%20%C3!")
+///     .map(Result::unwrap)
+///     .filter(|span| span.decoded.is_none())
+///     .collect();
+/// assert_eq!(1, invalid.len());
+/// assert_eq!((12..15, "%C3"), (invalid[0].range.clone(), invalid[0].encoded));
+/// ```
+pub fn decode_spans(data: &str) -> DecodeSpans<'_> {
+    DecodeSpans { data, idx: 0 }
+}
+
+/// The iterator returned by `decode_spans`.
+#[derive(Debug, Clone)]
+pub struct DecodeSpans<'a> {
+    data: &'a str,
+    idx: usize,
+}
+
+impl<'a> DecodeSpans<'a> {
+    fn span(&mut self, end: usize, decoded: Option<char>) -> DecodedSpan<'a> {
+        let range = self.idx..end;
+        self.idx = end;
+        DecodedSpan { encoded: &self.data[range.clone()], range, decoded }
+    }
+}
+
+impl<'a> Iterator for DecodeSpans<'a> {
+    type Item = Result<DecodedSpan<'a>, FromUrlEncodingError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.idx;
+        let c = self.data[start..].chars().next()?;
+        if c != '%' {
+            return Some(Ok(self.span(start + c.len_utf8(), Some(c))));
+        }
+        let first = match decode_triplet(self.data, start) {
+            Ok(b) => b,
+            Err(e) => {
+                self.idx = self.data.len();
+                return Some(Err(e));
+            },
+        };
+        // The number of bytes the lead byte announces; a byte that can't
+        // start a character is an invalid sequence of its own
+        let len = match first {
+            0x00..=0x7F => return Some(Ok(self.span(start + 3, Some(first as char)))),
+            0xC2..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF4 => 4,
+            _ => return Some(Ok(self.span(start + 3, None))),
+        };
+        let bytes = self.data.as_bytes();
+        let mut sequence = vec![first];
+        while sequence.len() < len {
+            match triplet_value(bytes, start + 3 * sequence.len()) {
+                Some(b) => sequence.push(b),
+                None => break,
+            }
+        }
+        match str::from_utf8(&sequence) {
+            Ok(decoded) => Some(Ok(self.span(start + 3 * len, decoded.chars().next()))),
+            Err(e) => {
+                let invalid_len = e.error_len().unwrap_or(sequence.len());
+                Some(Ok(self.span(start + 3 * invalid_len, None)))
+            },
+        }
+    }
+}
+
+impl<'a> FusedIterator for DecodeSpans<'a> {}
+
+#[cfg(test)]
+mod tests {
+    use super::decode_spans;
+    use super::decode_with_offsets;
+    use super::DecodedSpan;
+    use super::FromUrlEncodingError;
+
+    #[test]
+    fn it_decodes_with_offsets() {
+        let (decoded, offsets) = decode_with_offsets("a%20b%E2%82%ACc").unwrap();
+        assert_eq!("a b€c", decoded);
+        let expected = [0, 1, 4, 5, 8, 11, 14, 15];
+        for (decoded, &encoded) in expected.iter().enumerate() {
+            assert_eq!(encoded, offsets.encoded_offset(decoded));
+        }
+        assert_eq!(15, offsets.encoded_offset(100));
+        assert_eq!(5..14, offsets.encoded_range(3..6));
+        assert_eq!(4..4, offsets.encoded_range(2..2));
+    }
+
+    #[test]
+    fn it_decodes_with_offsets_empty() {
+        let (decoded, offsets) = decode_with_offsets("").unwrap();
+        assert_eq!("", decoded);
+        assert_eq!(0, offsets.encoded_offset(0));
+        assert_eq!(0..0, offsets.encoded_range(0..1));
+    }
+
+    #[test]
+    fn it_decodes_with_offsets_unsuccessfully() {
+        assert!(matches!(
+            decode_with_offsets("ab%2x"),
+            Err(FromUrlEncodingError::UriCharacterError { character: 'x', index: 4 })
+        ));
+        assert!(matches!(decode_with_offsets("%FF"), Err(FromUrlEncodingError::Utf8CharacterError { .. })));
+    }
+
+    #[test]
+    fn it_decodes_spans() {
+        let spans: Vec<_> = decode_spans("é%41%e2%82%ac/").map(Result::unwrap).collect();
+        assert_eq!(
+            vec![
+                DecodedSpan { range: 0..2, encoded: "é", decoded: Some('é') },
+                DecodedSpan { range: 2..5, encoded: "%41", decoded: Some('A') },
+                DecodedSpan { range: 5..14, encoded: "%e2%82%ac", decoded: Some('€') },
+                DecodedSpan { range: 14..15, encoded: "/", decoded: Some('/') },
+            ],
+            spans
+        );
+    }
+
+    #[test]
+    fn it_decodes_spans_of_invalid_utf8() {
+        let invalid = |data| -> Vec<&str> {
+            decode_spans(data).map(Result::unwrap).filter(|span| span.decoded.is_none()).map(|span| span.encoded).collect()
+        };
+        assert_eq!(vec!["%FF", "%80"], invalid("%FF%80"));
+        assert_eq!(vec!["%C3"], invalid("%C3%41"));
+        assert_eq!(vec!["%E2%82"], invalid("%E2%82x"));
+        assert_eq!(vec!["%E2%82"], invalid("%E2%82"));
+        assert_eq!(vec!["%C0", "%AF"], invalid("%C0%AF"));
+        assert_eq!(vec!["%ED", "%A0", "%80"], invalid("%ED%A0%80"));
+        assert_eq!(vec!["%F0%9F"], invalid("%F0%9Fz"));
+    }
+
+    #[test]
+    fn it_decodes_spans_unsuccessfully() {
+        let mut spans = decode_spans("a%2xb");
+        assert!(matches!(spans.next(), Some(Ok(DecodedSpan { decoded: Some('a'), .. }))));
+        assert!(matches!(spans.next(), Some(Err(FromUrlEncodingError::UriCharacterError { character: 'x', index: 3 }))));
+        assert!(spans.next().is_none());
+    }
+}
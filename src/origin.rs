@@ -0,0 +1,120 @@
+use std::fmt;
+
+use super::{default_port, normalize_host, ParseMode, UriParts};
+
+/// The origin of a URL with an `http`, `https`, `ws`, `wss` or `ftp` scheme:
+/// the scheme, host and port that decide whether two URLs are same-origin.
+///
+/// The scheme is lowercase, the host is normalized with `normalize_host` and
+/// the port is `None` when it is the default port of the scheme, so two
+/// origins compare equal exactly when browsers treat them as the same.
+/// `Display` writes the origin the way the `Origin` header does.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Origin {
+    pub scheme: String,
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}://{}", self.scheme, self.host)?;
+        if let Some(port) = self.port {
+            write!(f, ":{}", port)?;
+        }
+        Ok(())
+    }
+}
+
+/// Computes the origin of `url`, following the WHATWG URL standard.
+///
+/// The URL is first cleaned up with `ParseMode::Browser`, like a browser
+/// would. A `blob:` URL takes the origin of the `http` or `https` URL it
+/// wraps. Every other scheme, including `file`, has an opaque origin, which
+/// isn't the same as any other origin, not even itself; for those, and for
+/// URLs without a valid host or port, `None` is returned.
+///
+/// ```
+/// use urlencoding::origin;
+///
+/// let origin = origin("HTTPS://user@Example.com:443/a?b").unwrap();
+/// assert_eq!("https://example.com", origin.to_string());
+/// assert_eq!(origin, urlencoding::origin("https://example.com/").unwrap());
+/// assert_eq!(None, urlencoding::origin("data:text/plain,hi"));
+/// ```
+pub fn origin(url: &str) -> Option<Origin> {
+    let parts = UriParts::parse_with(url, ParseMode::Browser);
+    let scheme = parts.scheme?.to_ascii_lowercase();
+    match scheme.as_str() {
+        "blob" => origin(&parts.path).filter(|inner| inner.scheme == "http" || inner.scheme == "https"),
+        "http" | "https" | "ws" | "wss" | "ftp" => tuple_origin(scheme, &parts.authority?),
+        _ => None,
+    }
+}
+
+fn tuple_origin(scheme: String, authority: &str) -> Option<Origin> {
+    let host_port = &authority[authority.rfind('@').map(|idx| idx + 1).unwrap_or(0)..];
+    let host_end = if host_port.starts_with('[') {
+        host_port.find(']')? + 1
+    } else {
+        host_port.rfind(':').unwrap_or(host_port.len())
+    };
+    let (host, port) = host_port.split_at(host_end);
+    if host.is_empty() {
+        return None;
+    }
+    let host = normalize_host(host).ok()?;
+    let port = match port.strip_prefix(':') {
+        Some("") => None,
+        Some(port) if port.bytes().all(|b| b.is_ascii_digit()) => Some(port.parse::<u16>().ok()?),
+        Some(_) => return None,
+        None if port.is_empty() => None,
+        None => return None,
+    };
+    let port = port.filter(|&port| Some(port) != default_port(&scheme));
+    Some(Origin { scheme, host, port })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::origin;
+    use super::Origin;
+
+    #[test]
+    fn it_computes_origins() {
+        let expected = Origin {
+            scheme: "https".to_string(),
+            host: "example.com".to_string(),
+            port: None,
+        };
+        for url in &["https://example.com", "HTTPS://EXAMPLE.com:443/a?b#c", "https://u:p@example.com:0443", "https://example.com:/", " https:\\\\example.com\\a"] {
+            assert_eq!(Some(&expected), origin(url).as_ref(), "{}", url);
+        }
+        assert_eq!(Some(8080), origin("http://example.com:8080").unwrap().port);
+        assert_eq!(Some(443), origin("http://example.com:443").unwrap().port);
+        assert_eq!("127.0.0.1", origin("ws://0x7f.1/socket").unwrap().host);
+        assert_eq!("wss://[::1]:8443", origin("wss://[0::1]:8443/").unwrap().to_string());
+        assert_eq!("ftp://example.com", origin("ftp://example.com:21/file").unwrap().to_string());
+    }
+
+    #[test]
+    fn it_computes_blob_origins() {
+        assert_eq!("https://example.com", origin("blob:https://example.com/uuid").unwrap().to_string());
+        assert_eq!(None, origin("blob:ftp://example.com/uuid"));
+        assert_eq!(None, origin("blob:null/uuid"));
+    }
+
+    #[test]
+    fn it_returns_opaque_origins_as_none() {
+        for url in &["file:///etc/passwd", "data:text/html,hi", "mailto:a@example.com", "javascript:alert(1)", "custom://example.com/", "/relative", "//example.com/"] {
+            assert_eq!(None, origin(url), "{}", url);
+        }
+    }
+
+    #[test]
+    fn it_returns_none_for_invalid_hosts_and_ports() {
+        for url in &["https://", "https://:443", "https://example.com:65536", "https://example.com:x", "https://256.0.0.1", "https://[::1", "https://[::1]x", "https://a b"] {
+            assert_eq!(None, origin(url), "{}", url);
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::str::Split;
+
+use super::{decode, encode_bytes_with, FromUrlEncodingError};
+
+// pchar (RFC 3986, section 3.3) minus the ';' and '=' delimiters of matrix
+// parameters.
+fn is_matrix_safe(b: u8) -> bool {
+    matches!(b,
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' |
+        b'!' | b'$' | b'&' | b'\'' | b'(' | b')' | b'*' | b'+' | b',' | b':' | b'@'
+    )
+}
+
+/// A path segment carrying matrix parameters, such as
+/// `cars;color=red;year=2012`.
+///
+/// Parsing decodes the segment name and every parameter, while `Display`
+/// encodes them again with segment-appropriate escaping: `/`, `;`, `=`, `?`
+/// and `#` are always percent-encoded, sub-delimiters such as `,` are not.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MatrixSegment {
+    pub name: String,
+    pub params: Vec<(String, String)>,
+}
+
+impl MatrixSegment {
+    pub fn new(name: &str) -> MatrixSegment {
+        MatrixSegment {
+            name: name.to_string(),
+            params: Vec::new(),
+        }
+    }
+
+    /// Parses a single, still encoded, path segment. A parameter without `=`
+    /// has an empty value and empty parameters are skipped.
+    pub fn parse(segment: &str) -> Result<MatrixSegment, FromUrlEncodingError> {
+        let mut parts = segment.split(';');
+        let name = decode(parts.next().unwrap_or(""))?;
+        let mut params = Vec::new();
+        for param in parts.filter(|param| !param.is_empty()) {
+            let (key, value) = match param.find('=') {
+                Some(idx) => (&param[..idx], &param[idx + 1..]),
+                None => (param, ""),
+            };
+            params.push((decode(key)?, decode(value)?));
+        }
+        Ok(MatrixSegment { name, params })
+    }
+
+    /// Appends a matrix parameter.
+    pub fn param(mut self, key: &str, value: &str) -> MatrixSegment {
+        self.params.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Returns the value of the first parameter named `key`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.params.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+}
+
+impl fmt::Display for MatrixSegment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&encode_bytes_with(self.name.as_bytes(), is_matrix_safe))?;
+        for (key, value) in &self.params {
+            write!(
+                f,
+                ";{}={}",
+                encode_bytes_with(key.as_bytes(), is_matrix_safe),
+                encode_bytes_with(value.as_bytes(), is_matrix_safe)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Splits encoded `data` on unencoded occurrences of `delimiter` only, then
+/// decodes each piece, so `a%2Fb/c` split on `/` yields `a/b` and `c`.
+///
+/// Empty pieces are kept. Pieces without any escape are borrowed from
+/// `data`. `delimiter` should be a character that is meaningful unencoded,
+/// such as `/`, `;` or `,`, never `%` or a hex digit.
+pub fn split_encoded(data: &str, delimiter: char) -> SplitEncoded<'_> {
+    SplitEncoded {
+        pieces: data.split(delimiter),
+    }
+}
+
+/// Splits an encoded path into its segments on unencoded `/` and decodes
+/// each segment on its own, so `%2F` stays inside its segment.
+///
+/// The `/` a path starts with doesn't separate segments, but every other
+/// empty segment is kept, as in `/a//b/`, which has the segments `a`, an
+/// empty one, `b` and another empty one.
+///
+/// ```
+/// use urlencoding::decode_segments;
+///
+/// let segments: Result<Vec<_>, _> = decode_segments("/static/a%2Fb/c%20d").collect();
+/// assert_eq!(vec!["static", "a/b", "c d"], segments.unwrap());
+/// ```
+pub fn decode_segments(path: &str) -> SplitEncoded<'_> {
+    split_encoded(path.strip_prefix('/').unwrap_or(path), '/')
+}
+
+/// The iterator returned by `split_encoded` and `decode_segments`.
+#[derive(Debug, Clone)]
+pub struct SplitEncoded<'a> {
+    pieces: Split<'a, char>,
+}
+
+impl<'a> Iterator for SplitEncoded<'a> {
+    type Item = Result<Cow<'a, str>, FromUrlEncodingError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.pieces.next().map(decode_cow)
+    }
+}
+
+/// The captures of a route template matched by `match_route`, decoded, in
+/// template order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RouteMatch {
+    pub params: Vec<(String, String)>,
+}
+
+impl RouteMatch {
+    /// Returns the decoded value captured by `{name}`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.params.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+}
+
+/// Matches an encoded request path against a route template such as
+/// `/users/{id}/posts/{slug}`, where a segment of the form `{name}` captures
+/// any one segment of the path, and every other segment must be equal to
+/// the decoded path segment.
+///
+/// The path is split on unencoded `/` only, before decoding, so `%2F` can
+/// be part of a capture but never splits one. Any query or fragment is
+/// ignored. Returns `Ok(None)` if the path doesn't fit the template.
+///
+/// ```
+/// use urlencoding::match_route;
+///
+/// let route = match_route("/users/{id}/posts/{slug}", "/users/42/posts/a%2Fb%20c?page=2").unwrap().unwrap();
+/// assert_eq!(Some("42"), route.get("id"));
+/// assert_eq!(Some("a/b c"), route.get("slug"));
+/// ```
+pub fn match_route(template: &str, path: &str) -> Result<Option<RouteMatch>, FromUrlEncodingError> {
+    let path = &path[..path.find(['?', '#']).unwrap_or(path.len())];
+    let mut segments = split_encoded(path, '/');
+    let mut route = RouteMatch::default();
+    for expected in template.split('/') {
+        let segment = match segments.next() {
+            Some(segment) => segment?,
+            None => return Ok(None),
+        };
+        match expected.strip_prefix('{').and_then(|name| name.strip_suffix('}')) {
+            Some(name) => route.params.push((name.to_string(), segment.into_owned())),
+            None if segment == expected => {},
+            None => return Ok(None),
+        }
+    }
+    if segments.next().is_some() {
+        return Ok(None);
+    }
+    Ok(Some(route))
+}
+
+// Decodes `data`, borrowing it when there is nothing to decode.
+pub(crate) fn decode_cow(data: &str) -> Result<Cow<'_, str>, FromUrlEncodingError> {
+    if data.contains('%') {
+        decode(data).map(Cow::Owned)
+    } else {
+        Ok(Cow::Borrowed(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::decode_segments;
+    use super::match_route;
+    use super::split_encoded;
+    use super::MatrixSegment;
+
+    #[test]
+    fn it_parses_matrix_segment_successfully() {
+        let segment = MatrixSegment::parse("red%20cars;color=red,blue;year=2012;;flag").unwrap();
+        assert_eq!("red cars", segment.name);
+        assert_eq!(Some("red,blue"), segment.get("color"));
+        assert_eq!(Some("2012"), segment.get("year"));
+        assert_eq!(Some(""), segment.get("flag"));
+        assert_eq!(None, segment.get("missing"));
+    }
+
+    #[test]
+    fn it_parses_matrix_segment_without_params() {
+        assert_eq!(MatrixSegment::new("cars"), MatrixSegment::parse("cars").unwrap());
+    }
+
+    #[test]
+    fn it_parses_matrix_segment_unsuccessfully() {
+        assert!(MatrixSegment::parse("cars;color=%2").is_err());
+    }
+
+    #[test]
+    fn it_formats_matrix_segment_successfully() {
+        let segment = MatrixSegment::new("a/b c").param("k;1", "x=y,z").param("at", "@:");
+        assert_eq!("a%2Fb%20c;k%3B1=x%3Dy,z;at=@:", segment.to_string());
+    }
+
+    #[test]
+    fn it_round_trips_matrix_segment() {
+        let segment = MatrixSegment::new("50%?").param("#", "é");
+        assert_eq!(segment, MatrixSegment::parse(&segment.to_string()).unwrap());
+    }
+
+    #[test]
+    fn it_splits_encoded_successfully() {
+        let pieces: Vec<_> = split_encoded("/files/a%2Fb/c%20d//", '/').map(Result::unwrap).collect();
+        assert_eq!(vec!["", "files", "a/b", "c d", "", ""], pieces);
+    }
+
+    #[test]
+    fn it_splits_encoded_borrowing_plain_pieces() {
+        let mut pieces = split_encoded("plain,a%2Cb", ',');
+        match pieces.next() {
+            Some(Ok(Cow::Borrowed("plain"))) => {},
+            _ => panic!()
+        }
+        match pieces.next() {
+            Some(Ok(Cow::Owned(ref piece))) if piece == "a,b" => {},
+            _ => panic!()
+        }
+        assert!(pieces.next().is_none());
+    }
+
+    #[test]
+    fn it_splits_encoded_unsuccessfully() {
+        let pieces: Vec<_> = split_encoded("a/%zz/b", '/').collect();
+        assert!(pieces[0].is_ok());
+        assert!(pieces[1].is_err());
+        assert!(pieces[2].is_ok());
+    }
+
+    #[test]
+    fn it_decodes_segments() {
+        let segments: Vec<_> = decode_segments("/a//b%2Fc/").map(Result::unwrap).collect();
+        assert_eq!(vec!["a", "", "b/c", ""], segments);
+        let segments: Vec<_> = decode_segments("relative/x").map(Result::unwrap).collect();
+        assert_eq!(vec!["relative", "x"], segments);
+        assert_eq!(vec![""], decode_segments("/").map(Result::unwrap).collect::<Vec<_>>());
+        assert!(decode_segments("/ok/%zz").nth(1).unwrap().is_err());
+    }
+
+    #[test]
+    fn it_matches_routes() {
+        let template = "/users/{id}/posts/{slug}";
+        let route = match_route(template, "/users/42/posts/hello%20world").unwrap().unwrap();
+        assert_eq!(vec![("id".to_string(), "42".to_string()), ("slug".to_string(), "hello world".to_string())], route.params);
+        assert_eq!(None, route.get("missing"));
+
+        let route = match_route(template, "/users/a%2Fb/posts/c#top").unwrap().unwrap();
+        assert_eq!(Some("a/b"), route.get("id"));
+
+        assert_eq!(None, match_route(template, "/users/a/b/posts/c").unwrap());
+        assert_eq!(None, match_route(template, "/users/42/posts").unwrap());
+        assert_eq!(None, match_route(template, "/users/42/posts/c/").unwrap());
+        assert_eq!(None, match_route(template, "/people/42/posts/c").unwrap());
+        assert!(match_route(template, "/users/%zz/posts/c").is_err());
+    }
+
+    #[test]
+    fn it_matches_routes_on_decoded_literals() {
+        let route = match_route("/files/{name}", "/%66iles/a.txt").unwrap().unwrap();
+        assert_eq!(Some("a.txt"), route.get("name"));
+        assert_eq!(Some(""), match_route("/files/{name}", "/files/").unwrap().unwrap().get("name"));
+        assert!(match_route("/", "/").unwrap().unwrap().params.is_empty());
+    }
+}
@@ -0,0 +1,230 @@
+use super::{decode, FromUrlEncodingError, ParseMode};
+use uri::{decode_component, Component, UriParts};
+
+/// Matches `url` against `pattern`, a URL in which `*` matches any run of
+/// characters within a component, or within a single path segment, and a
+/// path segment of just `**` matches any number of segments, e.g.
+/// `https://*.example.com/files/**/*.pdf`.
+///
+/// Matching is done on the decoded components of `url`, the way the server
+/// will see them, so `%2E` can't smuggle a `.pdf` past an extension check.
+/// `url` is first cleaned up and split like a browser would, with
+/// `ParseMode::Browser`, so `https://evil.com\.example.com/` is a URL for
+/// `evil.com`, as it is for a browser:
+///
+/// - the scheme and host are compared case-insensitively. The userinfo of
+///   `url` is ignored, and a host that decodes to a `\`, `/` or `@` never
+///   matches. A port in `pattern` is matched against the port of `url`, and
+///   a pattern without one only matches a URL without one.
+/// - the path is split on unencoded `/` and every segment is decoded, after
+///   which `.` and `..` segments, encoded or not, are resolved. A decoded
+///   segment that holds a `/`, from `%2F`, is only matched by `**`.
+/// - the query, with `+` decoded as a space, is only compared if `pattern`
+///   has one. The fragment is ignored.
+///
+/// A component missing from `pattern` matches anything. `pattern` itself is
+/// not decoded.
+pub fn matches_pattern(url: &str, pattern: &str) -> Result<bool, FromUrlEncodingError> {
+    let url = UriParts::parse_with(url, ParseMode::Browser);
+    let pattern = UriParts::parse(pattern);
+    if let Some(scheme) = &pattern.scheme {
+        match &url.scheme {
+            Some(url_scheme) if glob_matches(&scheme.to_lowercase(), &url_scheme.to_lowercase()) => {},
+            _ => return Ok(false),
+        }
+    }
+    if let Some(authority) = &pattern.authority {
+        let url_authority = match &url.authority {
+            Some(url_authority) => url_authority,
+            None => return Ok(false),
+        };
+        let (host, port) = split_host_port(authority);
+        let (url_host, url_port) = split_host_port(url_authority);
+        let url_host = decode(url_host)?.to_lowercase();
+        if url_host.contains(['\\', '/', '@']) || !glob_matches(&host.to_lowercase(), &url_host) {
+            return Ok(false);
+        }
+        match (port, url_port) {
+            (Some(port), Some(url_port)) if glob_matches(port, url_port) => {},
+            (None, None) => {},
+            _ => return Ok(false),
+        }
+    }
+    let segments = decode_path_segments(&url.path, url.authority.is_some())?;
+    let pattern_segments: Vec<&str> = split_path(&pattern.path, pattern.authority.is_some()).collect();
+    if !segments_match(&pattern_segments, &segments) {
+        return Ok(false);
+    }
+    if let Some(query) = &pattern.query {
+        let url_query = decode_component(url.query.as_deref().unwrap_or(""), Component::Query)?;
+        if !glob_matches(query, &url_query) {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+// Splits an authority into its host and port, dropping the userinfo, the
+// same way `parse_authority` does. An empty port counts as none.
+fn split_host_port(authority: &str) -> (&str, Option<&str>) {
+    let host_port = authority.rfind('@').map_or(authority, |idx| &authority[idx + 1..]);
+    let host_end = if host_port.starts_with('[') {
+        host_port.find(']').map(|idx| idx + 1).unwrap_or(host_port.len())
+    } else {
+        host_port.rfind(':').unwrap_or(host_port.len())
+    };
+    let port = host_port.get(host_end + 1..).filter(|port| !port.is_empty());
+    (&host_port[..host_end], port)
+}
+
+// Splits a path on '/', treating an empty path after an authority as "/".
+fn split_path(path: &str, has_authority: bool) -> impl Iterator<Item = &str> {
+    let path = if path.is_empty() && has_authority { "/" } else { path };
+    path.split('/')
+}
+
+// Decodes every segment of `path` and resolves "." and ".." segments.
+fn decode_path_segments(path: &str, has_authority: bool) -> Result<Vec<String>, FromUrlEncodingError> {
+    let mut segments: Vec<String> = Vec::new();
+    let mut trailing_dot = false;
+    for segment in split_path(path, has_authority) {
+        let segment = decode(segment)?;
+        trailing_dot = segment == "." || segment == "..";
+        if segment == ".." {
+            // Never remove the empty segment before a leading '/'
+            if segments.len() > 1 || segments.first().is_some_and(|first| !first.is_empty()) {
+                segments.pop();
+            }
+        } else if segment != "." {
+            segments.push(segment);
+        }
+    }
+    // "/a/.." is "/", with an empty last segment
+    if trailing_dot {
+        segments.push(String::new());
+    }
+    Ok(segments)
+}
+
+fn segments_match(pattern: &[&str], segments: &[String]) -> bool {
+    match pattern.split_first() {
+        None => segments.is_empty(),
+        Some((&"**", rest)) => (0..=segments.len()).any(|skip| segments_match(rest, &segments[skip..])),
+        Some((first, rest)) => match segments.split_first() {
+            Some((segment, segments)) => {
+                !segment.contains('/') && glob_matches(first, segment) && segments_match(rest, segments)
+            },
+            None => false,
+        },
+    }
+}
+
+// Matches `text` against `pattern`, where '*' matches any run of
+// characters.
+pub(crate) fn glob_matches(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // split always yields at least one part
+    let first = parts.next().unwrap_or("");
+    let mut rest = match text.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let parts: Vec<&str> = parts.collect();
+    let (last, middle) = match parts.split_last() {
+        Some(split) => split,
+        // No '*' at all
+        None => return rest.is_empty(),
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::glob_matches;
+    use super::matches_pattern;
+
+    const PDFS: &str = "https://example.com/files/*.pdf";
+
+    #[test]
+    fn it_matches_patterns() {
+        assert!(matches_pattern("https://example.com/files/report.pdf", PDFS).unwrap());
+        assert!(matches_pattern("HTTPS://Example.COM/files/report.pdf?download=1#page=2", PDFS).unwrap());
+        assert!(!matches_pattern("https://example.com/files/report.pdf.exe", PDFS).unwrap());
+        assert!(!matches_pattern("https://example.com/files/a/report.pdf", PDFS).unwrap());
+        assert!(!matches_pattern("http://example.com/files/report.pdf", PDFS).unwrap());
+        assert!(!matches_pattern("https://example.com.evil/files/report.pdf", PDFS).unwrap());
+    }
+
+    #[test]
+    fn it_matches_patterns_on_decoded_components() {
+        assert!(matches_pattern("https://example.com/files/report%2Epdf", PDFS).unwrap());
+        assert!(matches_pattern("https://ex%61mple.com/%66iles/report.pdf", PDFS).unwrap());
+        assert!(!matches_pattern("https://example.com/files/report%2Eexe", PDFS).unwrap());
+        assert!(!matches_pattern("https://example.com/files/report.pdf", "https://example.com/files/*%2Epdf").unwrap());
+        assert!(matches_pattern("https://example.com/files/%zz.pdf", PDFS).is_err());
+    }
+
+    #[test]
+    fn it_matches_patterns_after_resolving_dot_segments() {
+        assert!(matches_pattern("https://example.com/files/../files/./a.pdf", PDFS).unwrap());
+        assert!(!matches_pattern("https://example.com/files/%2E%2E/secret/a.pdf", PDFS).unwrap());
+        assert!(!matches_pattern("https://example.com/files/..%2Fsecret.pdf", PDFS).unwrap());
+        assert!(matches_pattern("https://example.com/../../files/a.pdf", PDFS).unwrap());
+        assert!(matches_pattern("https://example.com/files/a/..", "https://example.com/files/").unwrap());
+    }
+
+    #[test]
+    fn it_matches_patterns_with_double_stars_and_queries() {
+        let pattern = "https://*.example.com/files/**/*.pdf";
+        assert!(matches_pattern("https://cdn.example.com/files/a.pdf", pattern).unwrap());
+        assert!(matches_pattern("https://cdn.example.com/files/a/b%2Fc/d.pdf", pattern).unwrap());
+        assert!(!matches_pattern("https://example.com/files/a.pdf", pattern).unwrap());
+
+        let pattern = "/search?q=*";
+        assert!(matches_pattern("https://example.com/search?q=a+b", pattern).unwrap());
+        assert!(!matches_pattern("https://example.com/search", pattern).unwrap());
+        assert!(matches_pattern("https://example.com", "https://example.com/").unwrap());
+    }
+
+    #[test]
+    fn it_matches_patterns_on_the_host_a_browser_connects_to() {
+        let pattern = "https://*.example.com/files/**/*.pdf";
+        assert!(!matches_pattern("https://evil.com\\.example.com/files/a.pdf", pattern).unwrap());
+        assert!(!matches_pattern("https://evil.com%5C.example.com/files/a.pdf", pattern).unwrap());
+        assert!(!matches_pattern("https://evil.com%2F.example.com/files/a.pdf", pattern).unwrap());
+        assert!(matches_pattern("https://cdn.example.com\\files\\a.pdf", pattern).unwrap());
+    }
+
+    #[test]
+    fn it_matches_patterns_ignoring_userinfo() {
+        let pattern = "https://*.example.com/files/**/*.pdf";
+        assert!(matches_pattern("https://user:pw@cdn.example.com/files/a.pdf", pattern).unwrap());
+        assert!(!matches_pattern("https://cdn.example.com@evil.com/files/a.pdf", pattern).unwrap());
+        assert!(!matches_pattern("https://cdn.example.com:x@evil.com/files/a.pdf", pattern).unwrap());
+    }
+
+    #[test]
+    fn it_matches_patterns_with_ports() {
+        assert!(matches_pattern("https://example.com:443/files/a.pdf", PDFS).unwrap());
+        assert!(!matches_pattern("https://example.com:8443/files/a.pdf", PDFS).unwrap());
+        assert!(!matches_pattern("https://cdn.example.com:8443/", "https://*.example.com").unwrap());
+        assert!(matches_pattern("https://example.com:8443/", "https://example.com:84*/").unwrap());
+        assert!(!matches_pattern("https://example.com/", "https://example.com:8443/").unwrap());
+        assert!(matches_pattern("http://[::1]:8080/", "http://[::1]:*/").unwrap());
+    }
+
+    #[test]
+    fn it_matches_globs() {
+        assert!(glob_matches("utm_*", "utm_source"));
+        assert!(glob_matches("*a*b", "xaxb"));
+        assert!(!glob_matches("a*bc*c", "abc"));
+        assert!(glob_matches("", ""));
+        assert!(!glob_matches("a", "ab"));
+    }
+}
@@ -0,0 +1,137 @@
+use proptest::prelude::*;
+
+use super::{is_unreserved, UrlEncodedString};
+
+// Percent-encodes `chars`, choosing for each one whether a character that
+// could stay unencoded is escaped anyway, and whether hex digits are
+// lowercase. With `plus_for_space`, some spaces are written as '+'.
+fn encode_pieces(pieces: Vec<(char, bool, bool)>, plus_for_space: bool) -> String {
+    let mut encoded = String::new();
+    for (c, escape, lowercase) in pieces {
+        if c.is_ascii() && is_unreserved(c as u8) && !escape {
+            encoded.push(c);
+        } else if c == ' ' && plus_for_space && !escape {
+            encoded.push('+');
+        } else {
+            for b in c.encode_utf8(&mut [0; 4]).bytes() {
+                if lowercase {
+                    encoded.push_str(&format!("%{:02x}", b));
+                } else {
+                    encoded.push_str(&format!("%{:02X}", b));
+                }
+            }
+        }
+    }
+    encoded
+}
+
+// Mostly ASCII, so generated strings look like real-world data, with some
+// non-ASCII characters of every UTF-8 length.
+fn piece(escape_odds: u32) -> impl Strategy<Value = (char, bool, bool)> {
+    let c = prop_oneof![
+        6 => proptest::char::range(' ', '~'),
+        1 => any::<char>(),
+    ];
+    (c, proptest::bool::weighted(1.0 / f64::from(escape_odds)), any::<bool>())
+}
+
+/// Generates well-formed percent-encoding that decodes to valid UTF-8: a
+/// mix of unreserved characters, escapes with uppercase or lowercase hex
+/// digits, including needless ones such as `%41`, and encoded non-ASCII
+/// characters.
+pub fn arb_encoded_string() -> impl Strategy<Value = String> {
+    proptest::collection::vec(piece(4), 0..32).prop_map(|pieces| encode_pieces(pieces, false))
+}
+
+/// Generates query strings of `key=value` pairs joined with `&`, where
+/// spaces are written as `+` or `%20`, and that also hold empty pairs and
+/// keys without `=`. Every one decodes to valid UTF-8.
+pub fn arb_query_string() -> impl Strategy<Value = String> {
+    let part = || proptest::collection::vec(piece(4), 0..12).prop_map(|pieces| encode_pieces(pieces, true));
+    let pair = (part(), proptest::option::weighted(0.9, part())).prop_map(|(key, value)| match value {
+        Some(value) => format!("{}={}", key, value),
+        None => key,
+    });
+    proptest::collection::vec(pair, 0..8).prop_map(|pairs| pairs.join("&"))
+}
+
+/// Generates strings mixing well-formed percent-encoding with the inputs
+/// that break decoders: isolated or truncated `%`, non-hex digits, `+` and
+/// `%2B`, `%00`, overlong UTF-8 such as `%C0%AF`, encoded surrogates, bytes
+/// that are never valid UTF-8 and multi-byte sequences cut short.
+pub fn arb_nasty_encoded_string() -> impl Strategy<Value = String> {
+    let nasty = prop_oneof![
+        Just("%"),
+        Just("%%"),
+        Just("%2"),
+        Just("%zz"),
+        Just("%%41"),
+        Just("%+1"),
+        Just("+"),
+        Just("%2B"),
+        Just("%20+"),
+        Just("%25"),
+        Just("%2525"),
+        Just("%00"),
+        Just("%C0%AF"),
+        Just("%E0%80%AF"),
+        Just("%F0%80%80%AF"),
+        Just("%ED%A0%80"),
+        Just("%FF"),
+        Just("%C3"),
+        Just("%E2%82"),
+        Just("\u{FEFF}"),
+        Just("é"),
+    ];
+    let piece = prop_oneof![nasty.prop_map(String::from), arb_encoded_string()];
+    proptest::collection::vec(piece, 0..16).prop_map(|pieces| pieces.concat())
+}
+
+/// Generates strings with `arb_encoded_string`.
+impl Arbitrary for UrlEncodedString {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<UrlEncodedString>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        arb_encoded_string().prop_filter_map("well-formed", |encoded| UrlEncodedString::new(encoded).ok()).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::arb_encoded_string;
+    use super::arb_nasty_encoded_string;
+    use super::arb_query_string;
+    use super::super::{decode, encode, UrlEncodedString};
+    #[cfg(feature = "decoder")]
+    use super::super::decode_display_safe;
+
+    proptest! {
+        #[test]
+        fn it_generates_decodable_encoded_strings(encoded in arb_encoded_string()) {
+            let decoded = decode(&encoded).unwrap();
+            prop_assert_eq!(decoded.clone(), decode(&encode(&decoded)).unwrap());
+        }
+
+        #[test]
+        fn it_generates_decodable_query_strings(query in arb_query_string()) {
+            for pair in query.split('&') {
+                prop_assert!(decode(&pair.replace('+', " ")).is_ok());
+            }
+        }
+
+        #[test]
+        fn it_decodes_nasty_encoded_strings_without_panicking(data in arb_nasty_encoded_string()) {
+            let _ = decode(&data);
+            #[cfg(feature = "decoder")]
+            let _ = decode_display_safe(&data);
+        }
+
+        #[test]
+        fn it_generates_arbitrary_url_encoded_strings(encoded in any::<UrlEncodedString>()) {
+            prop_assert!(encoded.decode().is_ok());
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use std::net::Ipv4Addr;
+
+use psl::{List, Psl};
+
+use super::normalize_host;
+
+/// Returns the registrable domain (eTLD+1) of `host`, using the Public
+/// Suffix List, such as `example.co.uk` for `www.example.co.uk`.
+///
+/// The host is normalized with `normalize_host` first, so the domain comes
+/// back lowercase, and with the `idna` feature, in Punycode. `None` is
+/// returned for IP addresses, invalid hosts and hosts that are themselves a
+/// public suffix. A top-level domain missing from the list counts as a
+/// public suffix, as the list's default rule says.
+///
+/// ```
+/// use urlencoding::registrable_domain;
+///
+/// assert_eq!(Some("example.co.uk".to_string()), registrable_domain("WWW.Example.co.uk"));
+/// assert_eq!(Some("example.github.io".to_string()), registrable_domain("a.example.github.io"));
+/// assert_eq!(None, registrable_domain("co.uk"));
+/// assert_eq!(None, registrable_domain("127.0.0.1"));
+/// ```
+pub fn registrable_domain(host: &str) -> Option<String> {
+    let host = normalize_domain(host)?;
+    let domain = List.domain(host.as_bytes())?;
+    // The list only matches ASCII, so the domain is always valid UTF-8
+    String::from_utf8(domain.as_bytes().to_vec()).ok()
+}
+
+/// Whether `host` is a public suffix, such as `com`, `co.uk` or
+/// `github.io`, under which anyone can register a domain. Cookies must not
+/// be scoped to a public suffix.
+///
+/// The host is normalized with `normalize_host` first. IP addresses and
+/// invalid hosts are never public suffixes.
+///
+/// ```
+/// use urlencoding::is_public_suffix;
+///
+/// assert!(is_public_suffix("co.uk"));
+/// assert!(is_public_suffix("GitHub.io"));
+/// assert!(!is_public_suffix("example.com"));
+/// ```
+pub fn is_public_suffix(host: &str) -> bool {
+    match normalize_domain(host) {
+        Some(host) => List.suffix(host.as_bytes()).is_some_and(|suffix| suffix.as_bytes() == host.as_bytes()),
+        None => false,
+    }
+}
+
+// Normalizes a host and drops its trailing dot, or returns `None` if it is
+// invalid or an IP address.
+fn normalize_domain(host: &str) -> Option<String> {
+    let mut host = normalize_host(host).ok()?;
+    if host.starts_with('[') || host.parse::<Ipv4Addr>().is_ok() {
+        return None;
+    }
+    if host.ends_with('.') {
+        host.pop();
+    }
+    if host.is_empty() {
+        None
+    } else {
+        Some(host)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_public_suffix;
+    use super::registrable_domain;
+
+    #[test]
+    fn it_finds_registrable_domains() {
+        assert_eq!(Some("example.com".to_string()), registrable_domain("example.com"));
+        assert_eq!(Some("example.com".to_string()), registrable_domain("a.b.EXAMPLE.com."));
+        assert_eq!(Some("example.co.uk".to_string()), registrable_domain("ex%61mple.co.uk"));
+        assert_eq!(Some("example.unknowntld".to_string()), registrable_domain("www.example.unknowntld"));
+        assert_eq!(None, registrable_domain("com"));
+        assert_eq!(None, registrable_domain("github.io"));
+        assert_eq!(None, registrable_domain("0x7f.1"));
+        assert_eq!(None, registrable_domain("[::1]"));
+        assert_eq!(None, registrable_domain("a/b.com"));
+        assert_eq!(None, registrable_domain(""));
+    }
+
+    #[test]
+    fn it_finds_public_suffixes() {
+        for host in &["com", "co.uk", "github.io", "COM.", "unknowntld"] {
+            assert!(is_public_suffix(host), "{}", host);
+        }
+        for host in &["example.com", "www.co.uk.example", "127.0.0.1", "[::1]", "", "a b"] {
+            assert!(!is_public_suffix(host), "{}", host);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "idna")]
+    fn it_finds_internationalized_registrable_domains() {
+        assert_eq!(Some("xn--bcher-kva.xn--p1ai".to_string()), registrable_domain("www.bücher.рф"));
+    }
+}
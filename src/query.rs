@@ -0,0 +1,680 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(feature = "http")]
+use http::uri::InvalidUriParts;
+
+use super::{decode_bytes_lenient, encode, FromUrlEncodingError};
+use uri::{decode_component, query_range, Component};
+
+// Splits a query string into decoded `(key, value)` pairs, in order, with
+// the same rules as `for_each_query_pair`.
+pub(crate) fn parse_pairs(query: &str) -> Result<Vec<(String, String)>, FromUrlEncodingError> {
+    let mut pairs = Vec::new();
+    for_each_query_pair(query, |key, value| pairs.push((key.into_owned(), value.into_owned())))?;
+    Ok(pairs)
+}
+
+/// Calls `on_pair` with each decoded key and value of a query string as it
+/// is scanned, without collecting the pairs, so even a query string of
+/// hundreds of kilobytes only needs memory for one pair at a time.
+///
+/// A leading `?` is ignored, empty pairs are skipped, a pair without `=` has
+/// an empty value and `+` is decoded as a space. Keys and values that need
+/// no decoding are borrowed from `query`. Scanning stops at the first pair
+/// that fails to decode, after `on_pair` has seen all the pairs before it.
+///
+/// ```
+/// use urlencoding::for_each_query_pair;
+///
+/// let mut total = 0;
+/// for_each_query_pair("?n=1&n=2&other=x&n=39", |key, value| {
+///     if key == "n" {
+///         total += value.parse::<u32>().unwrap();
+///     }
+/// }).unwrap();
+/// assert_eq!(42, total);
+/// ```
+pub fn for_each_query_pair<'a, F>(query: &'a str, mut on_pair: F) -> Result<(), FromUrlEncodingError>
+where
+    F: FnMut(Cow<'a, str>, Cow<'a, str>),
+{
+    let query = query.strip_prefix('?').unwrap_or(query);
+    for pair in query.split('&') {
+        if pair.is_empty() {
+            continue;
+        }
+        let (key, value) = match pair.find('=') {
+            Some(idx) => (&pair[..idx], &pair[idx + 1..]),
+            None => (pair, ""),
+        };
+        on_pair(decode_query_cow(key)?, decode_query_cow(value)?);
+    }
+    Ok(())
+}
+
+fn decode_query_cow(data: &str) -> Result<Cow<'_, str>, FromUrlEncodingError> {
+    if data.contains(['%', '+']) {
+        decode_query_part(data).map(Cow::Owned)
+    } else {
+        Ok(Cow::Borrowed(data))
+    }
+}
+
+// Decodes a query key or value, where '+' is a space.
+pub(crate) fn decode_query_part(data: &str) -> Result<String, FromUrlEncodingError> {
+    decode_component(data, Component::Query)
+}
+
+/// Returns a canonical form of a query string, suitable as a cache key or as
+/// input to a request signature.
+///
+/// Every pair is decoded (treating `+` as a space), the pairs are sorted by
+/// key and then by value, and re-encoded with `encode`, so spaces always
+/// become `%20` and hex digits are always uppercase. Empty pairs are dropped
+/// and a key without `=` is written as `key=`.
+pub fn canonicalize_query(query: &str) -> Result<String, FromUrlEncodingError> {
+    let mut pairs = parse_pairs(query)?;
+    pairs.sort();
+    Ok(serialize_pairs(&pairs))
+}
+
+/// A difference between two query strings found by `diff_query`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryChange {
+    /// The key is only in the second query.
+    Added { key: String, values: Vec<String> },
+    /// The key is only in the first query.
+    Removed { key: String, values: Vec<String> },
+    /// The key is in both queries, with different values.
+    Changed { key: String, old: Vec<String>, new: Vec<String> },
+}
+
+impl fmt::Display for QueryChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QueryChange::Added { key, values } => write!(f, "added {:?}: {:?}", key, values),
+            QueryChange::Removed { key, values } => write!(f, "removed {:?}: {:?}", key, values),
+            QueryChange::Changed { key, old, new } => write!(f, "changed {:?}: {:?} -> {:?}", key, old, new),
+        }
+    }
+}
+
+/// Compares the decoded parameters of two query strings, ignoring their
+/// order and how they were encoded, and returns what changed from `a` to
+/// `b`, sorted by key. No changes means the queries are equivalent.
+///
+/// The values of a repeated key are compared as a whole, and reported
+/// sorted: `a=1&a=2` and `a=2&a=1` are equal, `a=1` and `a=1&a=1` aren't.
+pub fn diff_query(a: &str, b: &str) -> Result<Vec<QueryChange>, FromUrlEncodingError> {
+    let mut old = group_pairs(parse_pairs(a)?);
+    let new = group_pairs(parse_pairs(b)?);
+    let mut changes = Vec::new();
+    for (key, new) in new {
+        match old.remove(&key) {
+            None => changes.push(QueryChange::Added { key, values: new }),
+            Some(old) => {
+                if old != new {
+                    changes.push(QueryChange::Changed { key, old, new });
+                }
+            },
+        }
+    }
+    for (key, values) in old {
+        changes.push(QueryChange::Removed { key, values });
+    }
+    changes.sort_by(|a, b| change_key(a).cmp(change_key(b)));
+    Ok(changes)
+}
+
+// Groups the sorted values of each key.
+fn group_pairs(pairs: Vec<(String, String)>) -> BTreeMap<String, Vec<String>> {
+    let mut grouped: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (key, value) in pairs {
+        grouped.entry(key).or_default().push(value);
+    }
+    for values in grouped.values_mut() {
+        values.sort();
+    }
+    grouped
+}
+
+fn change_key(change: &QueryChange) -> &str {
+    match change {
+        QueryChange::Added { key, .. } | QueryChange::Removed { key, .. } | QueryChange::Changed { key, .. } => key,
+    }
+}
+
+/// What `merge_query` does with a key that is in both queries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergePolicy {
+    /// The values from the overrides replace the base values, at the position
+    /// of the first base value.
+    Replace,
+    /// The base values are kept and the overrides for the key are dropped.
+    Keep,
+    /// The values from the overrides are added at the end, keeping the base
+    /// values.
+    Append,
+}
+
+/// Adds the parameters of the `overrides` query string to the `base` query
+/// string, resolving keys that are in both with `policy`, and returns the
+/// re-encoded query.
+///
+/// Keys only in `overrides` are added at the end, in their order. Both
+/// queries are decoded first, so keys match however they were encoded.
+pub fn merge_query(base: &str, overrides: &str, policy: MergePolicy) -> Result<String, FromUrlEncodingError> {
+    let base = parse_pairs(base)?;
+    let overrides = parse_pairs(overrides)?;
+    let mut merged = Vec::with_capacity(base.len() + overrides.len());
+    for (i, (key, value)) in base.iter().enumerate() {
+        let overridden = overrides.iter().any(|(k, _)| k == key);
+        if policy == MergePolicy::Replace && overridden {
+            // Every override takes the place of the first base value
+            if !base[..i].iter().any(|(k, _)| k == key) {
+                merged.extend(overrides.iter().filter(|(k, _)| k == key).cloned());
+            }
+        } else {
+            merged.push((key.clone(), value.clone()));
+        }
+    }
+    for (key, value) in overrides {
+        if policy == MergePolicy::Append || !base.iter().any(|(k, _)| *k == key) {
+            merged.push((key, value));
+        }
+    }
+    Ok(serialize_pairs(&merged))
+}
+
+// Encodes `(key, value)` pairs with `encode` and joins them with '&'.
+fn serialize_pairs(pairs: &[(String, String)]) -> String {
+    let mut serialized = String::new();
+    for (i, (key, value)) in pairs.iter().enumerate() {
+        if i > 0 {
+            serialized.push('&');
+        }
+        serialized.push_str(&encode(key));
+        serialized.push('=');
+        serialized.push_str(&encode(value));
+    }
+    serialized
+}
+
+/// Replaces the value of every query parameter of `url` whose key is in
+/// `keys` with `***`, for logging URLs without leaking secrets.
+///
+/// Keys are compared after decoding, so `pass%77ord` matches `password`, but
+/// everything else, including the other parameters and the fragment, is kept
+/// byte for byte.
+pub fn redact_query(url: &str, keys: &[&str]) -> String {
+    let (query_start, query_end) = match query_range(url) {
+        Some(range) => range,
+        None => return url.to_string(),
+    };
+
+    let mut redacted = String::with_capacity(url.len());
+    redacted.push_str(&url[..query_start]);
+    for (i, pair) in url[query_start..query_end].split('&').enumerate() {
+        if i > 0 {
+            redacted.push('&');
+        }
+        match pair.find('=') {
+            Some(idx) if keys.contains(&&*decode_key(&pair[..idx])) => {
+                redacted.push_str(&pair[..idx + 1]);
+                redacted.push_str("***");
+            },
+            _ => redacted.push_str(pair),
+        }
+    }
+    redacted.push_str(&url[query_end..]);
+    redacted
+}
+
+// Decodes a query key for comparison, never failing.
+pub(crate) fn decode_key(key: &str) -> String {
+    String::from_utf8_lossy(&decode_bytes_lenient(&key.replace('+', " "))).into_owned()
+}
+
+/// The decoded parameters of a query string, in their original order.
+///
+/// Keys may repeat: `get` returns the first value for a key and `get_all`
+/// every value. `Display` encodes the parameters back into a query string
+/// (without a leading `?`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryParams {
+    pairs: Vec<(String, String)>,
+}
+
+impl QueryParams {
+    pub fn new() -> QueryParams {
+        QueryParams::default()
+    }
+
+    /// Parses a query string, with or without its leading `?`. Empty pairs
+    /// are skipped, a key without `=` has an empty value and `+` is decoded
+    /// as a space.
+    pub fn parse(query: &str) -> Result<QueryParams, FromUrlEncodingError> {
+        Ok(QueryParams {
+            pairs: parse_pairs(query)?,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.pairs.iter().any(|(k, _)| k == key)
+    }
+
+    /// Returns the first value of `key`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.pairs.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    /// Returns every value of `key`, in order.
+    pub fn get_all(&self, key: &str) -> Vec<&str> {
+        self.pairs.iter().filter(|(k, _)| k == key).map(|(_, v)| v.as_str()).collect()
+    }
+
+    /// Parses the first value of `key` with `FromStr`, returning `None` if
+    /// the key isn't present.
+    pub fn get_parsed<T: FromStr>(&self, key: &str) -> Option<Result<T, ParamError<T::Err>>> {
+        self.get(key).map(|value| parse_value(key, value))
+    }
+
+    /// Parses the first value of `key`, failing with
+    /// `ParamError::MissingParamError` if the key isn't present.
+    pub fn get_required<T: FromStr>(&self, key: &str) -> Result<T, ParamError<T::Err>> {
+        self.get_parsed(key).unwrap_or_else(|| Err(ParamError::MissingParamError {
+            key: key.to_string(),
+        }))
+    }
+
+    /// Parses the first value of `key`, or returns `default` if the key isn't
+    /// present.
+    pub fn get_parsed_or<T: FromStr>(&self, key: &str, default: T) -> Result<T, ParamError<T::Err>> {
+        self.get_parsed(key).unwrap_or(Ok(default))
+    }
+
+    /// Parses every value of `key`, failing on the first invalid one.
+    pub fn get_parsed_all<T: FromStr>(&self, key: &str) -> Result<Vec<T>, ParamError<T::Err>> {
+        self.get_all(key).into_iter().map(|value| parse_value(key, value)).collect()
+    }
+
+    /// Iterates over every `(key, value)` pair, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.pairs.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Adds a parameter after the existing ones, keeping other values of the
+    /// same key.
+    pub fn append(&mut self, key: &str, value: &str) {
+        self.pairs.push((key.to_string(), value.to_string()));
+    }
+
+    /// Replaces every value of `key` with a single `value`, kept at the
+    /// position of the first existing one.
+    pub fn set(&mut self, key: &str, value: &str) {
+        let mut found = false;
+        self.pairs.retain_mut(|(k, v)| {
+            if k != key {
+                return true;
+            }
+            if found {
+                return false;
+            }
+            found = true;
+            *v = value.to_string();
+            true
+        });
+        if !found {
+            self.append(key, value);
+        }
+    }
+
+    /// Removes every value of `key`.
+    pub fn remove(&mut self, key: &str) {
+        self.pairs.retain(|(k, _)| k != key);
+    }
+}
+
+impl fmt::Display for QueryParams {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&serialize_pairs(&self.pairs))
+    }
+}
+
+/// Types that can be built from the parameters of a query string.
+///
+/// With the `derive` feature, `#[derive(FromQuery)]` implements this trait for
+/// structs with named fields, mapping each field to the parameter of the same
+/// name.
+pub trait FromQuery: Sized {
+    fn from_query_params(params: &QueryParams) -> Result<Self, FromQueryError>;
+
+    /// Parses `query` and builds `Self` from its parameters.
+    fn from_query(query: &str) -> Result<Self, FromQueryError> {
+        let params = QueryParams::parse(query).map_err(|e| FromQueryError::DecodingError { error: e })?;
+        Self::from_query_params(&params)
+    }
+}
+
+/// Types that can be written as the parameters of a query string.
+///
+/// With the `derive` feature, `#[derive(ToQuery)]` implements this trait for
+/// structs with named fields, writing each field as the parameter of the same
+/// name.
+pub trait ToQuery {
+    fn to_query_params(&self) -> QueryParams;
+
+    /// Returns the encoded query string, without a leading `?`.
+    fn to_query(&self) -> String {
+        self.to_query_params().to_string()
+    }
+}
+
+#[derive(Debug)]
+pub enum FromQueryError {
+    DecodingError { error: FromUrlEncodingError },
+    MissingParamError { key: String },
+    // The `FromStr` error of the field type is kept as its message
+    InvalidParamError { key: String, value: String, message: String },
+}
+
+impl<E: fmt::Display> From<ParamError<E>> for FromQueryError {
+    fn from(error: ParamError<E>) -> FromQueryError {
+        match error {
+            ParamError::MissingParamError { key } => FromQueryError::MissingParamError { key },
+            ParamError::InvalidParamError { key, value, error } => FromQueryError::InvalidParamError {
+                key,
+                value,
+                message: error.to_string(),
+            },
+        }
+    }
+}
+
+impl fmt::Display for FromQueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FromQueryError::DecodingError { error } => write!(f, "invalid query string: {:?}", error),
+            FromQueryError::MissingParamError { key } => write!(f, "missing query parameter {:?}", key),
+            FromQueryError::InvalidParamError { key, value, message } => {
+                write!(f, "invalid value {:?} for query parameter {:?}: {}", value, key, message)
+            },
+        }
+    }
+}
+
+impl Error for FromQueryError {}
+
+/// Reads and edits the query parameters of a URI type from another crate:
+/// `http::Uri` and `http::Request` with the `http` feature, and `url::Url`
+/// with the `url` feature.
+///
+/// Editing a parameter re-encodes the whole query with `QueryParams`, so the
+/// other parameters keep their values but may be written differently, e.g.
+/// `+` becomes `%20`.
+pub trait QueryExt {
+    /// Returns the decoded query parameters, empty if there is no query.
+    fn query_params(&self) -> Result<QueryParams, QueryEditError>;
+
+    /// Replaces the whole query. An empty `params` removes the query.
+    fn set_query_params(&mut self, params: &QueryParams) -> Result<(), QueryEditError>;
+
+    /// Adds a parameter after the existing ones.
+    fn append_query_param(&mut self, key: &str, value: &str) -> Result<(), QueryEditError> {
+        let mut params = self.query_params()?;
+        params.append(key, value);
+        self.set_query_params(&params)
+    }
+
+    /// Replaces every value of `key` with a single `value`.
+    fn set_query_param(&mut self, key: &str, value: &str) -> Result<(), QueryEditError> {
+        let mut params = self.query_params()?;
+        params.set(key, value);
+        self.set_query_params(&params)
+    }
+
+    /// Removes every value of `key`.
+    fn remove_query_param(&mut self, key: &str) -> Result<(), QueryEditError> {
+        let mut params = self.query_params()?;
+        params.remove(key);
+        self.set_query_params(&params)
+    }
+}
+
+#[derive(Debug)]
+pub enum QueryEditError {
+    // The existing query string isn't valid percent-encoding
+    DecodingError { error: FromUrlEncodingError },
+    #[cfg(feature = "http")]
+    InvalidUriError { error: InvalidUriParts },
+}
+
+impl fmt::Display for QueryEditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QueryEditError::DecodingError { error } => write!(f, "invalid query string: {:?}", error),
+            #[cfg(feature = "http")]
+            QueryEditError::InvalidUriError { error } => write!(f, "invalid URI: {}", error),
+        }
+    }
+}
+
+impl Error for QueryEditError {}
+
+fn parse_value<T: FromStr>(key: &str, value: &str) -> Result<T, ParamError<T::Err>> {
+    value.parse().map_err(|e| ParamError::InvalidParamError {
+        key: key.to_string(),
+        value: value.to_string(),
+        error: e,
+    })
+}
+
+/// The error returned by the typed getters of `QueryParams`, where `E` is the
+/// `FromStr` error of the requested type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamError<E> {
+    MissingParamError { key: String },
+    InvalidParamError { key: String, value: String, error: E },
+}
+
+impl<E: fmt::Display> fmt::Display for ParamError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParamError::MissingParamError { key } => write!(f, "missing query parameter {:?}", key),
+            ParamError::InvalidParamError { key, value, error } => {
+                write!(f, "invalid value {:?} for query parameter {:?}: {}", value, key, error)
+            },
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for ParamError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParamError::MissingParamError { .. } => None,
+            ParamError::InvalidParamError { error, .. } => Some(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::canonicalize_query;
+    use super::diff_query;
+    use super::for_each_query_pair;
+    use super::merge_query;
+    use super::MergePolicy;
+    use super::redact_query;
+    use super::QueryChange;
+    use super::FromUrlEncodingError;
+    use super::ParamError;
+    use super::QueryParams;
+    use std::borrow::Cow;
+
+    #[test]
+    fn it_visits_query_pairs_in_order() {
+        let mut pairs = Vec::new();
+        for_each_query_pair("?a=1&&b&c=x+y%21&a=2", |key, value| pairs.push((key, value))).unwrap();
+        assert_eq!(
+            vec![("a", "1"), ("b", ""), ("c", "x y!"), ("a", "2")],
+            pairs.iter().map(|(k, v)| (k.as_ref(), v.as_ref())).collect::<Vec<_>>()
+        );
+        assert!(matches!(pairs[0].1, Cow::Borrowed(_)));
+        assert!(matches!(pairs[2].1, Cow::Owned(_)));
+    }
+
+    #[test]
+    fn it_stops_visiting_query_pairs_on_error() {
+        let mut keys = Vec::new();
+        let error = for_each_query_pair("a=1&b=%zz&c=3", |key, _| keys.push(key.into_owned())).unwrap_err();
+        assert_eq!(vec!["a"], keys);
+        assert!(matches!(error, FromUrlEncodingError::UriCharacterError { character: 'z', index: 1 }));
+    }
+
+    #[test]
+    fn it_canonicalizes_query_order() {
+        assert_eq!("a=1&b=2&c=3", canonicalize_query("c=3&a=1&b=2").unwrap());
+        assert_eq!("a=1&a=2&b=", canonicalize_query("?b&a=2&a=1").unwrap());
+    }
+
+    #[test]
+    fn it_canonicalizes_query_encoding() {
+        let expected = "q=this%20that&x=%2F%2F~";
+        assert_eq!(expected, canonicalize_query("x=%2f%2F%7E&q=this+that").unwrap());
+        assert_eq!(expected, canonicalize_query("q=this%20that&&x=/%2F~").unwrap());
+    }
+
+    #[test]
+    fn it_canonicalizes_query_unsuccessfully() {
+        match canonicalize_query("a=%2").unwrap_err() {
+            FromUrlEncodingError::UriCharacterError { index: i, character: c } => {
+                assert_eq!(0, i);
+                assert_eq!('%', c)
+            },
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn it_diffs_equivalent_queries() {
+        assert_eq!(Vec::<QueryChange>::new(), diff_query("b=x%20y&a=1&a=2", "?a=2&b=x+y&a=%31").unwrap());
+    }
+
+    #[test]
+    fn it_diffs_queries() {
+        let changes = diff_query("a=1&b=2&c=3&c=4", "c=4&d=5&b=3&c=3&c=3").unwrap();
+        assert_eq!(vec![
+            QueryChange::Removed { key: "a".to_string(), values: vec!["1".to_string()] },
+            QueryChange::Changed { key: "b".to_string(), old: vec!["2".to_string()], new: vec!["3".to_string()] },
+            QueryChange::Changed {
+                key: "c".to_string(),
+                old: vec!["3".to_string(), "4".to_string()],
+                new: vec!["3".to_string(), "3".to_string(), "4".to_string()],
+            },
+            QueryChange::Added { key: "d".to_string(), values: vec!["5".to_string()] },
+        ], changes);
+        assert_eq!("changed \"b\": [\"2\"] -> [\"3\"]", changes[1].to_string());
+        assert!(diff_query("a=1", "a=%2").is_err());
+    }
+
+    #[test]
+    fn it_merges_queries() {
+        let base = "lang=en&q=a+b&lang=fr&page=2";
+        let overrides = "lang=de&utm_source=mail&lang=it";
+        assert_eq!("lang=de&lang=it&q=a%20b&page=2&utm_source=mail", merge_query(base, overrides, MergePolicy::Replace).unwrap());
+        assert_eq!("lang=en&q=a%20b&lang=fr&page=2&utm_source=mail", merge_query(base, overrides, MergePolicy::Keep).unwrap());
+        assert_eq!(
+            "lang=en&q=a%20b&lang=fr&page=2&lang=de&utm_source=mail&lang=it",
+            merge_query(base, overrides, MergePolicy::Append).unwrap()
+        );
+    }
+
+    #[test]
+    fn it_merges_queries_unsuccessfully() {
+        assert!(merge_query("a=%2", "b=1", MergePolicy::Replace).is_err());
+        assert!(merge_query("a=1", "b=%2", MergePolicy::Keep).is_err());
+    }
+
+    #[test]
+    fn it_parses_query_params_successfully() {
+        let params = QueryParams::parse("?q=this+that&tag=a&tag=b%20c&empty").unwrap();
+        assert_eq!(4, params.len());
+        assert_eq!(Some("this that"), params.get("q"));
+        assert_eq!(vec!["a", "b c"], params.get_all("tag"));
+        assert_eq!(Some(""), params.get("empty"));
+        assert_eq!(None, params.get("missing"));
+        assert!(params.get_all("missing").is_empty());
+    }
+
+    #[test]
+    fn it_gets_parsed_query_params() {
+        let params = QueryParams::parse("page=2&debug=true&ids=1&ids=2&ids=3").unwrap();
+        assert_eq!(Some(Ok(2)), params.get_parsed::<u32>("page"));
+        assert_eq!(Some(Ok(true)), params.get_parsed::<bool>("debug"));
+        assert_eq!(None, params.get_parsed::<u32>("limit"));
+        assert_eq!(Ok(20), params.get_parsed_or("limit", 20u32));
+        assert_eq!(Ok(vec![1, 2, 3]), params.get_parsed_all::<u8>("ids"));
+        assert_eq!(Ok(2), params.get_required::<i64>("page"));
+    }
+
+    #[test]
+    fn it_gets_parsed_query_params_unsuccessfully() {
+        let params = QueryParams::parse("page=two&ids=1&ids=x").unwrap();
+
+        let error = params.get_parsed::<u32>("page").unwrap().unwrap_err();
+        assert_eq!(
+            "invalid value \"two\" for query parameter \"page\": invalid digit found in string",
+            error.to_string()
+        );
+        match params.get_parsed_all::<u8>("ids").unwrap_err() {
+            ParamError::InvalidParamError { key, value, .. } => {
+                assert_eq!("ids", key);
+                assert_eq!("x", value);
+            },
+            _ => panic!()
+        }
+
+        let error = params.get_required::<u32>("limit").unwrap_err();
+        assert_eq!("missing query parameter \"limit\"", error.to_string());
+    }
+
+    #[test]
+    fn it_edits_query_params() {
+        let mut params = QueryParams::parse("a=1&b=2&a=3").unwrap();
+        params.set("a", "x y");
+        params.append("c", "&");
+        params.remove("b");
+        assert!(!params.contains_key("b"));
+        assert_eq!("a=x%20y&c=%26", params.to_string());
+
+        let mut params = QueryParams::new();
+        params.set("k", "v");
+        assert_eq!(vec![("k", "v")], params.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn it_redacts_query_successfully() {
+        let url = "https://example.com/cb?code=abc&state=x%20y&token=s3cr3t&pass%77ord=hunter2#token=frag";
+        let expected = "https://example.com/cb?code=abc&state=x%20y&token=***&pass%77ord=***#token=frag";
+        assert_eq!(expected, redact_query(url, &["token", "password"]));
+    }
+
+    #[test]
+    fn it_redacts_query_leaving_other_urls_alone() {
+        assert_eq!("/a?b=%zz&&c#d?token=1", redact_query("/a?b=%zz&&c#d?token=1", &["token"]));
+        assert_eq!("/a", redact_query("/a", &["token"]));
+        assert_eq!("?token=***&token=***", redact_query("?token=1&token=2", &["token"]));
+    }
+}
@@ -0,0 +1,134 @@
+use std::borrow::Cow;
+
+use super::{decode_triplet, AsciiSet, FromUrlEncodingError};
+
+/// A place in a URL that a string is percent-encoded for, as used by
+/// `reencode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EncodeContext {
+    /// One segment of a path, where `/` must be encoded.
+    PathSegment,
+    /// A whole path, where `/` separates the segments.
+    Path,
+    /// A key or value in a query string, where `&`, `=` and `+` must be
+    /// encoded and a `+` stands for a space.
+    QueryValue,
+    /// A fragment, after the `#`.
+    Fragment,
+    /// A username or password, encoded with `AsciiSet::userinfo`.
+    Userinfo,
+}
+
+impl EncodeContext {
+    // The characters that can stay unencoded in this context
+    fn set(self) -> AsciiSet {
+        let pchar = AsciiSet::unreserved().union(AsciiSet::from_bytes(b"!$&'()*+,;=:@"));
+        match self {
+            EncodeContext::PathSegment => pchar,
+            EncodeContext::Path => pchar.add(b'/'),
+            EncodeContext::QueryValue => pchar.add(b'/').add(b'?').remove(b'&').remove(b'=').remove(b'+'),
+            EncodeContext::Fragment => pchar.add(b'/').add(b'?'),
+            EncodeContext::Userinfo => AsciiSet::userinfo(),
+        }
+    }
+
+    fn plus_is_space(self) -> bool {
+        self == EncodeContext::QueryValue
+    }
+}
+
+/// Converts `data`, percent-encoded for the `from` context, into the
+/// encoding the `to` context needs, such as a query value into a path
+/// segment, without decoding it first.
+///
+/// Escapes are kept as they are, since they mean the same everywhere. Only
+/// the unencoded characters `to` doesn't allow get encoded, and a `+` is
+/// translated between a space, in a query value, and a literal `+`
+/// elsewhere. If nothing changes, `data` is borrowed. Malformed
+/// percent-encoding is an error.
+///
+/// ```
+/// use urlencoding::{reencode, EncodeContext};
+///
+/// let segment = reencode("a/b+c%26d", EncodeContext::QueryValue, EncodeContext::PathSegment).unwrap();
+/// assert_eq!("a%2Fb%20c%26d", segment);
+/// let value = reencode("c++;v=1", EncodeContext::PathSegment, EncodeContext::QueryValue).unwrap();
+/// assert_eq!("c%2B%2B;v%3D1", value);
+/// ```
+pub fn reencode(data: &str, from: EncodeContext, to: EncodeContext) -> Result<Cow<'_, str>, FromUrlEncodingError> {
+    let bytes = data.as_bytes();
+    let to_set = to.set();
+    let mut reencoded = String::new();
+    // Everything before `copied` is already in `reencoded`
+    let mut copied = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if b == b'%' {
+            decode_triplet(data, i)?;
+            i += 3;
+            continue;
+        }
+        // Non-ASCII characters are never allowed unencoded
+        let len = if b.is_ascii() { 1 } else { data[i..].chars().next().map_or(1, char::len_utf8) };
+        let replacement = if b == b'+' && from.plus_is_space() {
+            if to.plus_is_space() {
+                None
+            } else {
+                Some(Cow::Borrowed("%20"))
+            }
+        } else if to_set.contains(b) {
+            None
+        } else {
+            Some(Cow::Owned(bytes[i..i + len].iter().map(|b| format!("%{:02X}", b)).collect()))
+        };
+        if let Some(replacement) = replacement {
+            reencoded.push_str(&data[copied..i]);
+            reencoded.push_str(&replacement);
+            copied = i + len;
+        }
+        i += len;
+    }
+    if copied == 0 {
+        return Ok(Cow::Borrowed(data));
+    }
+    reencoded.push_str(&data[copied..]);
+    Ok(Cow::Owned(reencoded))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::reencode;
+    use super::EncodeContext;
+    use super::FromUrlEncodingError;
+
+    #[test]
+    fn it_reencodes_between_contexts() {
+        use super::EncodeContext::*;
+
+        assert_eq!("a%20b", reencode("a+b", QueryValue, Path).unwrap());
+        assert_eq!("a+b", reencode("a+b", QueryValue, QueryValue).unwrap());
+        assert_eq!("a/b", reencode("a/b", QueryValue, Path).unwrap());
+        assert_eq!("a%2Fb", reencode("a/b", Path, PathSegment).unwrap());
+        assert_eq!("x%3Fy%23", reencode("x?y#", Fragment, PathSegment).unwrap());
+        assert_eq!("%3A%40%2F", reencode(":@/", Path, Userinfo).unwrap());
+        assert_eq!("%C3%A9%20", reencode("é ", Path, Fragment).unwrap());
+    }
+
+    #[test]
+    fn it_reencodes_without_touching_escapes() {
+        let data = "%2f%20%2B%26";
+        assert!(matches!(reencode(data, EncodeContext::QueryValue, EncodeContext::PathSegment), Ok(Cow::Borrowed(_))));
+        assert!(matches!(reencode("abc", EncodeContext::Path, EncodeContext::QueryValue), Ok(Cow::Borrowed("abc"))));
+    }
+
+    #[test]
+    fn it_reencodes_unsuccessfully() {
+        assert!(matches!(
+            reencode("a%2", EncodeContext::Path, EncodeContext::QueryValue),
+            Err(FromUrlEncodingError::UriCharacterError { character: '%', index: 1 })
+        ));
+    }
+}
@@ -0,0 +1,100 @@
+use std::hint::black_box;
+#[cfg(feature = "zeroize")]
+use std::mem;
+
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroizing;
+
+#[cfg(not(feature = "zeroize"))]
+use super::decode_bytes;
+#[cfg(feature = "zeroize")]
+use super::{decode_to_vec, FromUrlEncodingError};
+
+/// Percent-decodes `encoded` and compares it with `plain` in constant time,
+/// for checking webhook signatures or tokens received in a query string.
+///
+/// The time taken depends only on the lengths of both inputs and on where
+/// `encoded` has escapes, never on how many bytes of the decoded value match
+/// `plain`. Malformed percent-encoding never matches.
+pub fn ct_eq_decoded(encoded: &str, plain: &str) -> bool {
+    #[cfg(feature = "zeroize")]
+    let decoded = decode_bytes_zeroizing(encoded);
+    #[cfg(not(feature = "zeroize"))]
+    let decoded = decode_bytes(encoded);
+    match decoded {
+        Ok(decoded) => ct_eq(&decoded, plain.as_bytes()),
+        Err(_) => false,
+    }
+}
+
+// Compares `a` and `b` without stopping at the first difference.
+fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let difference = a.iter().zip(b).fold(0, |difference, (x, y)| difference | (x ^ y));
+    // Keeps the compiler from turning the fold back into an early exit
+    black_box(difference) == 0
+}
+
+/// Percent-decodes `data` like `decode_to_vec`, into a buffer that is wiped
+/// when dropped, for values such as passwords and tokens.
+///
+/// The buffer is allocated once up front, so no copy of the decoded bytes is
+/// left behind by a reallocation, and it is wiped on error too.
+#[cfg(feature = "zeroize")]
+pub fn decode_bytes_zeroizing(data: &str) -> Result<Zeroizing<Vec<u8>>, FromUrlEncodingError> {
+    let mut decoded = Zeroizing::new(Vec::with_capacity(data.len()));
+    decode_to_vec(data, &mut decoded)?;
+    Ok(decoded)
+}
+
+/// Percent-decodes `data` like `decode`, into a string that is wiped when
+/// dropped.
+///
+/// If the decoded bytes aren't valid UTF-8, they are moved into the error as
+/// with `decode`, and are no longer wiped.
+#[cfg(feature = "zeroize")]
+pub fn decode_zeroizing(data: &str) -> Result<Zeroizing<String>, FromUrlEncodingError> {
+    let mut decoded = decode_bytes_zeroizing(data)?;
+    // Moves the buffer out without copying it
+    let bytes = mem::take(&mut *decoded);
+    String::from_utf8(bytes)
+        .map(Zeroizing::new)
+        .map_err(|e| FromUrlEncodingError::Utf8CharacterError { error: e })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ct_eq_decoded;
+    #[cfg(feature = "zeroize")]
+    use super::{decode_bytes_zeroizing, decode_zeroizing};
+    #[cfg(feature = "zeroize")]
+    use super::super::FromUrlEncodingError;
+
+    #[test]
+    fn it_compares_decoded_in_constant_time() {
+        assert!(ct_eq_decoded("sha256%3Dab%2Fcd", "sha256=ab/cd"));
+        assert!(ct_eq_decoded("", ""));
+        assert!(!ct_eq_decoded("sha256%3Dab%2Fcd", "sha256=ab/ce"));
+        assert!(!ct_eq_decoded("sha256%3Dab", "sha256=ab/cd"));
+        assert!(!ct_eq_decoded("sha256%3", "sha256%3"));
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn it_decodes_zeroizing_successfully() {
+        assert_eq!("p@ss word", decode_zeroizing("p%40ss%20word").unwrap().as_str());
+        assert_eq!(vec![0xFF, b'a'], *decode_bytes_zeroizing("%FFa").unwrap());
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn it_decodes_zeroizing_unsuccessfully() {
+        assert!(decode_bytes_zeroizing("100%").is_err());
+        match decode_zeroizing("%FF").unwrap_err() {
+            FromUrlEncodingError::Utf8CharacterError { error: e } => assert_eq!(vec![0xFF], e.into_bytes()),
+            _ => panic!()
+        }
+    }
+}
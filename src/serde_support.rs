@@ -0,0 +1,135 @@
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use encoded_string::UrlEncodedString;
+#[cfg(feature = "query")]
+use query::QueryParams;
+#[cfg(feature = "uri")]
+use uri::{validate_uri_reference, UriParts};
+
+// Deserializes a string with `parse`, which describes invalid input in an
+// error message.
+fn deserialize_str<'de, D, T, F>(deserializer: D, expecting: &'static str, parse: F) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    F: FnOnce(&str) -> Result<T, String>,
+{
+    struct StrVisitor<F>(&'static str, F);
+
+    impl<'de, T, F: FnOnce(&str) -> Result<T, String>> Visitor<'de> for StrVisitor<F> {
+        type Value = T;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str(self.0)
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<T, E> {
+            (self.1)(value).map_err(E::custom)
+        }
+    }
+
+    deserializer.deserialize_str(StrVisitor(expecting, parse))
+}
+
+/// Serializes as the encoded string.
+impl Serialize for UrlEncodedString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+/// Deserializes from an encoded string, failing if its percent-encoding is
+/// malformed.
+impl<'de> Deserialize<'de> for UrlEncodedString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<UrlEncodedString, D::Error> {
+        deserialize_str(deserializer, "a percent-encoded string", |value| {
+            UrlEncodedString::new(value.to_string()).map_err(|e| format!("invalid percent-encoding: {:?}", e))
+        })
+    }
+}
+
+/// Serializes as the encoded query string, without a leading `?`.
+#[cfg(feature = "query")]
+impl Serialize for QueryParams {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Deserializes from a query string, failing if its percent-encoding is
+/// malformed.
+#[cfg(feature = "query")]
+impl<'de> Deserialize<'de> for QueryParams {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<QueryParams, D::Error> {
+        deserialize_str(deserializer, "a query string", |value| {
+            QueryParams::parse(value).map_err(|e| format!("invalid query string: {:?}", e))
+        })
+    }
+}
+
+/// Serializes as the recomposed URI reference.
+#[cfg(feature = "uri")]
+impl Serialize for UriParts {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Deserializes from a URI reference, failing unless it is valid according
+/// to `validate_uri_reference`.
+#[cfg(feature = "uri")]
+impl<'de> Deserialize<'de> for UriParts {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<UriParts, D::Error> {
+        deserialize_str(deserializer, "a URI reference", |value| {
+            validate_uri_reference(value).map_err(|e| e.to_string())?;
+            Ok(UriParts::parse(value))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use encoded_string::UrlEncodedString;
+    #[cfg(feature = "query")]
+    use query::QueryParams;
+    #[cfg(feature = "uri")]
+    use uri::UriParts;
+
+    #[test]
+    fn it_serializes_url_encoded_string() {
+        let encoded = UrlEncodedString::encode("this that");
+        assert_eq!("\"this%20that\"", serde_json::to_string(&encoded).unwrap());
+        assert_eq!(encoded, serde_json::from_str("\"this%20that\"").unwrap());
+
+        let error = serde_json::from_str::<UrlEncodedString>("\"100%\"").unwrap_err();
+        assert!(error.to_string().starts_with("invalid percent-encoding"));
+        assert!(serde_json::from_str::<UrlEncodedString>("1").is_err());
+    }
+
+    #[cfg(feature = "query")]
+    #[test]
+    fn it_serializes_query_params() {
+        let mut params = QueryParams::new();
+        params.append("q", "a b");
+        params.append("page", "2");
+        assert_eq!("\"q=a%20b&page=2\"", serde_json::to_string(&params).unwrap());
+        assert_eq!(params, serde_json::from_str("\"q=a+b&page=2\"").unwrap());
+        assert!(serde_json::from_str::<QueryParams>("\"q=%zz\"").is_err());
+    }
+
+    #[cfg(feature = "uri")]
+    #[test]
+    fn it_serializes_uri_parts() {
+        let uri = "https://example.com/a%20b?q=1#top";
+        let parts: UriParts = serde_json::from_str(&format!("\"{}\"", uri)).unwrap();
+        assert_eq!(UriParts::parse(uri), parts);
+        assert_eq!(format!("\"{}\"", uri), serde_json::to_string(&parts).unwrap());
+
+        let error = serde_json::from_str::<UriParts>("\"https://exa mple.com/\"").unwrap_err();
+        assert!(error.to_string().starts_with("invalid URI host at offset 11"));
+    }
+}
@@ -0,0 +1,214 @@
+/// Turns `text` into a URL slug: lowercase ASCII letters and digits, with
+/// every other run of characters replaced by a single `-`.
+///
+/// Common accented Latin letters are transliterated, e.g. `ö` to `o` and `ß`
+/// to `ss`, and any other character separates words, so the result never
+/// needs to be percent-encoded. `Slugifier` makes all of this configurable.
+///
+/// ```
+/// use urlencoding::slugify;
+///
+/// assert_eq!("hello-world", slugify("Hello, Wörld!"));
+/// ```
+pub fn slugify(text: &str) -> String {
+    Slugifier::new().slugify(text)
+}
+
+/// A configurable `slugify`.
+///
+/// ```
+/// use urlencoding::Slugifier;
+///
+/// let slugifier = Slugifier::new()
+///     .separator("_")
+///     .max_len(20)
+///     .stop_words(&["a", "the"]);
+/// assert_eq!("tale_of_two_cities", slugifier.slugify("A Tale of Two Cities, the Novel"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Slugifier {
+    separator: String,
+    preserve_case: bool,
+    max_len: Option<usize>,
+    stop_words: Vec<String>,
+}
+
+impl Slugifier {
+    /// Creates a slugifier that behaves like `slugify`.
+    pub fn new() -> Slugifier {
+        Slugifier {
+            separator: "-".to_string(),
+            preserve_case: false,
+            max_len: None,
+            stop_words: Vec::new(),
+        }
+    }
+
+    /// Joins words with `separator` instead of `-`. It is used as-is, so it
+    /// should be URL-safe itself, such as `_` or `.`.
+    pub fn separator(mut self, separator: &str) -> Slugifier {
+        self.separator = separator.to_string();
+        self
+    }
+
+    /// Keeps uppercase letters instead of lowercasing them.
+    pub fn preserve_case(mut self, preserve_case: bool) -> Slugifier {
+        self.preserve_case = preserve_case;
+        self
+    }
+
+    /// Limits slugs to `max_len` bytes by dropping whole words from the end.
+    /// Only a first word that is too long on its own is cut.
+    pub fn max_len(mut self, max_len: usize) -> Slugifier {
+        self.max_len = Some(max_len);
+        self
+    }
+
+    /// Leaves out these words, compared case-insensitively.
+    pub fn stop_words(mut self, stop_words: &[&str]) -> Slugifier {
+        self.stop_words = stop_words.iter().map(|word| word.to_lowercase()).collect();
+        self
+    }
+
+    /// Turns `text` into a slug.
+    pub fn slugify(&self, text: &str) -> String {
+        let mut slug = String::with_capacity(text.len());
+        for word in words(text, self.preserve_case) {
+            if self.stop_words.iter().any(|stop_word| stop_word.eq_ignore_ascii_case(&word)) {
+                continue;
+            }
+            let separator = if slug.is_empty() { "" } else { self.separator.as_str() };
+            match self.max_len {
+                Some(max_len) if slug.is_empty() && word.len() > max_len => {
+                    // Words are ASCII, so any length is a char boundary
+                    slug.push_str(&word[..max_len]);
+                    break;
+                },
+                Some(max_len) if slug.len() + separator.len() + word.len() > max_len => break,
+                _ => {},
+            }
+            slug.push_str(separator);
+            slug.push_str(&word);
+        }
+        slug
+    }
+}
+
+impl Default for Slugifier {
+    fn default() -> Slugifier {
+        Slugifier::new()
+    }
+}
+
+// Splits `text` into words of ASCII letters and digits, transliterating the
+// letters `transliterate` knows and lowercasing the rest unless
+// `preserve_case` is set.
+fn words(text: &str, preserve_case: bool) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() {
+            if preserve_case {
+                word.push(c);
+            } else {
+                word.push(c.to_ascii_lowercase());
+            }
+            continue;
+        }
+        // Combining accents, as in decomposed text, are dropped
+        if let '\u{300}'..='\u{36F}' = c {
+            continue;
+        }
+        let mut known = true;
+        for lower in c.to_lowercase() {
+            match lower {
+                'a'..='z' if preserve_case && !c.is_lowercase() => word.push(lower.to_ascii_uppercase()),
+                'a'..='z' => word.push(lower),
+                '\u{300}'..='\u{36F}' => {},
+                _ => match transliterate(lower) {
+                    Some(ascii) if preserve_case && !c.is_lowercase() => word.push_str(&ascii.to_ascii_uppercase()),
+                    Some(ascii) => word.push_str(ascii),
+                    None => known = false,
+                },
+            }
+        }
+        if !known && !word.is_empty() {
+            words.push(word);
+            word = String::new();
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+// Returns the ASCII spelling of a lowercase accented Latin letter.
+fn transliterate(c: char) -> Option<&'static str> {
+    let ascii = match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+        'æ' => "ae",
+        'ç' | 'ć' | 'č' => "c",
+        'ď' | 'đ' | 'ð' => "d",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ę' | 'ě' => "e",
+        'ğ' => "g",
+        'ì' | 'í' | 'î' | 'ï' | 'ī' | 'ı' => "i",
+        'ł' | 'ľ' => "l",
+        'ñ' | 'ń' | 'ň' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ő' => "o",
+        'œ' => "oe",
+        'ř' => "r",
+        'ś' | 'š' | 'ş' => "s",
+        'ß' => "ss",
+        'ť' | 'ţ' => "t",
+        'þ' => "th",
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' | 'ű' => "u",
+        'ý' | 'ÿ' => "y",
+        'ź' | 'ż' | 'ž' => "z",
+        _ => return None,
+    };
+    Some(ascii)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::slugify;
+    use super::Slugifier;
+
+    #[test]
+    fn it_slugifies_successfully() {
+        assert_eq!("hello-world", slugify("Hello, Wörld!"));
+        assert_eq!("rust-2018-edition", slugify("  Rust 2018 -- Edition  "));
+        assert_eq!("strasse-cafe-creme", slugify("Straße: Café Crème"));
+        assert_eq!("cafe-istanbul", slugify("Cafe\u{301} İstanbul"));
+    }
+
+    #[test]
+    fn it_slugifies_untransliterated_characters_as_separators() {
+        assert_eq!("i-rust", slugify("I ❤️ Rust"));
+        assert_eq!("tokyo", slugify("東京 tokyo"));
+        assert_eq!("", slugify("東京"));
+    }
+
+    #[test]
+    fn it_slugifies_with_separator_and_case() {
+        let slugifier = Slugifier::new().separator("_").preserve_case(true);
+        assert_eq!("Hello_World_OEUVRE_AEon_Istanbul", slugifier.slugify("Hello, Wörld! ŒUVRE Æon İstanbul"));
+    }
+
+    #[test]
+    fn it_slugifies_with_max_len_on_word_boundaries() {
+        let slugifier = Slugifier::new().max_len(12);
+        assert_eq!("hello-world", slugifier.slugify("Hello World Again"));
+        assert_eq!("hello-world", slugifier.slugify("Hello World"));
+        assert_eq!("supercalifra", slugifier.slugify("Supercalifragilistic words"));
+        assert_eq!("", Slugifier::new().max_len(0).slugify("Hello"));
+    }
+
+    #[test]
+    fn it_slugifies_without_stop_words() {
+        let slugifier = Slugifier::new().stop_words(&["The", "of", "and"]);
+        assert_eq!("lord-rings", slugifier.slugify("The Lord of the Rings"));
+        assert_eq!("", slugifier.slugify("and the"));
+    }
+}
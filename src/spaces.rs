@@ -0,0 +1,127 @@
+use std::borrow::Cow;
+use std::mem;
+
+use super::hex_value;
+
+/// Rewrites every `+` of form-encoded `data` as `%20`, so it means a space
+/// wherever it ends up, such as in a signature base string or a path.
+///
+/// Nothing else is decoded or validated, and `data` is borrowed if it holds
+/// no `+`. Only use this where `+` stands for a space, as in a query string
+/// or form body: elsewhere a `+` is a literal plus sign.
+///
+/// ```
+/// use urlencoding::plus_to_percent20;
+///
+/// assert_eq!("q=a%20b%2Bc%20", plus_to_percent20("q=a+b%2Bc+"));
+/// ```
+pub fn plus_to_percent20(data: &str) -> Cow<'_, str> {
+    if data.contains('+') {
+        Cow::Owned(data.replace('+', "%20"))
+    } else {
+        Cow::Borrowed(data)
+    }
+}
+
+/// Rewrites every `%20` escape of `data` as `+`, the shorter form-encoded
+/// spelling of a space, and borrows `data` if there is none.
+///
+/// Other escapes are skipped without being decoded or validated, so the
+/// `20` of `%2520` is left alone. The result only means the same where `+`
+/// stands for a space, as in a query string or form body.
+///
+/// ```
+/// use urlencoding::percent20_to_plus;
+///
+/// assert_eq!("q=a+b+%2520", percent20_to_plus("q=a%20b%20%2520"));
+/// ```
+pub fn percent20_to_plus(data: &str) -> Cow<'_, str> {
+    if find_percent20(data.as_bytes(), 0).is_none() {
+        return Cow::Borrowed(data);
+    }
+    let mut rewritten = data.to_string();
+    percent20_to_plus_in_place(&mut rewritten);
+    Cow::Owned(rewritten)
+}
+
+/// Rewrites every `%20` escape of `data` as `+` like `percent20_to_plus`,
+/// shifting the rest of the string down in its own buffer instead of
+/// allocating a new one.
+///
+/// ```
+/// use urlencoding::percent20_to_plus_in_place;
+///
+/// let mut query = String::from("a=b%20c&d=%20");
+/// percent20_to_plus_in_place(&mut query);
+/// assert_eq!("a=b+c&d=+", query);
+/// ```
+pub fn percent20_to_plus_in_place(data: &mut String) {
+    let mut bytes = mem::take(data).into_bytes();
+    let mut read = 0;
+    let mut written = 0;
+    while let Some(found) = find_percent20(&bytes, read) {
+        bytes.copy_within(read..found, written);
+        written += found - read;
+        bytes[written] = b'+';
+        written += 1;
+        read = found + 3;
+    }
+    bytes.copy_within(read.., written);
+    bytes.truncate(written + bytes.len() - read);
+    // Only ASCII was replaced with ASCII, so the bytes are still UTF-8
+    *data = String::from_utf8(bytes).unwrap_or_default();
+}
+
+// Finds the next `%20` escape at or after `from`, skipping over other
+// escapes so their hex digits aren't mistaken for the start of one.
+fn find_percent20(bytes: &[u8], from: usize) -> Option<usize> {
+    let mut i = from;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            match (bytes.get(i + 1), bytes.get(i + 2)) {
+                (Some(b'2'), Some(b'0')) => return Some(i),
+                (Some(&high), Some(&low)) if hex_value(high).is_some() && hex_value(low).is_some() => i += 3,
+                _ => i += 1,
+            }
+        } else {
+            i += 1;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::percent20_to_plus;
+    use super::percent20_to_plus_in_place;
+    use super::plus_to_percent20;
+
+    #[test]
+    fn it_rewrites_plus_to_percent20() {
+        assert_eq!("%20%20", plus_to_percent20("++"));
+        assert!(matches!(plus_to_percent20("a%20b"), Cow::Borrowed("a%20b")));
+    }
+
+    #[test]
+    fn it_rewrites_percent20_to_plus() {
+        assert_eq!("+", percent20_to_plus("%20"));
+        assert_eq!("%+", percent20_to_plus("%%20"));
+        assert_eq!("é+%2", percent20_to_plus("é%20%2"));
+        assert!(matches!(percent20_to_plus("a+%2520%2"), Cow::Borrowed(_)));
+        for data in &["", "plain", "%20%20%20", "x%20", "%20y", "%zz%20", "a%2520b%20c"] {
+            assert_eq!(data.replace("%2520", "\0").replace("%20", "+").replace('\0', "%2520"), percent20_to_plus(data));
+        }
+    }
+
+    #[test]
+    fn it_rewrites_percent20_to_plus_in_place() {
+        let mut data = String::with_capacity(64);
+        data.push_str("%20a%20%20b%20");
+        let capacity = data.capacity();
+        percent20_to_plus_in_place(&mut data);
+        assert_eq!("+a++b+", data);
+        assert_eq!(capacity, data.capacity());
+    }
+}
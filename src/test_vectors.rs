@@ -0,0 +1,120 @@
+/// A case from the web-platform-tests `application/x-www-form-urlencoded`
+/// parser suite: an input and the name/value pairs it parses to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UrlencodedVector {
+    pub input: &'static str,
+    pub output: &'static [(&'static str, &'static str)],
+}
+
+/// The cases of `url/urlencoded-parser.any.js` from web-platform-tests,
+/// which browsers' `URLSearchParams` passes.
+pub const URLENCODED_VECTORS: &[UrlencodedVector] = &[
+    UrlencodedVector { input: "test", output: &[("test", "")] },
+    UrlencodedVector { input: "\u{FEFF}test=\u{FEFF}", output: &[("\u{FEFF}test", "\u{FEFF}")] },
+    UrlencodedVector { input: "%EF%BB%BFtest=%EF%BB%BF", output: &[("\u{FEFF}test", "\u{FEFF}")] },
+    UrlencodedVector { input: "%EF%BF%BF=%EF%BF%BF", output: &[("\u{FFFF}", "\u{FFFF}")] },
+    UrlencodedVector { input: "%FE%FF", output: &[("\u{FFFD}\u{FFFD}", "")] },
+    UrlencodedVector { input: "%FF%FE", output: &[("\u{FFFD}\u{FFFD}", "")] },
+    UrlencodedVector { input: "†&†=x", output: &[("†", ""), ("†", "x")] },
+    UrlencodedVector { input: "%C2", output: &[("\u{FFFD}", "")] },
+    UrlencodedVector { input: "%C2x", output: &[("\u{FFFD}x", "")] },
+    UrlencodedVector {
+        input: "_charset_=windows-1252&test=%C2x",
+        output: &[("_charset_", "windows-1252"), ("test", "\u{FFFD}x")],
+    },
+    UrlencodedVector { input: "", output: &[] },
+    UrlencodedVector { input: "a", output: &[("a", "")] },
+    UrlencodedVector { input: "a=b", output: &[("a", "b")] },
+    UrlencodedVector { input: "a=", output: &[("a", "")] },
+    UrlencodedVector { input: "=b", output: &[("", "b")] },
+    UrlencodedVector { input: "&", output: &[] },
+    UrlencodedVector { input: "&a", output: &[("a", "")] },
+    UrlencodedVector { input: "a&", output: &[("a", "")] },
+    UrlencodedVector { input: "a&a", output: &[("a", ""), ("a", "")] },
+    UrlencodedVector { input: "a&b&c", output: &[("a", ""), ("b", ""), ("c", "")] },
+    UrlencodedVector { input: "a=b&c=d", output: &[("a", "b"), ("c", "d")] },
+    UrlencodedVector { input: "a=b&c=d&", output: &[("a", "b"), ("c", "d")] },
+    UrlencodedVector { input: "&&&a=b&&&&c=d&", output: &[("a", "b"), ("c", "d")] },
+    UrlencodedVector { input: "a=a&a=b&a=c", output: &[("a", "a"), ("a", "b"), ("a", "c")] },
+    UrlencodedVector { input: "a==a", output: &[("a", "=a")] },
+    UrlencodedVector { input: "a=a+b+c+d", output: &[("a", "a b c d")] },
+    UrlencodedVector { input: "%=a", output: &[("%", "a")] },
+    UrlencodedVector { input: "%a=a", output: &[("%a", "a")] },
+    UrlencodedVector { input: "%a_=a", output: &[("%a_", "a")] },
+    UrlencodedVector { input: "%61=a", output: &[("a", "a")] },
+    UrlencodedVector { input: "%61+%4d%4D=", output: &[("a MM", "")] },
+    UrlencodedVector { input: "id=0&value=%", output: &[("id", "0"), ("value", "%")] },
+    UrlencodedVector { input: "b=%2sf%2a", output: &[("b", "%2sf*")] },
+    UrlencodedVector { input: "b=%2%2af%2a", output: &[("b", "%2*f*")] },
+    UrlencodedVector { input: "b=%%2a", output: &[("b", "%*")] },
+];
+
+/// A vector that a parser got wrong, with the pairs it returned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VectorMismatch {
+    pub vector: UrlencodedVector,
+    pub actual: Vec<(String, String)>,
+}
+
+/// Runs `parse` on the input of every vector in `URLENCODED_VECTORS`, and
+/// returns the ones where it didn't return the expected pairs. With the
+/// `form` feature, `parse_form` in `FormMode::Whatwg` passes them all.
+///
+/// ```
+/// use urlencoding::check_urlencoded_vectors;
+///
+/// // Splitting the pairs without decoding them
+/// let mismatches = check_urlencoded_vectors(|input| {
+///     input
+///         .split('&')
+///         .filter(|pair| !pair.is_empty())
+///         .map(|pair| {
+///             let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+///             (name.to_string(), value.to_string())
+///         })
+///         .collect()
+/// });
+/// assert!(mismatches.iter().any(|mismatch| mismatch.vector.input == "a=a+b+c+d"));
+/// assert!(mismatches.iter().all(|mismatch| mismatch.vector.input != "a=b"));
+/// ```
+pub fn check_urlencoded_vectors<F>(mut parse: F) -> Vec<VectorMismatch>
+where
+    F: FnMut(&str) -> Vec<(String, String)>,
+{
+    URLENCODED_VECTORS
+        .iter()
+        .filter_map(|&vector| {
+            let actual = parse(vector.input);
+            let matches = actual.len() == vector.output.len()
+                && actual.iter().zip(vector.output).all(|(actual, &(name, value))| actual.0 == name && actual.1 == value);
+            if matches {
+                None
+            } else {
+                Some(VectorMismatch { vector, actual })
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_urlencoded_vectors;
+    use super::URLENCODED_VECTORS;
+
+    #[test]
+    fn it_reports_vector_mismatches() {
+        let mismatches = check_urlencoded_vectors(|input| vec![(input.to_string(), String::new())]);
+        assert!(mismatches.iter().any(|mismatch| mismatch.vector.input == "a=b"));
+        assert!(mismatches.iter().all(|mismatch| mismatch.vector.input != "a"));
+        assert_eq!(URLENCODED_VECTORS.len(), mismatches.len() + 2);
+    }
+
+    #[test]
+    #[cfg(feature = "form")]
+    fn it_parses_whatwg_forms_like_browsers() {
+        use super::super::{parse_form, FormMode};
+
+        let mismatches = check_urlencoded_vectors(|input| parse_form(input, FormMode::Whatwg).unwrap());
+        assert!(mismatches.is_empty(), "{:?}", mismatches);
+    }
+}
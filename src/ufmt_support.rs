@@ -0,0 +1,53 @@
+use ufmt::{uDisplay, uWrite, Formatter};
+
+use display::{encode_pieces, Encoded};
+
+/// Percent-encodes `data` the same way as `encode`, straight into a `ufmt`
+/// writer, so nothing is allocated and `core::fmt` isn't needed.
+pub fn encode_to_uwrite<W: uWrite + ?Sized>(out: &mut W, data: &str) -> Result<(), W::Error> {
+    for piece in encode_pieces(data) {
+        out.write_str(piece)?;
+    }
+    Ok(())
+}
+
+// Percent-encodes everything written to it before passing it on to the
+// formatter, like `EncodingWriter`.
+struct EncodingFormatter<'a, 'w, W: uWrite + ?Sized>(&'a mut Formatter<'w, W>);
+
+impl<'a, 'w, W: uWrite + ?Sized> uWrite for EncodingFormatter<'a, 'w, W> {
+    type Error = W::Error;
+
+    fn write_str(&mut self, s: &str) -> Result<(), W::Error> {
+        for piece in encode_pieces(s) {
+            self.0.write_str(piece)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: uDisplay> uDisplay for Encoded<T> {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        uDisplay::fmt(&self.0, &mut Formatter::new(&mut EncodingFormatter(f)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::encode_to_uwrite;
+    use display::Encoded;
+
+    #[test]
+    fn it_encodes_to_uwrite() {
+        let mut out = String::from("q=");
+        encode_to_uwrite(&mut out, "👾 Exterminate!").unwrap();
+        assert_eq!("q=%F0%9F%91%BE%20Exterminate%21", out);
+    }
+
+    #[test]
+    fn it_encodes_lazily_with_udisplay() {
+        let mut out = String::new();
+        ufmt::uwrite!(out, "/search?q={}&page={}", Encoded("this that"), Encoded(2u32)).unwrap();
+        assert_eq!("/search?q=this%20that&page=2", out);
+    }
+}
@@ -0,0 +1,33 @@
+use unicode_normalization::UnicodeNormalization;
+
+use super::encode;
+
+/// Normalizes `data` to Unicode Normalization Form C before encoding it like
+/// `encode`.
+///
+/// The same visible text can be written with precomposed or combining
+/// characters, e.g. `é` as `U+00E9` or as `e` followed by `U+0301`, which
+/// `encode` turns into different URLs. After NFC normalization both encode
+/// the same way.
+pub fn encode_nfc(data: &str) -> String {
+    let normalized: String = data.nfc().collect();
+    encode(&normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::encode_nfc;
+    use super::super::encode;
+
+    #[test]
+    fn it_encodes_nfc_successfully() {
+        assert_eq!("caf%C3%A9", encode_nfc("cafe\u{301}"));
+        assert_eq!("caf%C3%A9", encode_nfc("caf\u{E9}"));
+        assert_eq!("cafe%CC%81", encode("cafe\u{301}"));
+    }
+
+    #[test]
+    fn it_encodes_nfc_ascii_unchanged() {
+        assert_eq!(encode("this that"), encode_nfc("this that"));
+    }
+}
@@ -0,0 +1,1232 @@
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use super::{decode, decode_bytes_lenient, encode_bytes_with, AsciiSet, EncodeSet, is_unreserved, preprocess_whatwg, FromUrlEncodingError, ParseMode};
+
+/// The five components of a URI reference (RFC 3986, section 3), still
+/// percent-encoded.
+///
+/// `Display` recomposes the components into a URI reference, so an unchanged
+/// `UriParts` formats back to the string it was parsed from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UriParts {
+    pub scheme: Option<String>,
+    pub authority: Option<String>,
+    pub path: String,
+    pub query: Option<String>,
+    pub fragment: Option<String>,
+}
+
+impl UriParts {
+    /// Splits a URI reference into its components, following the regular
+    /// expression in RFC 3986, appendix B. Every string is accepted;
+    /// components are not validated.
+    pub fn parse(uri: &str) -> UriParts {
+        let (rest, fragment) = match uri.find('#') {
+            Some(idx) => (&uri[..idx], Some(uri[idx + 1..].to_string())),
+            None => (uri, None),
+        };
+        let (rest, query) = match rest.find('?') {
+            Some(idx) => (&rest[..idx], Some(rest[idx + 1..].to_string())),
+            None => (rest, None),
+        };
+        let (scheme, rest) = match rest.find([':', '/']) {
+            Some(idx) if idx > 0 && rest.as_bytes()[idx] == b':' => {
+                (Some(rest[..idx].to_string()), &rest[idx + 1..])
+            },
+            _ => (None, rest),
+        };
+        let (authority, path) = match rest.strip_prefix("//") {
+            Some(rest) => {
+                let idx = rest.find('/').unwrap_or(rest.len());
+                (Some(rest[..idx].to_string()), &rest[idx..])
+            },
+            None => (None, rest),
+        };
+        UriParts {
+            scheme,
+            authority,
+            path: path.to_string(),
+            query,
+            fragment,
+        }
+    }
+}
+
+impl UriParts {
+    /// Splits a URI reference like `parse`, after cleaning it up with
+    /// `mode.normalize`, so in `ParseMode::Browser` it is split the way a
+    /// browser would.
+    ///
+    /// ```
+    /// use urlencoding::{ParseMode, UriParts};
+    ///
+    /// let parts = UriParts::parse_with(" https:\\\\evil.com\\x\n", ParseMode::Browser);
+    /// assert_eq!(Some("evil.com"), parts.authority.as_deref());
+    /// ```
+    pub fn parse_with(uri: &str, mode: ParseMode) -> UriParts {
+        UriParts::parse(&mode.normalize(uri))
+    }
+}
+
+impl ParseMode {
+    /// Cleans up a whole URL for this mode. `ParseMode::Strict` leaves it
+    /// as it is, while `ParseMode::Browser` applies `preprocess_whatwg` and
+    /// `normalize_backslashes`, then drops the port of a special scheme if
+    /// it is the default one, as in `https://example.com:443/`.
+    pub fn normalize(self, url: &str) -> Cow<'_, str> {
+        match self {
+            ParseMode::Strict => Cow::Borrowed(url),
+            ParseMode::Browser => {
+                let url = and_then(preprocess_whatwg(url), normalize_backslashes);
+                and_then(url, |url| match default_port_range(url) {
+                    Some((scheme, start, end)) if is_special_scheme(scheme) => {
+                        Cow::Owned(format!("{}{}", &url[..start], &url[end..]))
+                    },
+                    _ => Cow::Borrowed(url),
+                })
+            },
+        }
+    }
+}
+
+// Applies another clean-up step to a `Cow`, borrowing for as long as no step
+// has changed anything.
+fn and_then<'a, F>(url: Cow<'a, str>, step: F) -> Cow<'a, str>
+where
+    F: for<'b> FnOnce(&'b str) -> Cow<'b, str>,
+{
+    match url {
+        Cow::Borrowed(url) => step(url),
+        Cow::Owned(url) => Cow::Owned(step(&url).into_owned()),
+    }
+}
+
+impl fmt::Display for UriParts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(ref scheme) = self.scheme {
+            write!(f, "{}:", scheme)?;
+        }
+        if let Some(ref authority) = self.authority {
+            write!(f, "//{}", authority)?;
+        }
+        f.write_str(&self.path)?;
+        if let Some(ref query) = self.query {
+            write!(f, "?{}", query)?;
+        }
+        if let Some(ref fragment) = self.fragment {
+            write!(f, "#{}", fragment)?;
+        }
+        Ok(())
+    }
+}
+
+// Returns the byte range of the query of `url`, between the first '?' and
+// the fragment, or `None` if there is no query.
+#[cfg(feature = "query")]
+pub(crate) fn query_range(url: &str) -> Option<(usize, usize)> {
+    let end = url.find('#').unwrap_or(url.len());
+    let start = url[..end].find('?')? + 1;
+    Some((start, end))
+}
+
+/// A component of a URI, as named by RFC 3986.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Component {
+    Scheme,
+    Userinfo,
+    Host,
+    Port,
+    Path,
+    Query,
+    Fragment,
+}
+
+impl fmt::Display for Component {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Component::Scheme => "scheme",
+            Component::Userinfo => "userinfo",
+            Component::Host => "host",
+            Component::Port => "port",
+            Component::Path => "path",
+            Component::Query => "query",
+            Component::Fragment => "fragment",
+        })
+    }
+}
+
+/// The first violation of the RFC 3986 grammar found in a URI: the component
+/// it was found in and its byte offset in the whole input.
+///
+/// A missing part, such as the scheme of a relative reference passed to
+/// `validate_uri`, is reported at the offset where it was expected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UriValidationError {
+    pub component: Component,
+    pub index: usize,
+}
+
+impl fmt::Display for UriValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid URI {} at offset {}", self.component, self.index)
+    }
+}
+
+impl Error for UriValidationError {}
+
+/// Checks that `uri` is an absolute URI, with a scheme, matching the `URI`
+/// rule of RFC 3986: every component only holds the characters allowed in it
+/// and percent-encoding is well-formed.
+pub fn validate_uri(uri: &str) -> Result<(), UriValidationError> {
+    validate(uri, true)
+}
+
+/// Checks that `uri` matches the `URI-reference` rule of RFC 3986, so it may
+/// also be a relative reference such as `../a?b` or `//example.com/`.
+pub fn validate_uri_reference(uri: &str) -> Result<(), UriValidationError> {
+    validate(uri, false)
+}
+
+/// The four forms of an HTTP request target (RFC 9112, section 3.2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RequestTargetForm {
+    /// An absolute path with an optional query, such as `/a/b?c`, sent to
+    /// an origin server.
+    Origin,
+    /// An absolute URI without a fragment, such as `http://example.com/a`,
+    /// sent to a proxy.
+    Absolute,
+    /// A host and port, such as `example.com:443`, used by `CONNECT`.
+    Authority,
+    /// A lone `*`, used by a server-wide `OPTIONS`.
+    Asterisk,
+}
+
+/// Checks that `target` is a valid HTTP request target of the given form,
+/// following RFC 9112.
+///
+/// The error holds the offset of the first illegal byte: a fragment in an
+/// origin-form or absolute-form target, userinfo in an authority-form one,
+/// or a character not allowed in its component. A missing part, like the
+/// port of an authority-form target, is reported where it was expected.
+///
+/// ```
+/// use urlencoding::{validate_request_target, Component, RequestTargetForm, UriValidationError};
+///
+/// assert!(validate_request_target("/search?q=a%20b", RequestTargetForm::Origin).is_ok());
+/// assert!(validate_request_target("example.com:443", RequestTargetForm::Authority).is_ok());
+/// assert_eq!(
+///     Err(UriValidationError { component: Component::Path, index: 4 }),
+///     validate_request_target("/a/b c", RequestTargetForm::Origin),
+/// );
+/// ```
+pub fn validate_request_target(target: &str, form: RequestTargetForm) -> Result<(), UriValidationError> {
+    let error = |component, index| Err(UriValidationError { component, index });
+    match form {
+        RequestTargetForm::Origin => {
+            if !target.starts_with('/') {
+                return error(Component::Path, 0);
+            }
+            let fragment = target.find('#').unwrap_or(target.len());
+            let end = target[..fragment].find('?').unwrap_or(fragment);
+            validate_chars(&target[..end], 0, Component::Path, |b| is_pchar(b) || b == b'/')?;
+            if let Some(query) = target.get(end + 1..fragment) {
+                validate_chars(query, end + 1, Component::Query, |b| is_pchar(b) || b == b'/' || b == b'?')?;
+            }
+            if fragment < target.len() {
+                return error(Component::Fragment, fragment);
+            }
+            Ok(())
+        },
+        RequestTargetForm::Absolute => {
+            let end = target.find('#').unwrap_or(target.len());
+            validate(&target[..end], true)?;
+            if end < target.len() {
+                return error(Component::Fragment, end);
+            }
+            Ok(())
+        },
+        RequestTargetForm::Authority => {
+            validate_authority(target, 0)?;
+            if let Some(idx) = target.find('@') {
+                return error(Component::Userinfo, idx);
+            }
+            let host_end = if target.starts_with('[') {
+                target.find(']').map(|idx| idx + 1).unwrap_or(target.len())
+            } else {
+                target.rfind(':').unwrap_or(target.len())
+            };
+            if target.len() <= host_end + 1 {
+                return error(Component::Port, target.len());
+            }
+            Ok(())
+        },
+        RequestTargetForm::Asterisk => match target {
+            "*" => Ok(()),
+            _ if target.starts_with('*') => error(Component::Path, 1),
+            _ => error(Component::Path, 0),
+        },
+    }
+}
+
+/// Percent-decodes one component of a URI the way browsers do: `+` is a
+/// space only in the query, and stays a literal `+` everywhere else, such as
+/// in `/c++/` in a path.
+pub fn decode_component(data: &str, component: Component) -> Result<String, FromUrlEncodingError> {
+    match component {
+        Component::Query if data.contains('+') => decode(&data.replace('+', " ")),
+        _ => decode(data),
+    }
+}
+
+/// Replaces every `\` before the query with `/`, the way the WHATWG URL
+/// parser does for the special schemes `http`, `https`, `ws`, `wss`, `ftp`
+/// and `file`, so a filter sees the URL a browser will actually fetch:
+/// `https:\\evil.com\a` and `/\evil.com` both lead to `evil.com`.
+///
+/// A URL without a scheme is treated as relative to a web page, so it is
+/// normalized too. Other schemes are left alone, and so is the query and
+/// fragment of every URL, where browsers keep `\` as it is. Nothing is
+/// normalized unless this is called, as RFC 3986 allows no `\` at all.
+///
+/// ```
+/// use urlencoding::{normalize_backslashes, UriParts};
+///
+/// let parts = UriParts::parse(&normalize_backslashes("/\\evil.com\\x?a=\\"));
+/// assert_eq!(Some("evil.com"), parts.authority.as_deref());
+/// assert_eq!("/x", parts.path);
+/// assert_eq!(Some("a=\\"), parts.query.as_deref());
+/// ```
+pub fn normalize_backslashes(url: &str) -> Cow<'_, str> {
+    let end = url.find(['?', '#']).unwrap_or(url.len());
+    let before_query = &url[..end];
+    if !before_query.contains('\\') {
+        return Cow::Borrowed(url);
+    }
+    if let Some(scheme) = scheme_of(before_query) {
+        if !is_special_scheme(scheme) {
+            return Cow::Borrowed(url);
+        }
+    }
+    Cow::Owned(before_query.replace('\\', "/") + &url[end..])
+}
+
+/// The kind of a URI reference, as returned by `classify`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UrlKind {
+    /// A URL with a scheme, such as `https://example.com/` or `mailto:a@b`.
+    Absolute,
+    /// A network-path reference such as `//example.com/a`, which takes the
+    /// scheme of the base URL.
+    ProtocolRelative,
+    /// An absolute-path reference such as `/a/b`, which keeps the scheme and
+    /// authority of the base URL.
+    RootRelative,
+    /// A relative-path reference such as `a/b`, `../a` or `?q`, resolved
+    /// against the directory of the base URL.
+    Relative,
+    /// A reference to a fragment of the current document, such as `#top`.
+    Fragment,
+}
+
+/// Tells what kind of reference `url` is, following RFC 3986, section 4.2.
+///
+/// The reference is taken as it is. To classify it the way a browser would,
+/// where `/\evil.com` is protocol-relative, clean it up with
+/// `ParseMode::Browser.normalize` first.
+///
+/// ```
+/// use urlencoding::{classify, UrlKind};
+///
+/// assert_eq!(UrlKind::Absolute, classify("https://example.com/"));
+/// assert_eq!(UrlKind::ProtocolRelative, classify("//cdn.example.com/app.js"));
+/// assert_eq!(UrlKind::RootRelative, classify("/about"));
+/// assert_eq!(UrlKind::Relative, classify("img/logo.png"));
+/// assert_eq!(UrlKind::Fragment, classify("#top"));
+/// ```
+pub fn classify(url: &str) -> UrlKind {
+    if scheme_of(url).is_some() {
+        UrlKind::Absolute
+    } else if url.starts_with("//") {
+        UrlKind::ProtocolRelative
+    } else if url.starts_with('/') {
+        UrlKind::RootRelative
+    } else if url.starts_with('#') {
+        UrlKind::Fragment
+    } else {
+        UrlKind::Relative
+    }
+}
+
+/// Whether following `url` could run script: a `javascript:`, `vbscript:`
+/// or `livescript:` URL, or a `data:` URL of HTML, XHTML or SVG.
+///
+/// Sanitizers get fooled by spellings browsers still accept, so `url` is
+/// cleaned up with `preprocess_whatwg` and the scheme is compared after
+/// removing every control character and space in it, in any case. Some
+/// pipelines decode URLs before they are used, so percent-encoded schemes
+/// such as `java%73cript:` are decoded too, repeatedly.
+///
+/// ```
+/// use urlencoding::is_dangerous_scheme;
+///
+/// assert!(is_dangerous_scheme(" JavaScript:alert(1)"));
+/// assert!(is_dangerous_scheme("java\tscript:alert(1)"));
+/// assert!(is_dangerous_scheme("%6Aavascript%3Aalert(1)"));
+/// assert!(is_dangerous_scheme("data:text/html;base64,PHNjcmlwdD4="));
+/// assert!(!is_dangerous_scheme("data:image/png;base64,iVBORw0KGgo="));
+/// assert!(!is_dangerous_scheme("https://example.com/javascript:"));
+/// ```
+pub fn is_dangerous_scheme(url: &str) -> bool {
+    let mut url = preprocess_whatwg(url).into_owned();
+    // Each round undoes one layer of encoding; a few are enough to see
+    // through double encoding without looping on `%2525...`
+    for _ in 0..4 {
+        let prefix_len = url.find(['/', '?', '#']).unwrap_or(url.len());
+        if !url[..prefix_len].contains('%') {
+            break;
+        }
+        let decoded = String::from_utf8_lossy(&decode_bytes_lenient(&url[..prefix_len])).into_owned();
+        url = decoded + &url[prefix_len..];
+    }
+    let colon = match url.find(':') {
+        Some(colon) => colon,
+        None => return false,
+    };
+    let scheme: String = url[..colon].chars().filter(|&c| !c.is_control() && c != ' ').collect();
+    match scheme.to_ascii_lowercase().as_str() {
+        "javascript" | "vbscript" | "livescript" => true,
+        "data" => {
+            let media_type = &url[colon + 1..];
+            let media_type = &media_type[..media_type.find([';', ',']).unwrap_or(media_type.len())];
+            let media_type = media_type.trim().to_ascii_lowercase();
+            matches!(media_type.as_str(), "text/html" | "application/xhtml+xml" | "image/svg+xml" | "text/xml" | "application/xml")
+        },
+        _ => false,
+    }
+}
+
+/// Replaces `url` with `about:blank` if `is_dangerous_scheme` flags it, and
+/// borrows it otherwise, so a sanitizer can pass every link through it.
+///
+/// ```
+/// use urlencoding::sanitize_scheme;
+///
+/// assert_eq!("about:blank", sanitize_scheme("vbscript:msgbox(1)"));
+/// assert_eq!("/a?b", sanitize_scheme("/a?b"));
+/// ```
+pub fn sanitize_scheme(url: &str) -> Cow<'_, str> {
+    if is_dangerous_scheme(url) {
+        Cow::Borrowed("about:blank")
+    } else {
+        Cow::Borrowed(url)
+    }
+}
+
+// Returns the scheme at the start of `url`, if any.
+fn scheme_of(url: &str) -> Option<&str> {
+    let idx = url.find([':', '/', '\\'])?;
+    let scheme = &url[..idx];
+    let valid = url.as_bytes()[idx] == b':'
+        && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme.bytes().all(|b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'-' | b'.'));
+    if valid {
+        Some(scheme)
+    } else {
+        None
+    }
+}
+
+// The schemes the WHATWG URL standard gives special parsing rules.
+pub(crate) fn is_special_scheme(scheme: &str) -> bool {
+    ["http", "https", "ws", "wss", "ftp", "file"].iter().any(|special| special.eq_ignore_ascii_case(scheme))
+}
+
+// Well-known schemes and their default ports, sorted by scheme.
+const DEFAULT_PORTS: &[(&str, u16)] = &[
+    ("ftp", 21),
+    ("git", 9418),
+    ("http", 80),
+    ("https", 443),
+    ("imap", 143),
+    ("ldap", 389),
+    ("ldaps", 636),
+    ("nntp", 119),
+    ("pop", 110),
+    ("redis", 6379),
+    ("rtsp", 554),
+    ("sftp", 22),
+    ("smtp", 25),
+    ("ssh", 22),
+    ("telnet", 23),
+    ("ws", 80),
+    ("wss", 443),
+];
+
+/// Returns the default port of a well-known scheme, compared
+/// case-insensitively, such as 443 for `https`.
+///
+/// ```
+/// use urlencoding::default_port;
+///
+/// assert_eq!(Some(443), default_port("HTTPS"));
+/// assert_eq!(Some(21), default_port("ftp"));
+/// assert_eq!(None, default_port("mailto"));
+/// ```
+pub fn default_port(scheme: &str) -> Option<u16> {
+    let scheme = scheme.to_ascii_lowercase();
+    DEFAULT_PORTS
+        .binary_search_by(|&(known, _)| known.cmp(scheme.as_str()))
+        .ok()
+        .map(|idx| DEFAULT_PORTS[idx].1)
+}
+
+/// Removes the port from the authority of `url` if it is the default port
+/// of its scheme, as given by `default_port`, so `https://example.com:443/`
+/// becomes `https://example.com/`. A port written with leading zeros, such
+/// as `:0443`, is removed too. Without a change, `url` is borrowed.
+///
+/// ```
+/// use urlencoding::elide_default_port;
+///
+/// assert_eq!("https://example.com/a", elide_default_port("https://example.com:443/a"));
+/// assert_eq!("http://[::1]?q", elide_default_port("http://[::1]:80?q"));
+/// assert_eq!("http://example.com:8080/", elide_default_port("http://example.com:8080/"));
+/// ```
+pub fn elide_default_port(url: &str) -> Cow<'_, str> {
+    match default_port_range(url) {
+        Some((_, start, end)) => Cow::Owned(format!("{}{}", &url[..start], &url[end..])),
+        None => Cow::Borrowed(url),
+    }
+}
+
+// Finds the `:port` of the authority of `url`, if its scheme has a default
+// port and the port is that one. Returns the scheme and the byte range of
+// the port with its ':'.
+fn default_port_range(url: &str) -> Option<(&str, usize, usize)> {
+    let scheme = scheme_of(url)?;
+    let default = default_port(scheme)?;
+    let start = scheme.len() + 1;
+    let authority = url[start..].strip_prefix("//")?;
+    let authority = &authority[..authority.find(['/', '?', '#']).unwrap_or(authority.len())];
+    let host_start = authority.rfind('@').map(|idx| idx + 1).unwrap_or(0);
+    let host_port = &authority[host_start..];
+    // An IP literal without its closing ']' is malformed, so it is left alone
+    let colon = if host_port.starts_with('[') {
+        let end = host_port.find(']')? + 1;
+        end + host_port[end..].find(':')?
+    } else {
+        host_port.rfind(':')?
+    };
+    let port = &host_port[colon + 1..];
+    if port.is_empty() || !port.bytes().all(|b| b.is_ascii_digit()) || port.parse() != Ok(default) {
+        return None;
+    }
+    let colon = start + 2 + host_start + colon;
+    Some((scheme, colon, colon + 1 + port.len()))
+}
+
+pub(crate) fn is_sub_delim(b: u8) -> bool {
+    matches!(b, b'!' | b'$' | b'&' | b'\'' | b'(' | b')' | b'*' | b'+' | b',' | b';' | b'=')
+}
+
+pub(crate) fn is_pchar(b: u8) -> bool {
+    is_unreserved(b) || is_sub_delim(b) || b == b':' || b == b'@'
+}
+
+// Checks every byte of `data`, which starts at `offset` in the whole URI, is
+// either accepted by `is_allowed` or part of a well-formed `%XX` triplet.
+fn validate_chars<F: Fn(u8) -> bool>(
+    data: &str,
+    offset: usize,
+    component: Component,
+    is_allowed: F,
+) -> Result<(), UriValidationError> {
+    let bytes = data.as_bytes();
+    let error = |i| Err(UriValidationError { component, index: offset + i });
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            for j in i + 1..i + 3 {
+                match bytes.get(j) {
+                    Some(b) if b.is_ascii_hexdigit() => {},
+                    Some(_) => return error(j),
+                    None => return error(i),
+                }
+            }
+            i += 3;
+        } else if is_allowed(bytes[i]) {
+            i += 1;
+        } else {
+            return error(i);
+        }
+    }
+    Ok(())
+}
+
+fn validate(uri: &str, require_scheme: bool) -> Result<(), UriValidationError> {
+    let (rest, fragment) = match uri.find('#') {
+        Some(idx) => (&uri[..idx], Some(idx + 1)),
+        None => (uri, None),
+    };
+    let (rest, query) = match rest.find('?') {
+        Some(idx) => (&rest[..idx], Some(idx + 1)),
+        None => (rest, None),
+    };
+
+    let mut offset = 0;
+    match rest.find([':', '/']) {
+        Some(idx) if rest.as_bytes()[idx] == b':' => {
+            validate_scheme(&rest[..idx])?;
+            offset = idx + 1;
+        },
+        _ if require_scheme => return Err(UriValidationError { component: Component::Scheme, index: 0 }),
+        _ => {},
+    }
+
+    let hier_part = &rest[offset..];
+    if let Some(authority) = hier_part.strip_prefix("//") {
+        let end = authority.find('/').unwrap_or(authority.len());
+        validate_authority(&authority[..end], offset + 2)?;
+        offset += 2 + end;
+    }
+    validate_chars(&rest[offset..], offset, Component::Path, |b| is_pchar(b) || b == b'/')?;
+
+    let is_query_char = |b| is_pchar(b) || b == b'/' || b == b'?';
+    if let Some(start) = query {
+        let end = fragment.map(|idx| idx - 1).unwrap_or(uri.len());
+        validate_chars(&uri[start..end], start, Component::Query, is_query_char)?;
+    }
+    if let Some(start) = fragment {
+        validate_chars(&uri[start..], start, Component::Fragment, is_query_char)?;
+    }
+    Ok(())
+}
+
+fn validate_scheme(scheme: &str) -> Result<(), UriValidationError> {
+    for (i, b) in scheme.bytes().enumerate() {
+        let valid = match b {
+            b'A'..=b'Z' | b'a'..=b'z' => true,
+            b'0'..=b'9' | b'+' | b'-' | b'.' => i > 0,
+            _ => false,
+        };
+        if !valid {
+            return Err(UriValidationError { component: Component::Scheme, index: i });
+        }
+    }
+    if scheme.is_empty() {
+        return Err(UriValidationError { component: Component::Scheme, index: 0 });
+    }
+    Ok(())
+}
+
+// Validates `userinfo@host:port`, which starts at `offset` in the whole URI.
+fn validate_authority(authority: &str, offset: usize) -> Result<(), UriValidationError> {
+    let host_start = match authority.rfind('@') {
+        Some(idx) => {
+            validate_chars(&authority[..idx], offset, Component::Userinfo, |b| {
+                is_unreserved(b) || is_sub_delim(b) || b == b':'
+            })?;
+            idx + 1
+        },
+        None => 0,
+    };
+    let host_port = &authority[host_start..];
+    let offset = offset + host_start;
+
+    let port_start = if host_port.starts_with('[') {
+        let end = match host_port.find(']') {
+            Some(end) => end,
+            None => return Err(UriValidationError { component: Component::Host, index: offset }),
+        };
+        validate_ip_literal(&host_port[1..end], offset + 1)?;
+        match host_port.as_bytes().get(end + 1) {
+            None => None,
+            Some(b':') => Some(end + 2),
+            Some(_) => return Err(UriValidationError { component: Component::Host, index: offset + end + 1 }),
+        }
+    } else {
+        let port_start = host_port.rfind(':').map(|idx| idx + 1);
+        let host = &host_port[..port_start.map(|idx| idx - 1).unwrap_or(host_port.len())];
+        validate_chars(host, offset, Component::Host, |b| is_unreserved(b) || is_sub_delim(b))?;
+        port_start
+    };
+
+    if let Some(start) = port_start {
+        if let Some(i) = host_port[start..].bytes().position(|b| !b.is_ascii_digit()) {
+            return Err(UriValidationError { component: Component::Port, index: offset + start + i });
+        }
+    }
+    Ok(())
+}
+
+// Validates the inside of `[...]`: an IPv6 address, optionally followed by
+// an RFC 6874 zone identifier, or `IPvFuture`.
+fn validate_ip_literal(literal: &str, offset: usize) -> Result<(), UriValidationError> {
+    let error = |i| Err(UriValidationError { component: Component::Host, index: offset + i });
+    if literal.starts_with(['v', 'V']) {
+        // "v" 1*HEXDIG "." 1*( unreserved / sub-delims / ":" )
+        let dot = match literal.find('.') {
+            Some(dot) => dot,
+            None => return error(literal.len()),
+        };
+        if let Some(i) = literal[1..dot].bytes().position(|b| !b.is_ascii_hexdigit()) {
+            return error(1 + i);
+        }
+        if dot == 1 || dot + 1 == literal.len() {
+            return error(dot);
+        }
+        match literal[dot + 1..].bytes().position(|b| !(is_unreserved(b) || is_sub_delim(b) || b == b':')) {
+            Some(i) => error(dot + 1 + i),
+            None => Ok(()),
+        }
+    } else {
+        let (address, zone_id) = split_zone_id(literal);
+        if address.parse::<Ipv6Addr>().is_err() {
+            return error(0);
+        }
+        match zone_id {
+            // The '%' must be written as "%25" and the zone can't be empty
+            Some(zone_id) if !literal[address.len()..].starts_with("%25") || zone_id.is_empty() => {
+                error(address.len())
+            },
+            Some(zone_id) => validate_chars(zone_id, offset + address.len() + 3, Component::Host, is_unreserved),
+            None => Ok(()),
+        }
+    }
+}
+
+// Splits an IPv6 literal at the '%' starting its zone identifier, also
+// skipping the "25" of the "%25" delimiter.
+fn split_zone_id(literal: &str) -> (&str, Option<&str>) {
+    match literal.find('%') {
+        Some(idx) => {
+            let zone_id = &literal[idx..];
+            (&literal[..idx], Some(zone_id.strip_prefix("%25").unwrap_or(&zone_id[1..])))
+        },
+        None => (literal, None),
+    }
+}
+
+/// The host of an authority.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Host {
+    /// A registered name such as `example.com`, percent-decoded.
+    Name(String),
+    Ipv4(Ipv4Addr),
+    /// An IPv6 address, written inside brackets in a URI. A link-local
+    /// address may carry a zone identifier such as `eth0`, percent-decoded,
+    /// which is written as `[fe80::1%25eth0]` (RFC 6874).
+    Ipv6 { address: Ipv6Addr, zone_id: Option<String> },
+    /// An `IPvFuture` literal such as `v7.abc`, without its brackets.
+    IpFuture(String),
+}
+
+impl fmt::Display for Host {
+    /// Formats the host the way it is written in a URI, with brackets around
+    /// IP literals and registered names percent-encoded.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Host::Name(name) => f.write_str(&encode_bytes_with(name.as_bytes(), |b| {
+                is_unreserved(b) || is_sub_delim(b)
+            })),
+            Host::Ipv4(address) => write!(f, "{}", address),
+            Host::Ipv6 { address, zone_id: None } => write!(f, "[{}]", address),
+            Host::Ipv6 { address, zone_id: Some(zone_id) } => {
+                write!(f, "[{}%25{}]", address, encode_bytes_with(zone_id.as_bytes(), is_unreserved))
+            },
+            Host::IpFuture(literal) => write!(f, "[{}]", literal),
+        }
+    }
+}
+
+/// Builds the userinfo of a URL, `user` or `user:password`, for an
+/// authority such as `user:password@example.com`. Both parts are encoded
+/// with `AsciiSet::userinfo`, so a `:`, `@` or `/` in them can't change
+/// where the URL's parts are split.
+///
+/// ```
+/// use urlencoding::encode_userinfo;
+///
+/// assert_eq!("j%40ne:p%3Ass%2Fw0rd", encode_userinfo("j@ne", Some("p:ss/w0rd")));
+/// assert_eq!("alice", encode_userinfo("alice", None));
+/// ```
+pub fn encode_userinfo(user: &str, password: Option<&str>) -> String {
+    let mut userinfo = AsciiSet::userinfo().encode(user);
+    if let Some(password) = password {
+        userinfo.push(':');
+        userinfo.push_str(&AsciiSet::userinfo().encode(password));
+    }
+    userinfo
+}
+
+/// Splits a userinfo into the user and the password at the first `:`, then
+/// decodes both. Splitting comes first, so an encoded `%3A` stays in its
+/// part.
+///
+/// ```
+/// use urlencoding::decode_userinfo;
+///
+/// let (user, password) = decode_userinfo("j%40ne:p%3Ass:w0rd").unwrap();
+/// assert_eq!("j@ne", user);
+/// assert_eq!(Some("p:ss:w0rd".to_string()), password);
+/// ```
+pub fn decode_userinfo(userinfo: &str) -> Result<(String, Option<String>), FromUrlEncodingError> {
+    match userinfo.split_once(':') {
+        Some((user, password)) => Ok((decode(user)?, Some(decode(password)?))),
+        None => Ok((decode(userinfo)?, None)),
+    }
+}
+
+/// The parts of a `userinfo@host:port` authority, decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Authority {
+    pub userinfo: Option<String>,
+    pub host: Host,
+    /// `None` if the port is missing or empty, as in `example.com:`.
+    pub port: Option<u16>,
+}
+
+/// Parses an authority such as `user:pw@[::1]:8080`, splitting the userinfo
+/// at the last `@` and the port at the last `:` outside an IP literal.
+///
+/// The authority is validated against RFC 3986 first, and the userinfo and
+/// registered name are percent-decoded. Offsets in the error are relative to
+/// `authority`. A port above 65535, or a component that doesn't decode to
+/// valid UTF-8, is reported at the start of that component.
+pub fn parse_authority(authority: &str) -> Result<Authority, UriValidationError> {
+    validate_authority(authority, 0)?;
+    let error = |component, index| UriValidationError { component, index };
+
+    let (userinfo, host_start) = match authority.rfind('@') {
+        Some(idx) => {
+            let userinfo = decode(&authority[..idx]).map_err(|_| error(Component::Userinfo, 0))?;
+            (Some(userinfo), idx + 1)
+        },
+        None => (None, 0),
+    };
+    let host_port = &authority[host_start..];
+    let host_end = if host_port.starts_with('[') {
+        host_port.find(']').map(|idx| idx + 1).unwrap_or(host_port.len())
+    } else {
+        host_port.rfind(':').unwrap_or(host_port.len())
+    };
+
+    let host = &host_port[..host_end];
+    let host = if let Some(literal) = host.strip_prefix('[') {
+        let literal = &literal[..literal.len() - 1];
+        let (address, zone_id) = split_zone_id(literal);
+        match address.parse() {
+            Ok(address) => Host::Ipv6 {
+                address,
+                zone_id: match zone_id {
+                    Some(zone_id) => Some(decode(zone_id).map_err(|_| error(Component::Host, host_start))?),
+                    None => None,
+                },
+            },
+            Err(_) => Host::IpFuture(literal.to_string()),
+        }
+    } else if let Ok(address) = host.parse() {
+        Host::Ipv4(address)
+    } else {
+        Host::Name(decode(host).map_err(|_| error(Component::Host, host_start))?)
+    };
+
+    let port = match host_port.get(host_end + 1..) {
+        Some(port) if !port.is_empty() => {
+            Some(port.parse().map_err(|_| error(Component::Port, host_start + host_end + 1))?)
+        },
+        _ => None,
+    };
+    Ok(Authority { userinfo, host, port })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::classify;
+    use super::decode_component;
+    use super::decode_userinfo;
+    use super::default_port;
+    use super::elide_default_port;
+    use super::is_dangerous_scheme;
+    use super::sanitize_scheme;
+    use super::encode_userinfo;
+    use super::normalize_backslashes;
+    use super::parse_authority;
+    use super::validate_request_target;
+    use super::validate_uri;
+    use super::validate_uri_reference;
+    use super::Authority;
+    use super::Component;
+    use super::Host;
+    use super::ParseMode;
+    use super::RequestTargetForm;
+    use super::UriParts;
+    use super::UriValidationError;
+    use super::UrlKind;
+
+    #[test]
+    fn it_parses_uri_parts_successfully() {
+        let parts = UriParts::parse("https://user@example.com:8080/a/b?x=1&y=2#top");
+        assert_eq!(Some("https".to_string()), parts.scheme);
+        assert_eq!(Some("user@example.com:8080".to_string()), parts.authority);
+        assert_eq!("/a/b", parts.path);
+        assert_eq!(Some("x=1&y=2".to_string()), parts.query);
+        assert_eq!(Some("top".to_string()), parts.fragment);
+    }
+
+    #[test]
+    fn it_parses_uri_parts_relative_references() {
+        let parts = UriParts::parse("//example.com?#");
+        assert_eq!(None, parts.scheme);
+        assert_eq!(Some("example.com".to_string()), parts.authority);
+        assert_eq!("", parts.path);
+        assert_eq!(Some("".to_string()), parts.query);
+        assert_eq!(Some("".to_string()), parts.fragment);
+
+        let parts = UriParts::parse("a/b:c");
+        assert_eq!(None, parts.scheme);
+        assert_eq!("a/b:c", parts.path);
+
+        let parts = UriParts::parse("mailto:a@example.com");
+        assert_eq!(Some("mailto".to_string()), parts.scheme);
+        assert_eq!(None, parts.authority);
+        assert_eq!("a@example.com", parts.path);
+    }
+
+    #[test]
+    fn it_formats_uri_parts_back_unchanged() {
+        for uri in &["https://example.com/a?b#c", "//example.com?#", "/a/b", "", "urn:isbn:123", "?q"] {
+            assert_eq!(*uri, UriParts::parse(uri).to_string());
+        }
+    }
+
+    fn error(component: Component, index: usize) -> Result<(), UriValidationError> {
+        Err(UriValidationError { component, index })
+    }
+
+    #[test]
+    fn it_validates_uri_successfully() {
+        let uris = [
+            "https://user:pw@example.com:8080/a/b;c=d?x=1&y=/?#top/?",
+            "http://[::1]:80/",
+            "http://[2001:db8::7]/c=GB?objectClass?one",
+            "http://[v7.fe80::1-a]/",
+            "ldap://192.0.2.16:/",
+            "mailto:John.Doe@example.com",
+            "urn:oasis:names:specification:docbook:dtd:xml:4.1.2",
+            "file:///etc/hosts",
+            "a+b-c.d:%20",
+        ];
+        for uri in &uris {
+            assert_eq!(Ok(()), validate_uri(uri), "{}", uri);
+        }
+    }
+
+    #[test]
+    fn it_validates_uri_reference_successfully() {
+        for uri in &["", "../a/b?c", "//example.com", "?q", "#f", "/a:b", "a/b:c"] {
+            assert_eq!(Ok(()), validate_uri_reference(uri), "{}", uri);
+        }
+    }
+
+    #[test]
+    fn it_validates_uri_unsuccessfully() {
+        assert_eq!(error(Component::Scheme, 0), validate_uri("/relative"));
+        assert_eq!(error(Component::Scheme, 0), validate_uri("1http://example.com"));
+        assert_eq!(error(Component::Scheme, 4), validate_uri("http_s://example.com"));
+        assert_eq!(error(Component::Userinfo, 8), validate_uri("http://a@b@example.com"));
+        assert_eq!(error(Component::Host, 10), validate_uri("http://exa mple.com"));
+        assert_eq!(error(Component::Host, 7), validate_uri("http://[::1/"));
+        assert_eq!(error(Component::Host, 8), validate_uri("http://[::g]/"));
+        assert_eq!(error(Component::Host, 12), validate_uri("http://[::1]x/"));
+        assert_eq!(error(Component::Port, 20), validate_uri("http://example.com:8a/"));
+        assert_eq!(error(Component::Path, 20), validate_uri("http://example.com/a b"));
+        assert_eq!(error(Component::Path, 20), validate_uri("http://example.com/%zz"));
+        assert_eq!(error(Component::Query, 21), validate_uri("http://example.com/?a[]=1"));
+        assert_eq!(error(Component::Query, 20), validate_uri("http://example.com/?%2"));
+        assert_eq!(error(Component::Fragment, 23), validate_uri("http://example.com/?a#b#c"));
+        assert_eq!(error(Component::Path, 10), validate_uri("mailto:caf\u{e9}@example.com"));
+    }
+
+    #[test]
+    fn it_validates_uri_reference_unsuccessfully() {
+        assert_eq!(error(Component::Scheme, 0), validate_uri_reference(":a"));
+        assert_eq!(error(Component::Path, 1), validate_uri_reference("a b"));
+        assert_eq!(error(Component::Host, 2), validate_uri_reference("//<host>"));
+    }
+
+    #[test]
+    fn it_formats_uri_validation_error() {
+        let error = validate_uri("http://example.com/a b").unwrap_err();
+        assert_eq!("invalid URI path at offset 20", error.to_string());
+    }
+
+    #[test]
+    fn it_parses_authority_successfully() {
+        let expected = Authority {
+            userinfo: Some("user:p@ss".to_string()),
+            host: Host::Name("example.com".to_string()),
+            port: Some(8080),
+        };
+        assert_eq!(expected, parse_authority("user:p%40ss@example.com:8080").unwrap());
+
+        let authority = parse_authority("caf%C3%A9.example").unwrap();
+        assert_eq!(None, authority.userinfo);
+        assert_eq!(Host::Name("café.example".to_string()), authority.host);
+        assert_eq!(None, authority.port);
+    }
+
+    #[test]
+    fn it_parses_authority_ip_hosts() {
+        let authority = parse_authority("[2001:db8::1]:443").unwrap();
+        assert_eq!(Host::Ipv6 { address: "2001:db8::1".parse().unwrap(), zone_id: None }, authority.host);
+        assert_eq!(Some(443), authority.port);
+
+        assert_eq!(Host::Ipv4("127.0.0.1".parse().unwrap()), parse_authority("127.0.0.1:").unwrap().host);
+        assert_eq!(Host::IpFuture("v1.x".to_string()), parse_authority("[v1.x]").unwrap().host);
+    }
+
+    #[test]
+    fn it_parses_authority_unsuccessfully() {
+        assert_eq!(error(Component::Port, 12), parse_authority("example.com:99999").map(|_| ()));
+        assert_eq!(error(Component::Host, 11), parse_authority("example.com:80:80").map(|_| ()));
+        assert_eq!(error(Component::Host, 0), parse_authority("%FF").map(|_| ()));
+        assert_eq!(Component::Userinfo, parse_authority("a@b@[::1]").unwrap_err().component);
+    }
+
+    #[test]
+    fn it_formats_hosts() {
+        assert_eq!("[::1]", Host::Ipv6 { address: "::1".parse().unwrap(), zone_id: None }.to_string());
+        assert_eq!("caf%C3%A9.example", Host::Name("café.example".to_string()).to_string());
+    }
+
+    #[test]
+    fn it_parses_authority_ipv6_zone_ids() {
+        let authority = parse_authority("[fe80::1%25eth0]:8080").unwrap();
+        let expected = Host::Ipv6 {
+            address: "fe80::1".parse().unwrap(),
+            zone_id: Some("eth0".to_string()),
+        };
+        assert_eq!(expected, authority.host);
+        assert_eq!(Some(8080), authority.port);
+
+        let authority = parse_authority("[fe80::1%25en%2F1]").unwrap();
+        match authority.host {
+            Host::Ipv6 { zone_id, .. } => assert_eq!(Some("en/1".to_string()), zone_id),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn it_validates_ipv6_zone_ids_unsuccessfully() {
+        assert_eq!(error(Component::Host, 15), validate_uri("http://[fe80::1%eth0]/"));
+        assert_eq!(error(Component::Host, 15), validate_uri("http://[fe80::1%25]/"));
+        assert_eq!(error(Component::Host, 19), validate_uri("http://[fe80::1%25a:b]/"));
+        assert_eq!(error(Component::Host, 8), validate_uri("http://[fe80::g%25eth0]/"));
+    }
+
+    #[test]
+    fn it_formats_ipv6_zone_ids() {
+        let host = Host::Ipv6 {
+            address: "fe80::1".parse().unwrap(),
+            zone_id: Some("en/1".to_string()),
+        };
+        assert_eq!("[fe80::1%25en%2F1]", host.to_string());
+        assert_eq!(Ok(()), validate_uri(&format!("http://{}/", host)));
+        assert_eq!(host, parse_authority(&host.to_string()).unwrap().host);
+    }
+
+    #[test]
+    fn it_decodes_component_spaces() {
+        assert_eq!("/c++/a b", decode_component("/c++/a%20b", Component::Path).unwrap());
+        assert_eq!("c  a b", decode_component("c++a%20b", Component::Query).unwrap());
+        assert_eq!("a+b", decode_component("a+b", Component::Fragment).unwrap());
+        assert_eq!("a+b", decode_component("a%2Bb", Component::Query).unwrap());
+        assert!(decode_component("a+%2", Component::Query).is_err());
+    }
+
+    #[test]
+    fn it_normalizes_backslashes() {
+        assert_eq!("https://evil.com/a/b", normalize_backslashes("https:\\\\evil.com\\a\\b"));
+        assert_eq!("HTTP://example.com/a?b\\c#d\\", normalize_backslashes("HTTP://example.com\\a?b\\c#d\\"));
+        assert_eq!("//evil.com", normalize_backslashes("/\\evil.com"));
+        assert_eq!("mailto:a\\b", normalize_backslashes("mailto:a\\b"));
+        assert_eq!("c:\\dir", normalize_backslashes("c:\\dir"));
+        assert!(matches!(normalize_backslashes("https://example.com/"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn it_parses_with_parse_mode() {
+        let url = "\thttps:\\\\exa\nmple.com\\a b?c\\d ";
+        let strict = UriParts::parse_with(url, ParseMode::Strict);
+        assert_eq!(None, strict.authority);
+        assert_eq!(Some("\thttps"), strict.scheme.as_deref());
+
+        let browser = UriParts::parse_with(url, ParseMode::Browser);
+        assert_eq!(Some("https"), browser.scheme.as_deref());
+        assert_eq!(Some("example.com"), browser.authority.as_deref());
+        assert_eq!("/a b", browser.path);
+        assert_eq!(Some("c\\d"), browser.query.as_deref());
+
+        assert!(matches!(ParseMode::Browser.normalize("https://example.com/"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn it_looks_up_default_ports() {
+        assert_eq!(Some(80), default_port("http"));
+        assert_eq!(Some(443), default_port("WSS"));
+        assert_eq!(Some(22), default_port("ssh"));
+        assert_eq!(None, default_port("file"));
+        assert_eq!(None, default_port(""));
+    }
+
+    #[test]
+    fn it_elides_default_ports() {
+        assert_eq!("https://example.com", elide_default_port("https://example.com:443"));
+        assert_eq!("HTTP://u:p@example.com/a:80", elide_default_port("HTTP://u:p@example.com:080/a:80"));
+        assert_eq!("ws://[::1]#x", elide_default_port("ws://[::1]:80#x"));
+        assert_eq!("ssh://git@example.com/repo", elide_default_port("ssh://git@example.com:22/repo"));
+        assert_eq!("https://example.com:80/", elide_default_port("https://example.com:80/"));
+        assert_eq!("http://[::1]/", elide_default_port("http://[::1]:80/"));
+        for url in &["https://example.com/", "https://[::1]/", "http://[::1:80/", "http://[::1:80", "https://example.com:/", "https://example.com:+443/", "mailto:a@b:25", "urn:x:443", "https:443"] {
+            assert!(matches!(elide_default_port(url), Cow::Borrowed(_)), "{}", url);
+        }
+    }
+
+    #[test]
+    fn it_elides_default_ports_in_browser_mode() {
+        assert_eq!("https://example.com/", ParseMode::Browser.normalize(" https://example.com:443\\"));
+        assert_eq!("ssh://example.com:22/", ParseMode::Browser.normalize("ssh://example.com:22/"));
+        assert_eq!("https://example.com:443/", ParseMode::Strict.normalize("https://example.com:443/"));
+    }
+
+    #[test]
+    fn it_classifies_urls() {
+        for url in &["https://example.com", "HTTP:", "mailto:a@example.com", "urn:isbn:123", "git+ssh://host/repo"] {
+            assert_eq!(UrlKind::Absolute, classify(url), "{}", url);
+        }
+        for url in &["//example.com", "//", "///a"] {
+            assert_eq!(UrlKind::ProtocolRelative, classify(url), "{}", url);
+        }
+        for url in &["/", "/a:b", "/\\evil.com"] {
+            assert_eq!(UrlKind::RootRelative, classify(url), "{}", url);
+        }
+        for url in &["", "a/b", "../a", "./a:b", "?q", "1a:b", "a b:c", ":x"] {
+            assert_eq!(UrlKind::Relative, classify(url), "{}", url);
+        }
+        assert_eq!(UrlKind::Fragment, classify("#"));
+        assert_eq!(UrlKind::Fragment, classify("#a/b:c"));
+        assert_eq!(UrlKind::ProtocolRelative, classify(&ParseMode::Browser.normalize(" /\\evil.com")));
+    }
+
+    #[test]
+    fn it_validates_request_targets_successfully() {
+        for target in &["/", "/a/b;c=d?e=f/g?h", "/%C3%A9?%20", "//a:b@c"] {
+            assert_eq!(Ok(()), validate_request_target(target, RequestTargetForm::Origin), "{}", target);
+        }
+        for target in &["http://example.com/a?b", "HTTP://[::1]:8080", "urn:isbn:123"] {
+            assert_eq!(Ok(()), validate_request_target(target, RequestTargetForm::Absolute), "{}", target);
+        }
+        for target in &["example.com:443", "[::1]:8080", "127.0.0.1:1"] {
+            assert_eq!(Ok(()), validate_request_target(target, RequestTargetForm::Authority), "{}", target);
+        }
+        assert_eq!(Ok(()), validate_request_target("*", RequestTargetForm::Asterisk));
+    }
+
+    #[test]
+    fn it_validates_request_targets_unsuccessfully() {
+        let error = |component, index| Err(UriValidationError { component, index });
+        let origin = |target| validate_request_target(target, RequestTargetForm::Origin);
+        assert_eq!(error(Component::Path, 0), origin(""));
+        assert_eq!(error(Component::Path, 0), origin("a/b"));
+        assert_eq!(error(Component::Path, 0), origin("http://example.com/"));
+        assert_eq!(error(Component::Fragment, 2), origin("/a#b"));
+        assert_eq!(error(Component::Fragment, 2), origin("/a#b?c"));
+        assert_eq!(error(Component::Path, 3), origin("/a%zz"));
+        assert_eq!(error(Component::Fragment, 4), origin("/a?b#c"));
+        assert_eq!(error(Component::Path, 2), origin("/a b#c"));
+        assert_eq!(error(Component::Query, 4), origin("/a?b\\"));
+
+        let absolute = |target| validate_request_target(target, RequestTargetForm::Absolute);
+        assert_eq!(error(Component::Scheme, 0), absolute("/a"));
+        assert_eq!(error(Component::Fragment, 20), absolute("http://example.com/a#b"));
+        assert_eq!(error(Component::Path, 20), absolute("http://example.com/a b#c"));
+
+        let authority = |target| validate_request_target(target, RequestTargetForm::Authority);
+        assert_eq!(error(Component::Port, 11), authority("example.com"));
+        assert_eq!(error(Component::Port, 12), authority("example.com:"));
+        assert_eq!(error(Component::Port, 5), authority("[::1]"));
+        assert_eq!(error(Component::Userinfo, 4), authority("user@example.com:443"));
+        assert_eq!(error(Component::Host, 7), authority("example/a:443"));
+
+        let asterisk = |target| validate_request_target(target, RequestTargetForm::Asterisk);
+        assert_eq!(error(Component::Path, 0), asterisk(""));
+        assert_eq!(error(Component::Path, 0), asterisk("/*"));
+        assert_eq!(error(Component::Path, 1), asterisk("**"));
+    }
+
+    #[test]
+    fn it_encodes_userinfo() {
+        assert_eq!("user:", encode_userinfo("user", Some("")));
+        assert_eq!("%20a%3Bb%3Dc:%C3%A9%25", encode_userinfo(" a;b=c", Some("é%")));
+    }
+
+    #[test]
+    fn it_decodes_userinfo() {
+        assert_eq!(("a:b".to_string(), None), decode_userinfo("a%3Ab").unwrap());
+        assert_eq!(("".to_string(), Some("".to_string())), decode_userinfo(":").unwrap());
+        assert!(decode_userinfo("a:%zz").is_err());
+        for (user, password) in &[("j@ne", Some("p:ss/w")), ("x y", None), ("", Some("é"))] {
+            let userinfo = encode_userinfo(user, *password);
+            assert_eq!((user.to_string(), password.map(String::from)), decode_userinfo(&userinfo).unwrap());
+        }
+    }
+
+    #[test]
+    fn it_detects_dangerous_schemes() {
+        let dangerous = [
+            "javascript:alert(1)",
+            "\u{0}\u{1F} javascript:x",
+            "JAVA\nSCRIPT:x",
+            "java\u{0}script:x",
+            "java script:x",
+            "vbscript:x",
+            "LiveScript:x",
+            "javascript%3Ax",
+            "%256A%2561vascript:x",
+            "data:text/html,<script>",
+            "DATA: Text/HTML ;charset=utf-8,x",
+            "data:image/svg+xml;base64,PHN2Zz4=",
+            "data:application/xhtml+xml,x",
+        ];
+        for url in &dangerous {
+            assert!(is_dangerous_scheme(url), "{:?}", url);
+        }
+        let safe = [
+            "https://example.com/?u=javascript:x",
+            "/javascript:x",
+            "#javascript:x",
+            "javascripts:x",
+            "javascript",
+            "data:,text/html",
+            "data:text/plain,<script>",
+            "mailto:javascript@example.com",
+            "",
+        ];
+        for url in &safe {
+            assert!(!is_dangerous_scheme(url), "{:?}", url);
+        }
+    }
+
+    #[test]
+    fn it_sanitizes_schemes() {
+        assert_eq!("about:blank", sanitize_scheme(" javascript:x"));
+        assert!(matches!(sanitize_scheme("https://example.com/"), Cow::Borrowed("https://example.com/")));
+    }
+}
@@ -0,0 +1,106 @@
+use std::convert::TryFrom;
+
+use url::{ParseError, Position, Url};
+
+use super::FromUrlEncodingError;
+use query::{QueryEditError, QueryExt, QueryParams};
+use uri::UriParts;
+
+impl<'a> From<&'a Url> for UriParts {
+    fn from(url: &'a Url) -> UriParts {
+        UriParts {
+            scheme: Some(url.scheme().to_string()),
+            authority: if url.has_authority() {
+                Some(url[Position::BeforeUsername..Position::AfterPort].to_string())
+            } else {
+                None
+            },
+            path: url.path().to_string(),
+            query: url.query().map(str::to_string),
+            fragment: url.fragment().map(str::to_string),
+        }
+    }
+}
+
+impl TryFrom<UriParts> for Url {
+    type Error = ParseError;
+
+    fn try_from(parts: UriParts) -> Result<Url, ParseError> {
+        Url::parse(&parts.to_string())
+    }
+}
+
+impl<'a> TryFrom<&'a Url> for QueryParams {
+    type Error = FromUrlEncodingError;
+
+    fn try_from(url: &'a Url) -> Result<QueryParams, FromUrlEncodingError> {
+        QueryParams::parse(url.query().unwrap_or(""))
+    }
+}
+
+impl QueryExt for Url {
+    fn query_params(&self) -> Result<QueryParams, QueryEditError> {
+        QueryParams::try_from(self).map_err(|e| QueryEditError::DecodingError { error: e })
+    }
+
+    fn set_query_params(&mut self, params: &QueryParams) -> Result<(), QueryEditError> {
+        if params.is_empty() {
+            self.set_query(None);
+        } else {
+            self.set_query(Some(&params.to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use url::Url;
+
+    use query::{QueryExt, QueryParams};
+    use uri::UriParts;
+
+    #[test]
+    fn it_converts_url_to_uri_parts() {
+        let url = Url::parse("https://user:pw@example.com:8080/a%20b?q=1#top").unwrap();
+        let parts = UriParts::from(&url);
+        assert_eq!(Some("https".to_string()), parts.scheme);
+        assert_eq!(Some("user:pw@example.com:8080".to_string()), parts.authority);
+        assert_eq!("/a%20b", parts.path);
+        assert_eq!(Some("q=1".to_string()), parts.query);
+        assert_eq!(Some("top".to_string()), parts.fragment);
+        assert_eq!(url.as_str(), parts.to_string());
+
+        let parts = UriParts::from(&Url::parse("mailto:a@example.com").unwrap());
+        assert_eq!(None, parts.authority);
+        assert_eq!("a@example.com", parts.path);
+    }
+
+    #[test]
+    fn it_converts_uri_parts_to_url() {
+        let parts = UriParts::parse("HTTPS://Example.com/a?b#c");
+        assert_eq!("https://example.com/a?b#c", Url::try_from(parts).unwrap().as_str());
+        assert!(Url::try_from(UriParts::parse("/relative")).is_err());
+    }
+
+    #[test]
+    fn it_converts_url_to_query_params() {
+        let url = Url::parse("https://example.com/?q=this+that&page=2").unwrap();
+        let params = QueryParams::try_from(&url).unwrap();
+        assert_eq!(Some("this that"), params.get("q"));
+        assert!(QueryParams::try_from(&Url::parse("https://example.com/?q=%2").unwrap()).is_err());
+    }
+
+    #[test]
+    fn it_edits_url_query_params() {
+        let mut url = Url::parse("https://example.com/search?q=rust#results").unwrap();
+        url.append_query_param("tag", "a&b").unwrap();
+        url.set_query_param("q", "this that").unwrap();
+        assert_eq!("https://example.com/search?q=this%20that&tag=a%26b#results", url.as_str());
+
+        url.set_query_params(&QueryParams::new()).unwrap();
+        assert_eq!("https://example.com/search#results", url.as_str());
+    }
+}
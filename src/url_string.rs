@@ -0,0 +1,311 @@
+use std::borrow::Cow;
+
+use super::{encode, hex_value, FromUrlEncodingError};
+use pattern::glob_matches;
+use query::{decode_key, decode_query_part};
+use uri::query_range;
+
+// Parameters that only track where a visitor came from.
+const TRACKING_PARAMS: &[&str] = &[
+    "utm_*", "fbclid", "gclid", "gclsrc", "dclid", "wbraid", "gbraid", "msclkid", "yclid", "twclid",
+    "ttclid", "igshid", "li_fat_id", "mc_cid", "mc_eid", "_ga", "_gl", "_hsenc", "_hsmi", "mkt_tok",
+];
+
+/// Matches query parameter keys against a list of patterns, where `*`
+/// matches any run of characters, e.g. `utm_*` or `*_id`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParamMatcher {
+    patterns: Vec<String>,
+}
+
+impl ParamMatcher {
+    /// A matcher without patterns, which matches nothing.
+    pub fn new() -> ParamMatcher {
+        ParamMatcher { patterns: Vec::new() }
+    }
+
+    /// A matcher for common tracking parameters: `utm_*`, `fbclid`, `gclid`,
+    /// `msclkid` and the like.
+    pub fn tracking() -> ParamMatcher {
+        TRACKING_PARAMS.iter().fold(ParamMatcher::new(), |matcher, pattern| matcher.pattern(pattern))
+    }
+
+    /// Also matches keys matching `pattern`.
+    pub fn pattern(mut self, pattern: &str) -> ParamMatcher {
+        self.patterns.push(pattern.to_string());
+        self
+    }
+
+    /// Returns whether the decoded `key` matches any of the patterns.
+    pub fn matches(&self, key: &str) -> bool {
+        self.patterns.iter().any(|pattern| glob_matches(pattern, key))
+    }
+}
+
+impl Default for ParamMatcher {
+    fn default() -> ParamMatcher {
+        ParamMatcher::tracking()
+    }
+}
+
+/// Finds the first query parameter of `url` with the decoded key `key` and
+/// decodes its value, treating `+` as a space.
+///
+/// The query is scanned in place, without building a list of parameters:
+/// keys are compared while decoding them and the value is only copied if it
+/// has to be decoded. Returns `None` if there is no such parameter.
+pub fn get_param<'a>(url: &'a str, key: &str) -> Option<Result<Cow<'a, str>, FromUrlEncodingError>> {
+    let (query_start, query_end) = query_range(url)?;
+    let value = url[query_start..query_end].split('&').find_map(|pair| {
+        let (encoded_key, value) = match pair.find('=') {
+            Some(idx) => (&pair[..idx], &pair[idx + 1..]),
+            None => (pair, ""),
+        };
+        if !pair.is_empty() && key_matches(encoded_key, key) {
+            Some(value)
+        } else {
+            None
+        }
+    })?;
+    if value.contains(['%', '+']) {
+        Some(decode_query_part(value).map(Cow::Owned))
+    } else {
+        Some(Ok(Cow::Borrowed(value)))
+    }
+}
+
+// Compares an encoded query key with a decoded one, decoding on the fly.
+fn key_matches(encoded: &str, key: &str) -> bool {
+    let encoded = encoded.as_bytes();
+    let mut expected = key.bytes();
+    let mut i = 0;
+    while i < encoded.len() {
+        let high = encoded.get(i + 1).and_then(|&b| hex_value(b));
+        let low = encoded.get(i + 2).and_then(|&b| hex_value(b));
+        let b = match (encoded[i], high, low) {
+            (b'%', Some(high), Some(low)) => {
+                i += 3;
+                high << 4 | low
+            },
+            (b'+', _, _) => {
+                i += 1;
+                b' '
+            },
+            (b, _, _) => {
+                i += 1;
+                b
+            }
+        };
+        if expected.next() != Some(b) {
+            return false;
+        }
+    }
+    expected.next().is_none()
+}
+
+/// Removes every query parameter of `url` whose decoded key matches
+/// `matcher`, e.g. `ParamMatcher::tracking()` to scrub tracking parameters.
+///
+/// Everything else, including the other parameters and the fragment, is kept
+/// byte for byte. If no parameter is left, the `?` is removed too.
+pub fn strip_params(url: &str, matcher: &ParamMatcher) -> String {
+    rewrite_pairs(url, |pair, key| {
+        if matcher.matches(&decode_key(key)) {
+            None
+        } else {
+            Some(Cow::Borrowed(pair))
+        }
+    })
+}
+
+/// Sets the query parameter `key` of `url` to `value`, both encoded with
+/// `encode`, like `QueryParams::set`: the first parameter with that decoded
+/// key gets the new value and any later ones are removed. If there is none,
+/// the parameter is appended like with `append_params`.
+///
+/// Everything else, including the other parameters and the fragment, is kept
+/// byte for byte.
+pub fn set_param(url: &str, key: &str, value: &str) -> String {
+    let mut found = false;
+    let rewritten = rewrite_pairs(url, |pair, encoded_key| {
+        if !key_matches(encoded_key, key) {
+            Some(Cow::Borrowed(pair))
+        } else if found {
+            None
+        } else {
+            found = true;
+            Some(Cow::Owned(format!("{}={}", encoded_key, encode(value))))
+        }
+    });
+    if found {
+        rewritten
+    } else {
+        append_params(url, &[(key, value)])
+    }
+}
+
+/// Removes every query parameter of `url` with the decoded key `key`,
+/// keeping everything else byte for byte. If no parameter is left, the `?`
+/// is removed too.
+pub fn remove_param(url: &str, key: &str) -> String {
+    rewrite_pairs(url, |pair, encoded_key| {
+        if key_matches(encoded_key, key) {
+            None
+        } else {
+            Some(Cow::Borrowed(pair))
+        }
+    })
+}
+
+// Replaces each non-empty pair of the query of `url` with what `rewrite`
+// returns for the pair and its encoded key, removing it on `None`. The rest
+// of `url` is kept, except for the '?' of a query left empty.
+fn rewrite_pairs<'a, F>(url: &'a str, mut rewrite: F) -> String
+where
+    F: FnMut(&'a str, &'a str) -> Option<Cow<'a, str>>,
+{
+    let (query_start, query_end) = match query_range(url) {
+        Some(range) => range,
+        None => return url.to_string(),
+    };
+    let mut removed = false;
+    let mut kept: Vec<Cow<str>> = Vec::new();
+    for pair in url[query_start..query_end].split('&') {
+        if pair.is_empty() {
+            kept.push(Cow::Borrowed(pair));
+            continue;
+        }
+        match rewrite(pair, pair.split('=').next().unwrap_or("")) {
+            Some(pair) => kept.push(pair),
+            None => removed = true,
+        }
+    }
+
+    let mut rewritten = String::with_capacity(url.len());
+    if removed && kept.iter().all(|pair| pair.is_empty()) {
+        // Also drop the '?'
+        rewritten.push_str(&url[..query_start - 1]);
+    } else {
+        rewritten.push_str(&url[..query_start]);
+        rewritten.push_str(&kept.join("&"));
+    }
+    rewritten.push_str(&url[query_end..]);
+    rewritten
+}
+
+/// Adds encoded `key=value` parameters to the query of `url`, which may be
+/// any absolute or relative URL.
+///
+/// The parameters are joined to an existing query with `&`, or start a new
+/// one with `?`, and are inserted before the fragment, if any. The rest of
+/// `url` is kept byte for byte.
+///
+/// ```
+/// use urlencoding::append_params;
+///
+/// assert_eq!("/search?q=rust&page=2#top", append_params("/search?q=rust#top", &[("page", "2")]));
+/// assert_eq!("/search?q=a%26b", append_params("/search", &[("q", "a&b")]));
+/// ```
+pub fn append_params(url: &str, params: &[(&str, &str)]) -> String {
+    let end = url.find('#').unwrap_or(url.len());
+    let mut appended = String::with_capacity(url.len() + params.len() * 16);
+    appended.push_str(&url[..end]);
+    for (i, &(key, value)) in params.iter().enumerate() {
+        if i > 0 {
+            appended.push('&');
+        } else {
+            match url[..end].find('?') {
+                None => appended.push('?'),
+                Some(idx) if idx + 1 == end || url[..end].ends_with('&') => {},
+                Some(_) => appended.push('&'),
+            }
+        }
+        appended.push_str(&encode(key));
+        appended.push('=');
+        appended.push_str(&encode(value));
+    }
+    appended.push_str(&url[end..]);
+    appended
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::append_params;
+    use super::get_param;
+    use super::remove_param;
+    use super::set_param;
+    use super::strip_params;
+    use super::ParamMatcher;
+
+    #[test]
+    fn it_appends_params_successfully() {
+        assert_eq!("https://example.com/?a=1&b=x%20y", append_params("https://example.com/", &[("a", "1"), ("b", "x y")]));
+        assert_eq!("/p?a=1&b=2", append_params("/p?a=1", &[("b", "2")]));
+        assert_eq!("/p?b=2", append_params("/p?", &[("b", "2")]));
+        assert_eq!("/p?a=1&b=2", append_params("/p?a=1&", &[("b", "2")]));
+    }
+
+    #[test]
+    fn it_appends_params_before_fragment() {
+        assert_eq!("/p?a=1#frag?x=1", append_params("/p#frag?x=1", &[("a", "1")]));
+        assert_eq!("/p?x&a=%23#f", append_params("/p?x#f", &[("a", "#")]));
+        assert_eq!("/p#f", append_params("/p#f", &[]));
+    }
+
+    #[test]
+    fn it_gets_param_successfully() {
+        let url = "https://example.com/search?q=a+b%21&page=2&flag&pass%77ord=x#page=9";
+        assert_eq!("a b!", get_param(url, "q").unwrap().unwrap());
+        assert_eq!("", get_param(url, "flag").unwrap().unwrap());
+        assert_eq!("x", get_param(url, "password").unwrap().unwrap());
+        match get_param(url, "page") {
+            Some(Ok(Cow::Borrowed("2"))) => {},
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn it_gets_missing_param() {
+        assert!(get_param("/a?pages=1&pag=2#page=3", "page").is_none());
+        assert!(get_param("/a", "page").is_none());
+        assert!(get_param("/a?a%2=1", "a%2").is_some());
+        assert!(get_param("/a?a=%2", "a").unwrap().is_err());
+    }
+
+    #[test]
+    fn it_strips_tracking_params() {
+        let url = "https://example.com/a?id=7&utm_source=mail&utm%5Fmedium=x&fbclid=abc&q=a%20b#utm_source=frag";
+        assert_eq!("https://example.com/a?id=7&q=a%20b#utm_source=frag", strip_params(url, &ParamMatcher::tracking()));
+        assert_eq!("/a#f", strip_params("/a?gclid=1&utm_term=x#f", &ParamMatcher::default()));
+        assert_eq!("/a?utm=1", strip_params("/a?utm=1", &ParamMatcher::default()));
+    }
+
+    #[test]
+    fn it_strips_custom_params() {
+        let matcher = ParamMatcher::new().pattern("session_*").pattern("*_token").pattern("x*y*z");
+        assert_eq!("/a?b=1&&token=2", strip_params("/a?session_id=1&b=1&&access_token=3&token=2&x_y_z", &matcher));
+        assert_eq!("/a?", strip_params("/a?", &matcher));
+        assert_eq!("/a", strip_params("/a", &matcher));
+        assert!(!ParamMatcher::new().matches("utm_source"));
+    }
+
+    #[test]
+    fn it_sets_param_successfully() {
+        let url = "/a?page=1&q=a+b&p%61ge=2&x#top";
+        assert_eq!("/a?page=3&q=a+b&x#top", set_param(url, "page", "3"));
+        assert_eq!("/a?page=1&q=a%26b&p%61ge=2&x#top", set_param(url, "q", "a&b"));
+        assert_eq!("/a?page=1&q=a+b&p%61ge=2&x&new=%20#top", set_param(url, "new", " "));
+        assert_eq!("/a?new=1", set_param("/a", "new", "1"));
+    }
+
+    #[test]
+    fn it_removes_param_successfully() {
+        let url = "/a?page=1&q=a+b&&p%61ge=2#page=3";
+        assert_eq!("/a?q=a+b&#page=3", remove_param(url, "page"));
+        assert_eq!("/a#f", remove_param("/a?page=1&page=2#f", "page"));
+        assert_eq!("/a?&", remove_param("/a?&", "page"));
+        assert_eq!("/a#?page=1", remove_param("/a#?page=1", "page"));
+    }
+}
@@ -0,0 +1,110 @@
+#![cfg(feature = "derive")]
+
+extern crate urlencoding;
+
+use urlencoding::{FromQuery, FromQueryError, ToQuery};
+
+#[derive(Debug, PartialEq, FromQuery)]
+struct Search {
+    q: String,
+    page: Option<u32>,
+    #[query(rename = "tag")]
+    tags: Vec<String>,
+    #[query(default)]
+    limit: u8,
+    #[query(rename = "sort-by", default)]
+    sort_by: String,
+    r#type: char,
+}
+
+#[test]
+fn it_derives_from_query_successfully() {
+    let search = Search::from_query("q=this+that&page=2&tag=a&tag=b%20c&limit=10&sort-by=date&type=x").unwrap();
+    let expected = Search {
+        q: "this that".to_string(),
+        page: Some(2),
+        tags: vec!["a".to_string(), "b c".to_string()],
+        limit: 10,
+        sort_by: "date".to_string(),
+        r#type: 'x',
+    };
+    assert_eq!(expected, search);
+}
+
+#[test]
+fn it_derives_from_query_with_missing_optional_fields() {
+    let search = Search::from_query("q=rust&type=y").unwrap();
+    let expected = Search {
+        q: "rust".to_string(),
+        page: None,
+        tags: Vec::new(),
+        limit: 0,
+        sort_by: String::new(),
+        r#type: 'y',
+    };
+    assert_eq!(expected, search);
+}
+
+#[test]
+fn it_derives_from_query_unsuccessfully() {
+    match Search::from_query("type=x").unwrap_err() {
+        FromQueryError::MissingParamError { key } => assert_eq!("q", key),
+        _ => panic!()
+    }
+    match Search::from_query("q=rust&type=x&page=two").unwrap_err() {
+        FromQueryError::InvalidParamError { key, value, .. } => {
+            assert_eq!("page", key);
+            assert_eq!("two", value);
+        },
+        _ => panic!()
+    }
+    match Search::from_query("q=%zz&type=x").unwrap_err() {
+        FromQueryError::DecodingError { .. } => {},
+        _ => panic!()
+    }
+}
+
+#[derive(Debug, PartialEq, FromQuery, ToQuery)]
+struct Request {
+    #[query(rename = "q")]
+    query: String,
+    page: Option<u32>,
+    tag: Vec<String>,
+    #[query(skip)]
+    cached: bool,
+}
+
+#[test]
+fn it_derives_to_query_successfully() {
+    let request = Request {
+        query: "this that&".to_string(),
+        page: Some(3),
+        tag: vec!["a".to_string(), "b".to_string()],
+        cached: true,
+    };
+    assert_eq!("q=this%20that%26&page=3&tag=a&tag=b", request.to_query());
+}
+
+#[test]
+fn it_derives_to_query_leaving_out_missing_fields() {
+    let request = Request {
+        query: String::new(),
+        page: None,
+        tag: Vec::new(),
+        cached: false,
+    };
+    assert_eq!("q=", request.to_query());
+}
+
+#[test]
+fn it_round_trips_derived_query() {
+    let request = Request {
+        query: "€ 100%".to_string(),
+        page: None,
+        tag: vec!["x y".to_string()],
+        cached: true,
+    };
+    let expected = Request { cached: false, ..request };
+    let query = expected.to_query();
+    assert_eq!(expected, Request::from_query(&query).unwrap());
+}
@@ -0,0 +1,195 @@
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use syn::ext::IdentExt;
+use syn::{Data, DeriveInput, Error, Field, Fields, GenericArgument, LitStr, PathArguments, Type};
+
+/// Derives `urlencoding::FromQuery` for a struct with named fields.
+///
+/// Each field is read from the query parameter of the same name, parsed with
+/// `FromStr`. `Option<T>` fields may be missing, `Vec<T>` fields collect every
+/// value of a repeated key, and fields accept these attributes:
+///
+/// - `#[query(rename = "name")]` reads the field from another key
+/// - `#[query(default)]` uses `Default::default()` when the key is missing
+/// - `#[query(skip)]` never reads the field and sets it to `Default::default()`
+#[proc_macro_derive(FromQuery, attributes(query))]
+pub fn derive_from_query(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    expand_from_query(&input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+fn expand_from_query(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let mut initializers = Vec::new();
+    for field in named_fields(input)? {
+        let ident = &field.ident;
+        let attrs = FieldAttrs::parse(field)?;
+        let key = attrs.key(field);
+        let value = if attrs.skip {
+            quote! { ::std::default::Default::default() }
+        } else if let Some(inner) = generic_argument(&field.ty, "Option") {
+            quote! {
+                match params.get_parsed::<#inner>(#key) {
+                    ::std::option::Option::Some(value) => ::std::option::Option::Some(value?),
+                    ::std::option::Option::None => ::std::option::Option::None,
+                }
+            }
+        } else if let Some(inner) = generic_argument(&field.ty, "Vec") {
+            quote! { params.get_parsed_all::<#inner>(#key)? }
+        } else if attrs.default {
+            let ty = &field.ty;
+            quote! {
+                match params.get_parsed::<#ty>(#key) {
+                    ::std::option::Option::Some(value) => value?,
+                    ::std::option::Option::None => ::std::default::Default::default(),
+                }
+            }
+        } else {
+            let ty = &field.ty;
+            quote! { params.get_required::<#ty>(#key)? }
+        };
+        initializers.push(quote! { #ident: #value });
+    }
+
+    Ok(quote! {
+        impl #impl_generics ::urlencoding::FromQuery for #name #ty_generics #where_clause {
+            fn from_query_params(
+                params: &::urlencoding::QueryParams,
+            ) -> ::std::result::Result<Self, ::urlencoding::FromQueryError> {
+                ::std::result::Result::Ok(#name {
+                    #(#initializers,)*
+                })
+            }
+        }
+    })
+}
+
+/// Derives `urlencoding::ToQuery` for a struct with named fields.
+///
+/// Each field is written as a parameter of the same name using its `Display`
+/// implementation. `None` fields are left out, `Vec<T>` fields write one
+/// parameter per element, and fields accept these attributes:
+///
+/// - `#[query(rename = "name")]` writes the field under another key
+/// - `#[query(skip)]` never writes the field
+#[proc_macro_derive(ToQuery, attributes(query))]
+pub fn derive_to_query(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    expand_to_query(&input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+fn expand_to_query(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let mut appends = Vec::new();
+    for field in named_fields(input)? {
+        let ident = &field.ident;
+        let attrs = FieldAttrs::parse(field)?;
+        if attrs.skip {
+            continue;
+        }
+        let key = attrs.key(field);
+        let append = if generic_argument(&field.ty, "Option").is_some() {
+            quote! {
+                if let ::std::option::Option::Some(ref value) = self.#ident {
+                    params.append(#key, &::std::string::ToString::to_string(value));
+                }
+            }
+        } else if generic_argument(&field.ty, "Vec").is_some() {
+            quote! {
+                for value in &self.#ident {
+                    params.append(#key, &::std::string::ToString::to_string(value));
+                }
+            }
+        } else {
+            quote! { params.append(#key, &::std::string::ToString::to_string(&self.#ident)); }
+        };
+        appends.push(append);
+    }
+
+    Ok(quote! {
+        impl #impl_generics ::urlencoding::ToQuery for #name #ty_generics #where_clause {
+            fn to_query_params(&self) -> ::urlencoding::QueryParams {
+                let mut params = ::urlencoding::QueryParams::new();
+                #(#appends)*
+                params
+            }
+        }
+    })
+}
+
+fn named_fields(input: &DeriveInput) -> Result<Vec<&Field>, Error> {
+    match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => Ok(fields.named.iter().collect()),
+            _ => Err(Error::new_spanned(input, "only structs with named fields are supported")),
+        },
+        _ => Err(Error::new_spanned(input, "only structs with named fields are supported")),
+    }
+}
+
+// Returns `T` if `ty` is written as `wrapper<T>`, e.g. `Option<u32>`.
+fn generic_argument<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    let path = match *ty {
+        Type::Path(ref ty) if ty.qself.is_none() => &ty.path,
+        _ => return None,
+    };
+    let segment = path.segments.last()?;
+    if segment.ident != wrapper {
+        return None;
+    }
+    match segment.arguments {
+        PathArguments::AngleBracketed(ref args) if args.args.len() == 1 => match args.args[0] {
+            GenericArgument::Type(ref inner) => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[derive(Default)]
+struct FieldAttrs {
+    rename: Option<LitStr>,
+    default: bool,
+    skip: bool,
+}
+
+impl FieldAttrs {
+    fn parse(field: &Field) -> Result<FieldAttrs, Error> {
+        let mut attrs = FieldAttrs::default();
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("query")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    attrs.rename = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("default") {
+                    attrs.default = true;
+                    Ok(())
+                } else if meta.path.is_ident("skip") {
+                    attrs.skip = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported query attribute"))
+                }
+            })?;
+        }
+        Ok(attrs)
+    }
+
+    fn key(&self, field: &Field) -> LitStr {
+        match self.rename {
+            Some(ref rename) => rename.clone(),
+            None => {
+                let ident = field.ident.as_ref().expect("named field");
+                LitStr::new(&ident.unraw().to_string(), ident.span())
+            }
+        }
+    }
+}